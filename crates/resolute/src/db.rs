@@ -5,8 +5,12 @@ use log::{info, warn};
 use native_db::{db_type::Error as NativeDbError, Builder, Database, Models};
 use once_cell::sync::Lazy;
 use redb::{DatabaseError, StorageError};
+use semver::VersionReq;
 
-use crate::{models::ResoluteMod, Error, Result};
+use crate::{
+	models::{ModProfile, ResoluteMod, Snapshot},
+	Error, Result,
+};
 
 /// Wrapper for interacting with a Resolute database
 #[allow(missing_debug_implementations)]
@@ -89,6 +93,55 @@ impl ResoluteDatabase<'_> {
 		Ok(())
 	}
 
+	/// Sets or clears the version pin of a mod stored in the database
+	pub fn set_mod_pin(&self, id: impl AsRef<str>, pin: Option<VersionReq>) -> Result<()> {
+		// Find the item in the database
+		let id = id.as_ref();
+		let rw = self.db.rw_transaction()?;
+		let mut rmod: ResoluteMod = rw
+			.get()
+			.primary(id)?
+			.ok_or_else(|| Error::ItemNotFound(id.to_owned()))?;
+
+		// Update its pin
+		rmod.pin = pin;
+		let mod_name = rmod.to_string();
+		let pin_text = rmod.pin.as_ref().map(ToString::to_string);
+		rw.upsert(rmod)?;
+		rw.commit()?;
+
+		if let Some(pin) = pin_text {
+			info!("Pinned mod {} to {} in the database", mod_name, pin);
+		} else {
+			info!("Unpinned mod {} in the database", mod_name);
+		}
+		Ok(())
+	}
+
+	/// Sets whether a mod stored in the database is active (enabled)
+	pub fn set_mod_active(&self, id: impl AsRef<str>, active: bool) -> Result<()> {
+		// Find the item in the database
+		let id = id.as_ref();
+		let rw = self.db.rw_transaction()?;
+		let mut rmod: ResoluteMod = rw
+			.get()
+			.primary(id)?
+			.ok_or_else(|| Error::ItemNotFound(id.to_owned()))?;
+
+		// Update its active state
+		rmod.active = active;
+		let mod_name = rmod.to_string();
+		rw.upsert(rmod)?;
+		rw.commit()?;
+
+		info!(
+			"Marked mod {} as {} in the database",
+			mod_name,
+			if active { "active" } else { "inactive" }
+		);
+		Ok(())
+	}
+
 	/// Removes a mod from the database
 	pub fn remove_mod(&self, rmod: ResoluteMod) -> Result<()> {
 		let mod_name = rmod.to_string();
@@ -115,6 +168,114 @@ impl ResoluteDatabase<'_> {
 		// Remove it
 		self.remove_mod(rmod)
 	}
+
+	/// Retrieves all mod profiles stored in the database
+	pub fn get_profiles(&self) -> Result<Vec<ModProfile>> {
+		let read = self.db.r_transaction()?;
+		let profiles = read.scan().primary()?.all()?.collect::<result::Result<_, _>>()?;
+		Ok(profiles)
+	}
+
+	/// Retrieves a single mod profile from the database by its name
+	pub fn get_profile(&self, name: impl AsRef<str>) -> Result<Option<ModProfile>> {
+		let read = self.db.r_transaction()?;
+		let profile = read.get().primary(name.as_ref())?;
+		Ok(profile)
+	}
+
+	/// Stores a mod profile in the database (overwrites any existing profile with the same name)
+	pub fn store_profile(&self, profile: ModProfile) -> Result<()> {
+		let profile_name = profile.to_string();
+
+		let rw = self.db.rw_transaction()?;
+		rw.upsert(profile)?;
+		rw.commit()?;
+
+		info!("Stored mod profile {} in the database", profile_name);
+		Ok(())
+	}
+
+	/// Removes a mod profile from the database by its name
+	pub fn remove_profile(&self, name: impl AsRef<str>) -> Result<()> {
+		// Find the item in the database
+		let name = name.as_ref();
+		let rw = self.db.rw_transaction()?;
+		let profile: ModProfile = rw
+			.get()
+			.primary(name)?
+			.ok_or_else(|| Error::ItemNotFound(name.to_owned()))?;
+
+		// Remove it
+		rw.remove(profile)?;
+		rw.commit()?;
+
+		info!("Removed mod profile {} from the database", name);
+		Ok(())
+	}
+
+	/// Stores and removes multiple mods in a single transaction, so that either all of the changes are made or none of
+	/// them are. Pins are only ever changed explicitly, so any pin already stored for a mod is kept.
+	pub fn commit_mod_changes(&self, store: Vec<ResoluteMod>, remove: &[String]) -> Result<()> {
+		let (stored, removed) = (store.len(), remove.len());
+		let rw = self.db.rw_transaction()?;
+
+		for mut rmod in store {
+			let existing: Option<ResoluteMod> = rw.get().primary(rmod.id.as_str())?;
+			rmod.pin = existing.and_then(|existing| existing.pin);
+			rw.upsert(rmod)?;
+		}
+
+		for id in remove {
+			let rmod: ResoluteMod = rw
+				.get()
+				.primary(id.as_str())?
+				.ok_or_else(|| Error::ItemNotFound(id.clone()))?;
+			rw.remove(rmod)?;
+		}
+
+		rw.commit()?;
+
+		info!("Stored {} mods and removed {} mods in the database", stored, removed);
+		Ok(())
+	}
+
+	/// Retrieves all snapshots stored in the database, oldest first
+	pub fn get_snapshots(&self) -> Result<Vec<Snapshot>> {
+		let read = self.db.r_transaction()?;
+		let snapshots = read.scan().primary()?.all()?.collect::<result::Result<_, _>>()?;
+		Ok(snapshots)
+	}
+
+	/// Retrieves a single snapshot from the database by its ID
+	pub fn get_snapshot(&self, id: u64) -> Result<Option<Snapshot>> {
+		let read = self.db.r_transaction()?;
+		let snapshot = read.get().primary(id)?;
+		Ok(snapshot)
+	}
+
+	/// Stores a snapshot in the database
+	pub fn store_snapshot(&self, snapshot: Snapshot) -> Result<()> {
+		let snapshot_name = snapshot.to_string();
+
+		let rw = self.db.rw_transaction()?;
+		rw.upsert(snapshot)?;
+		rw.commit()?;
+
+		info!("Stored snapshot {} in the database", snapshot_name);
+		Ok(())
+	}
+
+	/// Removes a snapshot from the database
+	pub fn remove_snapshot(&self, snapshot: Snapshot) -> Result<()> {
+		let snapshot_name = snapshot.to_string();
+
+		let rw = self.db.rw_transaction()?;
+		rw.remove(snapshot)?;
+		rw.commit()?;
+
+		info!("Removed snapshot {} from the database", snapshot_name);
+		Ok(())
+	}
 }
 
 /// Models that a [`ResoluteDatabase`] interacts with
@@ -126,12 +287,167 @@ pub static MODELS: Lazy<Models> = Lazy::new(|| {
 	models
 		.define::<ResoluteMod>()
 		.expect("Unable to define current ResoluteMod model");
+	models
+		.define::<ModProfile>()
+		.expect("Unable to define current ModProfile model");
+	models
+		.define::<Snapshot>()
+		.expect("Unable to define current Snapshot model");
 
 	// Legacy models
 	#[cfg(feature = "models_v1")]
 	models
 		.define::<crate::models::v1::ResoluteMod>()
 		.expect("Unable to define v1 ResoluteMod model");
+	#[cfg(feature = "models_v2")]
+	models
+		.define::<crate::models::v2::ResoluteMod>()
+		.expect("Unable to define v2 ResoluteMod model");
 
 	models
 });
+
+#[cfg(test)]
+mod tests {
+	use std::{collections::HashMap, env, fs, path::PathBuf, process};
+
+	#[cfg(feature = "models_v1")]
+	use native_db::{Builder, Models};
+	use semver::{Version, VersionReq};
+
+	use super::ResoluteDatabase;
+	#[cfg(feature = "models_v1")]
+	use crate::models::{v1, v2};
+	use crate::models::{InstallReason, ResoluteMod};
+
+	/// Gets a path for a temporary database that doesn't exist yet
+	fn temp_db_path(name: &str) -> PathBuf {
+		let path = env::temp_dir().join(format!("resolute-test-{}-{name}.redb", process::id()));
+		let _ = fs::remove_file(&path);
+		path
+	}
+
+	fn current_mod(id: &str, installed_version: Option<Version>) -> ResoluteMod {
+		ResoluteMod {
+			id: id.to_owned(),
+			name: id.to_owned(),
+			description: String::new(),
+			category: "Libraries".to_owned(),
+			authors: Vec::new(),
+			source_location: None,
+			website: None,
+			tags: None,
+			flags: None,
+			platforms: None,
+			versions: HashMap::new(),
+			active: installed_version.is_some(),
+			install_reason: installed_version.as_ref().map(|_| InstallReason::Explicit),
+			installed_version,
+			pin: None,
+		}
+	}
+
+	#[cfg(feature = "models_v1")]
+	fn v1_mod(id: &str, installed_version: Option<Version>) -> v1::ResoluteMod {
+		v1::ResoluteMod {
+			id: id.to_owned(),
+			name: id.to_owned(),
+			description: String::new(),
+			category: "Libraries".to_owned(),
+			authors: Vec::new(),
+			source_location: None,
+			website: None,
+			tags: None,
+			flags: None,
+			platforms: None,
+			versions: HashMap::new(),
+			installed_version,
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "models_v1")]
+	fn migrates_v1_mods() {
+		let path = temp_db_path("migrate-v1");
+		{
+			let mut models = Models::new();
+			models.define::<v1::ResoluteMod>().expect("define v1 model");
+			let db = Builder::new().create(&models, &path).expect("create v1 database");
+			let rw = db.rw_transaction().expect("open transaction");
+			rw.insert(v1_mod("installed", Some(Version::new(1, 2, 3))))
+				.expect("insert mod");
+			rw.insert(v1_mod("available", None)).expect("insert mod");
+			rw.commit().expect("commit transaction");
+		}
+
+		let db = ResoluteDatabase::open(&path).expect("open and migrate database");
+		let installed = db.get_mod("installed").expect("get mod").expect("mod exists");
+		assert_eq!(installed.installed_version, Some(Version::new(1, 2, 3)));
+		assert_eq!(installed.install_reason, Some(InstallReason::Explicit));
+		assert!(installed.active);
+		assert!(installed.pin.is_none());
+
+		let available = db.get_mod("available").expect("get mod").expect("mod exists");
+		assert!(available.installed_version.is_none());
+		assert!(available.install_reason.is_none());
+		assert!(!available.active);
+
+		drop(db);
+		fs::remove_file(&path).expect("remove database");
+	}
+
+	#[test]
+	#[cfg(feature = "models_v1")]
+	fn migrates_v2_mods() {
+		let path = temp_db_path("migrate-v2");
+		{
+			let mut models = Models::new();
+			models.define::<v2::ResoluteMod>().expect("define v2 model");
+			let db = Builder::new().create(&models, &path).expect("create v2 database");
+			let rw = db.rw_transaction().expect("open transaction");
+			let mut disabled: v2::ResoluteMod = v1_mod("disabled", Some(Version::new(0, 4, 0))).into();
+			disabled.active = false;
+			rw.insert(disabled).expect("insert mod");
+			rw.commit().expect("commit transaction");
+		}
+
+		let db = ResoluteDatabase::open(&path).expect("open and migrate database");
+		let disabled = db.get_mod("disabled").expect("get mod").expect("mod exists");
+		assert_eq!(disabled.installed_version, Some(Version::new(0, 4, 0)));
+		assert_eq!(disabled.install_reason, Some(InstallReason::Explicit));
+		assert!(!disabled.active);
+		assert!(disabled.pin.is_none());
+
+		drop(db);
+		fs::remove_file(&path).expect("remove database");
+	}
+
+	#[test]
+	fn keeps_pins_across_mod_changes() {
+		let path = temp_db_path("pins");
+		let db = ResoluteDatabase::open(&path).expect("open database");
+		db.store_mod(current_mod("pinned", Some(Version::new(1, 0, 0))))
+			.expect("store mod");
+		let pin = VersionReq::parse("~1.0").expect("parse pin");
+		db.set_mod_pin("pinned", Some(pin.clone())).expect("pin mod");
+
+		// Storing a new version of the mod doesn't touch the pin, even though the stored mod doesn't have one
+		db.commit_mod_changes(vec![current_mod("pinned", Some(Version::new(1, 0, 1)))], &[])
+			.expect("commit changes");
+		let pinned = db.get_mod("pinned").expect("get mod").expect("mod exists");
+		assert_eq!(pinned.installed_version, Some(Version::new(1, 0, 1)));
+		assert_eq!(pinned.pin, Some(pin));
+
+		// Unpinning sticks across later changes as well
+		db.set_mod_pin("pinned", None).expect("unpin mod");
+		let mut repinned = current_mod("pinned", Some(Version::new(1, 0, 2)));
+		repinned.pin = Some(VersionReq::parse("=1.0.2").expect("parse pin"));
+		db.commit_mod_changes(vec![repinned], &[]).expect("commit changes");
+		let unpinned = db.get_mod("pinned").expect("get mod").expect("mod exists");
+		assert_eq!(unpinned.installed_version, Some(Version::new(1, 0, 2)));
+		assert!(unpinned.pin.is_none());
+
+		drop(db);
+		fs::remove_file(&path).expect("remove database");
+	}
+}
@@ -1,16 +1,16 @@
 use std::{
 	collections::HashMap,
 	ffi::OsString,
-	fs, io,
+	fs,
 	path::{Path, PathBuf},
 };
 
 use log::{debug, error, trace};
-use sha2::{Digest, Sha256};
 use steamlocate::SteamDir;
 
 use crate::{
-	models::{ModVersion, ResoluteMod, ResoluteModMap},
+	manager,
+	models::{InstallReason, ModVersion, ResoluteMod, ResoluteModMap},
 	Result,
 };
 
@@ -91,25 +91,15 @@ pub fn mods_by_checksum(base_path: impl AsRef<Path>, mods: &ResoluteModMap) -> R
 					None => {}
 				};
 
-				// Open the file - if we're unable to, add that fact to the checksum cache
-				let mut file = match fs::File::open(&path) {
-					Ok(file) => file,
+				// Calculate the file's checksum - if we're unable to, add that fact to the checksum cache
+				let hash = match manager::hash_file_blocking(&path) {
+					Ok(hash) => hash,
 					Err(err) => {
-						trace!("Artifact file {} can't be opened: {}", artifact, err);
+						trace!("Artifact file {} can't be hashed: {}", artifact, err);
 						checksums.insert(path, None);
 						continue 'versions;
 					}
 				};
-
-				// Calculate the file's checksum
-				let mut hasher = Sha256::new();
-				let hash = match io::copy(&mut file, &mut hasher) {
-					Ok(_bytes) => format!("{:x}", hasher.finalize()),
-					Err(err) => {
-						error!("Error hashing artifact file {}: {}", artifact, err);
-						continue 'versions;
-					}
-				};
 				checksums.insert(path, Some(hash.clone()));
 
 				// If the hash doesn't match, move on to the next version
@@ -128,6 +118,8 @@ pub fn mods_by_checksum(base_path: impl AsRef<Path>, mods: &ResoluteModMap) -> R
 			debug!("Discovered installed mod {} v{}", rmod, semver);
 			let rmod = ResoluteMod {
 				installed_version: Some(semver.clone()),
+				install_reason: Some(InstallReason::Explicit),
+				pin: None,
 				..rmod.clone()
 			};
 			discovered.insert(id.clone(), rmod);
@@ -215,24 +207,11 @@ pub fn mods_by_filename(
 			});
 
 			// Calculate the checksum of the file
-			let sha256 = {
-				// Open the file
-				let mut file = match fs::File::open(&artifact_path) {
-					Ok(file) => file,
-					Err(err) => {
-						error!("Artifact file {} can't be opened: {}", artifact_path.display(), err);
-						continue;
-					}
-				};
-
-				// Calculate the checksum
-				let mut hasher = Sha256::new();
-				match io::copy(&mut file, &mut hasher) {
-					Ok(_bytes) => format!("{:x}", hasher.finalize()),
-					Err(err) => {
-						error!("Error hashing artifact file {}: {}", artifact_path.display(), err);
-						continue;
-					}
+			let sha256 = match manager::hash_file_blocking(&artifact_path) {
+				Ok(hash) => hash,
+				Err(err) => {
+					error!("Error hashing artifact file {}: {}", artifact_path.display(), err);
+					continue;
 				}
 			};
 
@@ -251,6 +230,7 @@ pub fn mods_by_filename(
 				let mut rmod = rmod.clone();
 				rmod.versions.insert(semver.clone(), version);
 				rmod.installed_version = Some(semver.clone());
+				rmod.install_reason = Some(InstallReason::Explicit);
 
 				debug!("Created unrecognized version {} for existing mod {}", semver, rmod);
 				rmod
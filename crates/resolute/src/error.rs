@@ -1,15 +1,16 @@
 use core::result;
-use std::io;
+use std::{collections::BTreeMap, io, time::Duration};
 
 #[cfg(feature = "db")]
 use native_db::db_type;
 use reqwest::StatusCode;
-use semver::Version;
+use semver::{Version, VersionReq};
 use tokio::task;
 
 use crate::{
 	manager::artifacts::{ArtifactError, ArtifactErrorVec},
-	models::ResoluteMod,
+	models::{ModFlag, Platform, ResoluteMod},
+	resolver::{Incompatibility, ModConflictVec},
 };
 
 /// Error returned from a Downloader
@@ -22,6 +23,9 @@ pub enum Error {
 	#[error("http request resulted in a non-successful (not 2xx) status: {0}")]
 	Http(StatusCode),
 
+	#[error("http request resulted in a non-successful (not 2xx) status: {status} (retry after {retry_after:?})")]
+	HttpRetryAfter { status: StatusCode, retry_after: Duration },
+
 	#[error("io error: {0}")]
 	Io(#[from] io::Error),
 
@@ -34,6 +38,9 @@ pub enum Error {
 	#[error("unable to parse url: {0}")]
 	Url(String),
 
+	#[error("no artifact source is registered for url scheme \"{0}\"")]
+	UnsupportedScheme(String),
+
 	#[error("unable to parse semver: {0}")]
 	Semver(#[from] semver::Error),
 
@@ -47,12 +54,61 @@ pub enum Error {
 		file: String,
 	},
 
+	#[error("operation was canceled")]
+	Cancelled,
+
 	#[error("unknown version \"{1}\" for mod \"{0}\"")]
 	UnknownVersion(String, Version),
 
+	#[error("unknown mod \"{0}\"")]
+	UnknownMod(String),
+
+	#[error("no version of mod \"{0}\" is available to install")]
+	NoAvailableVersion(String),
+
+	#[error("unable to find versions satisfying all dependencies of mod \"{id}\" v{version}: {cause}")]
+	UnresolvableDependencies {
+		id: String,
+		version: Version,
+		cause: Box<Incompatibility>,
+	},
+
+	#[error("unable to find versions satisfying all dependencies of profile \"{name}\": {cause}")]
+	UnresolvableProfile { name: String, cause: Box<Incompatibility> },
+
+	#[error("unable to find compatible versions for all installed mods: {0}")]
+	UnresolvableUpdate(Box<Incompatibility>),
+
+	#[error("installing would introduce mod conflicts: {0}")]
+	Conflicts(Box<ModConflictVec>),
+
 	#[error("mod \"{0}\" isn't installed")]
 	ModNotInstalled(Box<ResoluteMod>),
 
+	#[error("mod \"{id}\" is pinned to {pin}, which doesn't allow v{version}")]
+	Pinned {
+		id: String,
+		version: Version,
+		pin: VersionReq,
+	},
+
+	#[error("mods don't support the {platform} platform: {}", ids.join(", "))]
+	UnsupportedPlatform { platform: Platform, ids: Vec<String> },
+
+	#[error(
+		"installing would introduce flagged mods that need confirmation: {}",
+		describe_flagged(flagged)
+	)]
+	Flagged {
+		flagged: Box<BTreeMap<String, Vec<ModFlag>>>,
+	},
+
+	#[error("mod \"{id}\" is required by other installed mods: {}", dependents.join(", "))]
+	RequiredBy { id: String, dependents: Vec<String> },
+
+	#[error("lockfile format version {version} isn't supported (newest supported version is {supported})")]
+	UnsupportedLockfile { version: u32, supported: u32 },
+
 	#[error("artifact error: {0}")]
 	Artifact(#[from] ArtifactError),
 
@@ -74,5 +130,17 @@ pub enum Error {
 	ItemNotFound(String),
 }
 
+/// Describes flagged mods for an error message
+fn describe_flagged(flagged: &BTreeMap<String, Vec<ModFlag>>) -> String {
+	flagged
+		.iter()
+		.map(|(id, flags)| {
+			let flags = flags.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+			format!("{id} ({flags})")
+		})
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
 /// Alias for a `Result` with the error type `resolute::Error`.
 pub type Result<T> = result::Result<T, Error>;
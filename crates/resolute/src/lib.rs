@@ -75,9 +75,12 @@
 pub mod db;
 pub mod discover;
 mod error;
+pub mod lockfile;
 pub mod manager;
 pub mod manifest;
 pub mod models;
+pub mod resolver;
+pub mod retry;
 
 pub use error::Error;
 pub use error::Result;
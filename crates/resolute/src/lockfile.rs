@@ -0,0 +1,305 @@
+use std::collections::{BTreeMap, HashMap};
+
+use log::warn;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+	models::{InstallReason, ModArtifact, ModAuthor, ModDependencyMap, ModVersion, ResoluteMod, ResoluteModMap},
+	Error, Result,
+};
+
+/// Version of the lockfile format that is written by this version of Resolute
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// Exact record of an installation's mods that can be used to reproduce it elsewhere
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Lockfile {
+	/// Version of the lockfile format
+	pub version: u32,
+	/// Locked mods mapped by their IDs
+	pub mods: BTreeMap<String, LockedMod>,
+}
+
+impl Lockfile {
+	/// Builds a lockfile from the installed mods in a mod map.
+	/// Unrecognized mods are left out since there's no way to obtain their artifacts elsewhere.
+	pub fn from_installed(mods: &ResoluteModMap) -> Result<Self> {
+		let mut locked = BTreeMap::new();
+		for rmod in mods.values() {
+			let Some(semver) = &rmod.installed_version else {
+				continue;
+			};
+			if rmod.is_unrecognized() {
+				warn!("Leaving unrecognized mod {} out of lockfile", rmod);
+				continue;
+			}
+
+			let version = rmod
+				.versions
+				.get(semver)
+				.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), semver.clone()))?;
+			locked.insert(
+				rmod.id.clone(),
+				LockedMod {
+					name: rmod.name.clone(),
+					version: semver.clone(),
+					install_reason: rmod.install_reason,
+					artifacts: version.artifacts.iter().map(LockedArtifact::from).collect(),
+				},
+			);
+		}
+
+		Ok(Self {
+			version: LOCKFILE_VERSION,
+			mods: locked,
+		})
+	}
+
+	/// Parses a lockfile from JSON, ensuring its format version is supported
+	pub fn parse(json: &str) -> Result<Self> {
+		// Check the format version on its own first so that an unsupported version gets a useful error
+		#[derive(Deserialize)]
+		struct VersionOnly {
+			version: u32,
+		}
+		let VersionOnly { version } = serde_json::from_str(json)?;
+		if version > LOCKFILE_VERSION {
+			return Err(Error::UnsupportedLockfile {
+				version,
+				supported: LOCKFILE_VERSION,
+			});
+		}
+
+		Ok(serde_json::from_str(json)?)
+	}
+
+	/// Serializes the lockfile to pretty-printed JSON
+	pub fn to_json(&self) -> Result<String> {
+		Ok(serde_json::to_string_pretty(self)?)
+	}
+
+	/// Builds the mods to install from the lockfile. Each mod gets a version made up of exactly the locked artifacts.
+	/// Details such as the name, description, and dependencies are taken from the matching entry in the given mod map
+	/// if there is one, and any installed version from that entry is kept alongside the locked version so that it can
+	/// be replaced.
+	#[must_use]
+	pub fn to_mods(&self, mods: &ResoluteModMap) -> ResoluteModMap {
+		self.mods
+			.iter()
+			.map(|(id, locked)| {
+				let known = mods.get(id);
+				let known_version = known.and_then(|rmod| rmod.versions.get(&locked.version));
+				let version = ModVersion {
+					semver: locked.version.clone(),
+					artifacts: locked.artifacts.iter().map(ModArtifact::from).collect(),
+					dependencies: known_version
+						.map_or_else(ModDependencyMap::new, |version| version.dependencies.clone()),
+					conflicts: known_version.map_or_else(ModDependencyMap::new, |version| version.conflicts.clone()),
+					release_url: known_version.and_then(|version| version.release_url.clone()),
+					changelog: known_version.and_then(|version| version.changelog.clone()),
+				};
+
+				let mut versions = HashMap::from([(locked.version.clone(), version)]);
+				if let Some(installed) = known.and_then(|rmod| rmod.versions.get(rmod.installed_version.as_ref()?)) {
+					versions
+						.entry(installed.semver.clone())
+						.or_insert_with(|| installed.clone());
+				}
+
+				let rmod = ResoluteMod {
+					id: id.clone(),
+					name: locked.name.clone(),
+					description: known.map_or_else(String::new, |rmod| rmod.description.clone()),
+					category: known.map_or_else(String::new, |rmod| rmod.category.clone()),
+					authors: known.map_or_else(|| vec![ModAuthor::unknown()], |rmod| rmod.authors.clone()),
+					source_location: known.and_then(|rmod| rmod.source_location.clone()),
+					website: known.and_then(|rmod| rmod.website.clone()),
+					tags: known.and_then(|rmod| rmod.tags.clone()),
+					flags: known.and_then(|rmod| rmod.flags.clone()),
+					platforms: known.and_then(|rmod| rmod.platforms.clone()),
+					versions,
+					installed_version: known.and_then(|rmod| rmod.installed_version.clone()),
+					install_reason: locked.install_reason,
+					pin: known.and_then(|rmod| rmod.pin.clone()),
+					active: known.is_some_and(|rmod| rmod.active),
+				};
+				(id.clone(), rmod)
+			})
+			.collect()
+	}
+}
+
+/// A mod recorded in a [`Lockfile`] with the exact version that was installed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LockedMod {
+	pub name: String,
+	pub version: Version,
+	#[serde(rename = "installReason")]
+	pub install_reason: Option<InstallReason>,
+	pub artifacts: Vec<LockedArtifact>,
+}
+
+/// An artifact recorded in a [`Lockfile`] for a locked mod version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LockedArtifact {
+	pub url: Url,
+	pub sha256: String,
+	pub filename: Option<String>,
+	#[serde(rename = "installLocation")]
+	pub install_location: Option<String>,
+	#[serde(rename = "overrideFilename")]
+	pub override_filename: Option<String>,
+}
+
+impl From<&ModArtifact> for LockedArtifact {
+	fn from(artifact: &ModArtifact) -> Self {
+		Self {
+			url: artifact.url.clone(),
+			sha256: artifact.sha256.clone(),
+			filename: artifact.filename.clone(),
+			install_location: artifact.install_location.clone(),
+			override_filename: artifact.override_filename.clone(),
+		}
+	}
+}
+
+impl From<&LockedArtifact> for ModArtifact {
+	fn from(artifact: &LockedArtifact) -> Self {
+		Self {
+			url: artifact.url.clone(),
+			sha256: artifact.sha256.clone(),
+			filename: artifact.filename.clone(),
+			install_location: artifact.install_location.clone(),
+			override_filename: artifact.override_filename.clone(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use semver::Version;
+	use url::Url;
+
+	use super::{Lockfile, LOCKFILE_VERSION};
+	use crate::{
+		models::{InstallReason, ModArtifact, ModDependencyMap, ModVersion, ResoluteMod, ResoluteModMap},
+		Error,
+	};
+
+	fn artifact(name: &str) -> ModArtifact {
+		ModArtifact {
+			url: Url::parse(&format!("https://example.com/{name}")).expect("parse url"),
+			sha256: format!("{name}-checksum"),
+			filename: None,
+			install_location: Some("/rml_mods".to_owned()),
+			override_filename: None,
+		}
+	}
+
+	/// Builds a mod with a single version made up of one artifact, optionally installed
+	fn rmod(id: &str, semver: &str, reason: Option<InstallReason>) -> ResoluteMod {
+		let semver = Version::parse(semver).expect("parse version");
+		let version = ModVersion {
+			semver: semver.clone(),
+			artifacts: vec![artifact(&format!("{id}.dll"))],
+			dependencies: ModDependencyMap::new(),
+			conflicts: ModDependencyMap::new(),
+			release_url: None,
+			changelog: None,
+		};
+
+		ResoluteMod {
+			id: id.to_owned(),
+			name: id.to_uppercase(),
+			description: format!("The {id} mod"),
+			category: "Libraries".to_owned(),
+			authors: Vec::new(),
+			source_location: None,
+			website: None,
+			tags: None,
+			flags: None,
+			platforms: None,
+			versions: HashMap::from([(semver.clone(), version)]),
+			installed_version: reason.is_some().then_some(semver),
+			install_reason: reason,
+			pin: None,
+			active: reason.is_some(),
+		}
+	}
+
+	fn map(mods: impl IntoIterator<Item = ResoluteMod>) -> ResoluteModMap {
+		mods.into_iter().map(|rmod| (rmod.id.clone(), rmod)).collect()
+	}
+
+	#[test]
+	fn round_trips_installed_mods() {
+		let mods = map([
+			rmod("app", "1.2.0", Some(InstallReason::Explicit)),
+			rmod("lib", "0.3.1", Some(InstallReason::Dependency)),
+			rmod("available", "2.0.0", None),
+			ResoluteMod::new_unrecognized("Unknown.dll", "/rml_mods", "unknown-checksum"),
+		]);
+
+		let lockfile = Lockfile::from_installed(&mods).expect("build lockfile");
+		assert_eq!(lockfile.version, LOCKFILE_VERSION);
+		assert_eq!(lockfile.mods.keys().collect::<Vec<_>>(), ["app", "lib"]);
+
+		let json = lockfile.to_json().expect("serialize lockfile");
+		let parsed = Lockfile::parse(&json).expect("parse lockfile");
+		assert_eq!(parsed.to_json().expect("serialize parsed lockfile"), json);
+
+		let lib = &parsed.mods["lib"];
+		assert_eq!(lib.name, "LIB");
+		assert_eq!(lib.version, Version::new(0, 3, 1));
+		assert_eq!(lib.install_reason, Some(InstallReason::Dependency));
+		assert_eq!(lib.artifacts.len(), 1);
+		assert_eq!(lib.artifacts[0].url.as_str(), "https://example.com/lib.dll");
+		assert_eq!(lib.artifacts[0].sha256, "lib.dll-checksum");
+	}
+
+	#[test]
+	fn rejects_newer_lockfile_versions() {
+		let json = format!(r#"{{"version": {}, "mods": {{}}}}"#, LOCKFILE_VERSION.saturating_add(1));
+		let err = Lockfile::parse(&json).expect_err("parsing should fail");
+		assert!(matches!(
+			err,
+			Error::UnsupportedLockfile {
+				supported: LOCKFILE_VERSION,
+				..
+			}
+		));
+	}
+
+	#[test]
+	fn builds_mods_from_locked_versions() {
+		let lockfile = Lockfile::from_installed(&map([
+			rmod("app", "1.2.0", Some(InstallReason::Explicit)),
+			rmod("lib", "0.3.1", Some(InstallReason::Dependency)),
+		]))
+		.expect("build lockfile");
+
+		// The installation being reproduced has an older version of one of the mods and doesn't know the other
+		let known = map([rmod("app", "1.0.0", Some(InstallReason::Explicit))]);
+		let mods = lockfile.to_mods(&known);
+
+		let app = &mods["app"];
+		assert_eq!(app.description, "The app mod");
+		assert_eq!(app.installed_version, Some(Version::new(1, 0, 0)));
+		assert!(app.versions.contains_key(&Version::new(1, 0, 0)));
+		let locked = &app.versions[&Version::new(1, 2, 0)];
+		assert_eq!(locked.artifacts[0].url.as_str(), "https://example.com/app.dll");
+
+		let lib = &mods["lib"];
+		assert_eq!(lib.name, "LIB");
+		assert!(lib.installed_version.is_none());
+		assert_eq!(lib.install_reason, Some(InstallReason::Dependency));
+		assert_eq!(lib.versions.len(), 1);
+	}
+}
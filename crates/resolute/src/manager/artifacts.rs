@@ -1,13 +1,17 @@
 use std::{
 	error, fmt,
-	io::ErrorKind,
+	fs::File,
+	io::{self, ErrorKind},
 	path::{Path, PathBuf},
 };
 
-use tokio::fs;
+use sha2::{Digest, Sha256};
+use tokio::{fs, task};
 
 use crate::Error;
 
+use super::store::ArtifactStore;
+
 /// Deletes an artifact file
 pub(crate) async fn delete(path: &Path, ignore_nonexistent: bool) -> Result<bool, ArtifactError> {
 	match fs::remove_file(path).await {
@@ -44,6 +48,29 @@ pub(crate) async fn rename(from: &Path, to: &Path, ignore_nonexistent: bool) ->
 	}
 }
 
+/// Discards an artifact file - if a store is given, the file is moved into it to be kept, otherwise it's deleted.
+/// Nonexistent files are ignored.
+pub(crate) async fn discard(path: &Path, store: Option<&ArtifactStore>) -> Result<bool, ArtifactError> {
+	match store {
+		Some(store) => store.keep(path).await.map(|hash| hash.is_some()),
+		None => delete(path, true).await,
+	}
+}
+
+/// Calculates the SHA-256 checksum of a file as a lowercase hex string
+pub async fn hash_file(path: impl AsRef<Path>) -> crate::Result<String> {
+	let path = path.as_ref().to_owned();
+	task::spawn_blocking(move || hash_file_blocking(path)).await?
+}
+
+/// Calculates the SHA-256 checksum of a file as a lowercase hex string, blocking the current thread while doing so
+pub fn hash_file_blocking(path: impl AsRef<Path>) -> crate::Result<String> {
+	let mut hasher = Sha256::new();
+	let mut file = File::open(path)?;
+	io::copy(&mut file, &mut hasher)?;
+	Ok(format!("{:x}", hasher.finalize()))
+}
+
 /// An error performing an action on an artifact
 #[derive(Debug)]
 pub struct ArtifactError {
@@ -1,24 +1,28 @@
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
-use log::info;
+use log::{error, info};
 
 use crate::models::{ModArtifact, ModVersion};
 use crate::Error;
 
 use super::artifacts::{self, ArtifactAction, ArtifactError, ArtifactErrorVec, MappableToArtifactError};
+use super::store::ArtifactStore;
 
 /// Handles deleting mods
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct Deleter {
 	pub base_dest: PathBuf,
+	/// Store to keep deleted artifact files in. If there isn't one, they're deleted outright.
+	pub store: Option<ArtifactStore>,
 }
 
 impl Deleter {
 	pub fn new(base_dest: impl AsRef<Path>) -> Self {
 		Self {
 			base_dest: base_dest.as_ref().to_owned(),
+			store: None,
 		}
 	}
 
@@ -44,12 +48,50 @@ impl Deleter {
 		let path = artifact
 			.dest_within(&self.base_dest)
 			.map_pathless_artifact_err(ArtifactAction::Delete)?;
-		artifacts::delete(&path, true).await?;
+		artifacts::discard(&path, self.store.as_ref()).await?;
+
+		// Delete the disabled file as well in case the mod was disabled
+		let disabled_path = ModArtifact::disabled_dest(&path).map_pathless_artifact_err(ArtifactAction::Delete)?;
+		artifacts::discard(&disabled_path, self.store.as_ref()).await?;
 
 		info!("Deleted artifact file {}", path.display());
 		Ok(path)
 	}
 
+	/// Deletes the disabled files of a set of artifacts, if there are any
+	pub async fn delete_disabled_artifacts(
+		&self,
+		old_artifacts: impl IntoIterator<Item = &ModArtifact>,
+	) -> Result<(), ArtifactErrorVec> {
+		// Delete each disabled artifact path and track any failures
+		let mut failed = ArtifactErrorVec::new();
+		for artifact in old_artifacts {
+			let path = match artifact
+				.dest_within(&self.base_dest)
+				.and_then(ModArtifact::disabled_dest)
+				.map_pathless_artifact_err(ArtifactAction::Delete)
+			{
+				Ok(path) => path,
+				Err(err) => {
+					failed.push(err);
+					continue;
+				}
+			};
+
+			match artifacts::discard(&path, self.store.as_ref()).await {
+				Ok(true) => info!("Deleted disabled artifact file {}", path.display()),
+				Ok(false) => {}
+				Err(err) => failed.push(err),
+			}
+		}
+
+		if failed.is_empty() {
+			Ok(())
+		} else {
+			Err(failed)
+		}
+	}
+
 	/// Deletes leftover artifacts from an old set that aren't present in a new set of artifacts
 	pub async fn delete_artifacts_diff(
 		&self,
@@ -72,7 +114,7 @@ impl Deleter {
 		// Delete each unnecessary artifact path and track any failures
 		let mut failed = ArtifactErrorVec::new();
 		for path in unnecessary_paths {
-			if let Err(err) = artifacts::delete(path, true).await {
+			if let Err(err) = artifacts::discard(path, self.store.as_ref()).await {
 				failed.push(err);
 			}
 			info!("Deleted left-over artifact file {}", path.display());
@@ -84,4 +126,91 @@ impl Deleter {
 			Err(Error::Artifacts(failed))
 		}
 	}
+
+	/// Determines the paths of artifact files from an old set that are no longer needed once a new set of artifacts is
+	/// in place. This includes the disabled files of all of the old artifacts, since new artifacts are always installed
+	/// enabled.
+	pub fn leftover_paths(
+		&self,
+		old_artifacts: &[ModArtifact],
+		new_artifacts: &[ModArtifact],
+	) -> crate::Result<Vec<PathBuf>> {
+		let new_paths = new_artifacts
+			.iter()
+			.map(|artifact| artifact.dest_within(&self.base_dest))
+			.collect::<crate::Result<HashSet<PathBuf>>>()?;
+
+		let mut leftover = Vec::new();
+		for artifact in old_artifacts {
+			let path = artifact.dest_within(&self.base_dest)?;
+			leftover.push(ModArtifact::disabled_dest(&path)?);
+			if !new_paths.contains(&path) {
+				leftover.push(path);
+			}
+		}
+
+		Ok(leftover)
+	}
+
+	/// Moves artifact files out of the way (to their old destinations) so that their removal can still be undone.
+	/// Either all of the files are set aside or none of them are - if any rename fails, the successful ones are
+	/// restored. Nonexistent files are skipped.
+	pub async fn set_aside(&self, paths: Vec<PathBuf>) -> crate::Result<Vec<SetAsideArtifact>> {
+		let mut set_aside = Vec::new();
+		for path in paths {
+			let result = match ModArtifact::old_dest(&path).map_artifact_err(ArtifactAction::Rename, &path) {
+				Ok(aside) => artifacts::rename(&path, &aside, true)
+					.await
+					.map(|renamed| renamed.then_some(SetAsideArtifact { path, aside })),
+				Err(err) => Err(err),
+			};
+
+			match result {
+				Ok(Some(artifact)) => set_aside.push(artifact),
+				Ok(None) => {}
+				Err(err) => {
+					error!("Error setting aside artifacts, restoring any successful ones: {}", err);
+					restore_artifacts(set_aside).await;
+					return Err(Error::Artifact(err));
+				}
+			}
+		}
+
+		Ok(set_aside)
+	}
+}
+
+/// An artifact file that has been moved out of the way by [`Deleter::set_aside`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SetAsideArtifact {
+	/// Path the file was originally at
+	pub path: PathBuf,
+	/// Path the file was moved to
+	pub aside: PathBuf,
+}
+
+impl SetAsideArtifact {
+	/// Moves the file back to its original path
+	pub async fn restore(self) -> Result<(), ArtifactError> {
+		artifacts::rename(&self.aside, &self.path, false).await?;
+		Ok(())
+	}
+
+	/// Discards the file for good - if a store is given, the file is moved into it to be kept, otherwise it's deleted
+	pub async fn discard(self, store: Option<&ArtifactStore>) -> Result<(), ArtifactError> {
+		artifacts::discard(&self.aside, store).await?;
+		info!("Deleted left-over artifact file {}", self.path.display());
+		Ok(())
+	}
+}
+
+/// Restores set aside artifacts, logging any errors
+pub(crate) async fn restore_artifacts(set_aside: Vec<SetAsideArtifact>) {
+	for artifact in set_aside {
+		let path = artifact.path.clone();
+		if let Err(err) = artifact.restore().await {
+			error!("Error restoring set aside artifact ({}): {}", path.display(), err);
+		}
+	}
 }
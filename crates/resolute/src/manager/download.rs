@@ -1,114 +1,275 @@
 use std::{
+	collections::{hash_map::Entry, HashMap},
 	fmt,
 	io::ErrorKind,
 	path::{Path, PathBuf},
+	sync::Arc,
+	time::{Duration, Instant},
 };
 
-use futures_util::TryStreamExt;
-use log::{debug, error, info};
-use reqwest::{Client, IntoUrl};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use log::{debug, error, info, warn};
+use reqwest::Client;
 use sha2::{Digest, Sha256};
 use tokio::{
 	fs,
-	io::{AsyncWriteExt, BufWriter},
+	io::{AsyncReadExt, AsyncWriteExt, BufWriter},
+	select,
 };
+use tokio_util::sync::CancellationToken;
+use url::Url;
 
 use crate::models::{ModArtifact, ModVersion};
+use crate::retry::RetryPolicy;
 use crate::{Error, Result};
 
 use super::artifacts::{self, ArtifactAction, ArtifactError, MappableToArtifactError};
+use super::mirror::Mirror;
+use super::progress::{ArtifactProgress, Progress};
+use super::source::{ArtifactSource, FileSource, HttpSource, Partial};
+use super::store::ArtifactStore;
 
 /// Handles mod downloads
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct Downloader {
 	pub base_dest: PathBuf,
-	pub http_client: Client,
+	/// Sources to fetch artifacts from, by the URL schemes they handle
+	pub sources: HashMap<String, Arc<dyn ArtifactSource>>,
+	/// Store to take artifacts from before downloading them, add downloaded artifacts to, and keep replaced artifact
+	/// files in. If there isn't one, everything is downloaded and replaced artifact files are deleted.
+	pub store: Option<ArtifactStore>,
+	/// Maximum number of artifacts to download at the same time
+	pub concurrency: usize,
+	/// Policy for retrying failed downloads
+	pub retry: RetryPolicy,
+	/// Mirrors to try in order when an artifact can't be downloaded from its own URL
+	pub mirrors: Vec<Mirror>,
 }
 
 impl Downloader {
+	/// Default maximum number of artifacts to download at the same time
+	pub const DEFAULT_CONCURRENCY: usize = 4;
+
+	/// Minimum time between progress updates for a single download
+	pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
 	pub fn new(base_dest: impl AsRef<Path>, http_client: Client) -> Self {
 		Self {
-			http_client,
 			base_dest: base_dest.as_ref().to_owned(),
+			sources: default_sources(http_client),
+			store: None,
+			concurrency: Self::DEFAULT_CONCURRENCY,
+			retry: RetryPolicy::default(),
+			mirrors: Vec::new(),
 		}
 	}
 
+	/// Registers a source to fetch artifacts with URLs of a scheme from, replacing any source already registered for it
+	pub fn register_source(&mut self, scheme: impl Into<String>, source: impl ArtifactSource + 'static) {
+		self.sources.insert(scheme.into().to_lowercase(), Arc::new(source));
+	}
+
+	/// Changes the HTTP client used by the source for http:// and https:// URLs
+	pub fn set_http_client(&mut self, http_client: Client) {
+		let source: Arc<dyn ArtifactSource> = Arc::new(HttpSource::new(http_client));
+		for scheme in HttpSource::SCHEMES {
+			self.sources.insert(scheme.to_owned(), Arc::clone(&source));
+		}
+	}
+
+	/// Gets the source registered for a URL's scheme
+	pub fn source_for(&self, url: &Url) -> Result<&dyn ArtifactSource> {
+		self.sources
+			.get(url.scheme())
+			.map(AsRef::as_ref)
+			.ok_or_else(|| Error::UnsupportedScheme(url.scheme().to_owned()))
+	}
+
 	/// Downloads all relevant artifacts for a specific mod version to their proper destinations in the given base path
-	pub async fn download_version(&self, version: &ModVersion, progress: impl Fn(u64, u64)) -> Result<()> {
-		let mut install_error = None;
-
-		// Download all of the artifacts and track any successful ones - on an error, abort any further ones
-		let mut downloaded = Vec::new();
-		for artifact in &version.artifacts {
-			match self.download_artifact(artifact, &progress).await {
-				Ok(dl_artifact) => downloaded.push(dl_artifact),
+	pub async fn download_version(
+		&self,
+		version: &ModVersion,
+		progress: impl Fn(Progress),
+		cancel: &CancellationToken,
+	) -> Result<()> {
+		self.download_versions(&[version], progress, cancel).await
+	}
+
+	/// Downloads all relevant artifacts for multiple mod versions to their proper destinations in the given base path.
+	/// Either all of the artifacts are put in place or none of them are - if any download or finalization fails, every
+	/// artifact that was already handled is reverted.
+	pub async fn download_versions(
+		&self,
+		versions: &[&ModVersion],
+		progress: impl Fn(Progress),
+		cancel: &CancellationToken,
+	) -> Result<()> {
+		let downloaded = self.stage_versions(versions, &progress, cancel).await?;
+		progress(Progress::Finalizing {
+			count: downloaded.len(),
+		});
+		let finalized = self.finalize_artifacts(downloaded).await?;
+		progress(Progress::DeletingOldFiles);
+		self.discard_old_artifacts(finalized).await;
+		Ok(())
+	}
+
+	/// Downloads all relevant artifacts for multiple mod versions to their temporary destinations without putting any
+	/// of them in place. Up to the downloader's concurrency limit of artifacts are downloaded at the same time, and
+	/// progress updates identify which artifact they're for. If any download fails, no further ones are started and
+	/// the ones that were downloaded are canceled.
+	pub async fn stage_versions<'a>(
+		&self,
+		versions: &[&'a ModVersion],
+		progress: impl Fn(Progress),
+		cancel: &CancellationToken,
+	) -> Result<Vec<DownloadedArtifact<'a>>> {
+		// Artifacts that share a destination (such as the same file shipped by two mods) would race each other on the
+		// same temporary files, so only the first artifact for each destination is downloaded
+		let mut destinations: HashMap<PathBuf, &ModArtifact> = HashMap::new();
+		let artifacts: Vec<&ModArtifact> = versions
+			.iter()
+			.flat_map(|version| &version.artifacts)
+			.filter(|artifact| {
+				let Ok(dest) = artifact.dest_within(&self.base_dest) else {
+					return true;
+				};
+				match destinations.entry(dest) {
+					Entry::Vacant(entry) => {
+						entry.insert(artifact);
+						true
+					}
+					Entry::Occupied(entry) => {
+						if !entry.get().sha256.eq_ignore_ascii_case(&artifact.sha256) {
+							warn!(
+								"Artifacts {} and {} share the destination {} but differ, only downloading the first",
+								entry.get().url,
+								artifact.url,
+								entry.key().display()
+							);
+						}
+						false
+					}
+				}
+			})
+			.collect();
+		let count = artifacts.len();
+
+		// Download the artifacts concurrently - once one fails, the others are aborted through a child token so that
+		// any in flight stop promptly and any that haven't started yet are skipped.
+		// The futures are collected up front rather than mapped lazily in the stream so that the resulting future
+		// stays Send.
+		let abort = cancel.child_token();
+		let downloads: Vec<_> = artifacts
+			.into_iter()
+			.enumerate()
+			.map(|(index, artifact)| {
+				let (abort, progress) = (&abort, &progress);
+				async move {
+					if cancel.is_cancelled() {
+						return Err(Error::Cancelled);
+					}
+					if abort.is_cancelled() {
+						return Ok(None);
+					}
+
+					let result = self
+						.download_artifact(
+							artifact,
+							|update| progress(update.at(index.saturating_add(1), count)),
+							abort,
+						)
+						.await;
+					match result {
+						Ok(dl_artifact) => Ok(Some(dl_artifact)),
+						// Downloads aborted because another one failed don't have errors of their own
+						Err(Error::Cancelled) if !cancel.is_cancelled() => Ok(None),
+						Err(err) => {
+							abort.cancel();
+							Err(err)
+						}
+					}
+				}
+			})
+			.collect();
+		let results: Vec<Result<Option<DownloadedArtifact<'a>>>> = stream::iter(downloads)
+			.buffered(self.concurrency.max(1))
+			.collect()
+			.await;
+
+		// Keep track of the successful downloads and the first error
+		let mut downloaded = Vec::with_capacity(results.len());
+		let mut download_error = None;
+		for result in results {
+			match result {
+				Ok(Some(dl_artifact)) => downloaded.push(dl_artifact),
+				Ok(None) => {}
 				Err(err) => {
-					install_error = Some(err);
-					break;
+					download_error.get_or_insert(err);
 				}
 			}
 		}
 
-		// If an error was encountered, delete the downloaded ones
-		if let Some(err) = install_error {
+		// If a download failed, delete the downloaded ones
+		if let Some(err) = download_error {
 			error!("Error downloading artifacts, canceling any successful ones: {}", err);
-
-			for dl_artifact in downloaded {
-				let artifact = dl_artifact.artifact;
-				if let Err(err) = dl_artifact.cancel().await {
-					error!("Error canceling downloaded artifact ({}.new): {}", artifact, err);
-				}
-			}
-
+			cancel_artifacts(downloaded).await;
 			return Err(err);
 		}
 
+		Ok(downloaded)
+	}
+
+	/// Finalizes downloaded artifacts, putting them in place. If any finalization fails, the ones that were already
+	/// finalized are undone and the rest are canceled.
+	pub async fn finalize_artifacts<'a>(
+		&self,
+		downloaded: Vec<DownloadedArtifact<'a>>,
+	) -> Result<Vec<FinalizedArtifact<'a>>> {
 		// Finalize the artifacts and track any successful ones - on an error, abort any further ones
-		let mut finalized = Vec::new();
-		for dl_artifact in downloaded {
+		let mut finalized = Vec::with_capacity(downloaded.len());
+		let mut remaining = downloaded.into_iter();
+		for dl_artifact in remaining.by_ref() {
 			match dl_artifact.finalize().await {
 				Ok(final_artifact) => finalized.push(final_artifact),
 				Err(err) => {
-					install_error = Some(err);
-					break;
+					// Delete the finalized ones and return the old artifacts to their original locations
+					error!(
+						"Error finalizing downloaded artifacts, undoing any successful ones: {}",
+						err
+					);
+					undo_artifacts(finalized).await;
+					cancel_artifacts(remaining.collect()).await;
+					return Err(err);
 				}
 			}
 		}
 
-		// If an error was encountered, delete the finalized ones and return the old artifacts to their original locations
-		if let Some(err) = install_error {
-			error!(
-				"Error finalizing downloaded artifacts, undoing any successful ones: {}",
-				err
-			);
-
-			for final_artifact in finalized {
-				let artifact = final_artifact.artifact;
-				if let Err(err) = final_artifact.undo().await {
-					error!("Error undoing actions for finalized artifact ({}): {}", artifact, err);
-				}
-			}
-
-			return Err(err);
-		}
+		Ok(finalized)
+	}
 
-		// Delete the old artifacts
+	/// Keeps or deletes the old artifacts that finalized artifacts replaced, depending on whether there's a store.
+	/// Any errors are logged rather than returned, since the new artifacts are already in place.
+	pub async fn discard_old_artifacts(&self, finalized: Vec<FinalizedArtifact<'_>>) {
 		for final_artifact in finalized.into_iter().filter(FinalizedArtifact::has_old) {
 			let artifact = final_artifact.artifact;
-			if let Err(err) = final_artifact.delete_old().await {
-				error!("Error deleting old artifact ({}.old): {}", artifact, err);
+			let result = match &self.store {
+				Some(store) => final_artifact.keep_old(store).await,
+				None => final_artifact.delete_old().await,
+			};
+			if let Err(err) = result {
+				error!("Error discarding old artifact ({}.old): {}", artifact, err);
 			}
 		}
-
-		Ok(())
 	}
 
 	/// Downloads a specific artifact to a temporary destination (filename.dll.new) within a given base path
 	pub async fn download_artifact<'a>(
 		&self,
 		artifact: &'a ModArtifact,
-		progress: impl Fn(u64, u64),
+		progress: impl Fn(ArtifactProgress),
+		cancel: &CancellationToken,
 	) -> Result<DownloadedArtifact<'a>> {
 		let final_dest = artifact
 			.dest_within(&self.base_dest)
@@ -136,11 +297,79 @@ impl Downloader {
 			}
 		}
 
-		// Download the artifact to its temporary location
+		// Use a kept copy of the artifact if there is one
+		if let Some(store) = &self.store {
+			match store.copy_to(&artifact.sha256, &tmp_dest).await {
+				Ok(true) => {
+					info!(
+						"Using kept copy of artifact {} for {}",
+						artifact.url,
+						tmp_dest.display()
+					);
+
+					// Any partial download is no longer needed
+					if let Ok(resume_dest) = ModArtifact::resume_dest(&tmp_dest) {
+						artifacts::delete(&resume_dest, true).await?;
+					}
+					return Ok(DownloadedArtifact {
+						artifact,
+						final_dest,
+						tmp_dest,
+					});
+				}
+				Ok(false) => {}
+				Err(err) => warn!("Unable to use kept copy of artifact {}: {}", artifact.url, err),
+			}
+		}
+
+		// Download the artifact to its temporary location, falling back to any mirrors in order if that fails
 		info!("Downloading artifact {} to {}", artifact.url, tmp_dest.display());
-		self.download(artifact.url.clone(), &tmp_dest, &artifact.sha256, progress)
-			.await
-			.map_artifact_err(ArtifactAction::Download, &tmp_dest)?;
+		let mut result = self
+			.download(artifact.url.clone(), &tmp_dest, &artifact.sha256, &progress, cancel)
+			.await;
+		for mirror in &self.mirrors {
+			let Err(err) = &result else {
+				break;
+			};
+			if let Error::Cancelled = err {
+				break;
+			}
+
+			let url = match mirror.url_for(artifact) {
+				Ok(url) => url,
+				Err(mirror_err) => {
+					warn!(
+						"Unable to use mirror {} for artifact {}: {}",
+						mirror, artifact.url, mirror_err
+					);
+					continue;
+				}
+			};
+			warn!(
+				"Unable to download artifact {} ({}), trying mirror {}",
+				artifact.url, err, url
+			);
+
+			match self
+				.download(url.clone(), &tmp_dest, &artifact.sha256, &progress, cancel)
+				.await
+			{
+				Ok(()) => result = Ok(()),
+				Err(Error::Cancelled) => result = Err(Error::Cancelled),
+				Err(mirror_err) => warn!("Unable to download artifact from mirror {}: {}", url, mirror_err),
+			}
+		}
+		if let Err(Error::Cancelled) = result {
+			return Err(Error::Cancelled);
+		}
+		result.map_artifact_err(ArtifactAction::Download, &tmp_dest)?;
+
+		// Add the verified download to the store so that it doesn't need to be downloaded again
+		if let Some(store) = &self.store {
+			if let Err(err) = store.add(&tmp_dest, &artifact.sha256).await {
+				warn!("Unable to add artifact {} to the store: {}", artifact.url, err);
+			}
+		}
 
 		Ok(DownloadedArtifact {
 			artifact,
@@ -149,41 +378,131 @@ impl Downloader {
 		})
 	}
 
-	/// Downloads a file to a destination path and checks its integrity, emitting progress updates along the way
+	/// Downloads a file to a destination path from the source registered for its URL's scheme and checks its
+	/// integrity, emitting progress updates along the way.
+	/// Failed attempts are retried according to the downloader's retry policy. If the download is canceled, streaming
+	/// stops right away and the partial file is deleted. If a previous download to the
+	/// destination was interrupted and the source supports it, the download picks up where it left off rather than
+	/// starting over. Interrupted downloads leave the partial file in place for this.
 	pub(crate) async fn download(
 		&self,
-		url: impl IntoUrl + Into<String> + Clone,
+		url: Url,
 		dest: impl AsRef<Path>,
 		checksum: &str,
-		progress: impl Fn(u64, u64),
+		progress: impl Fn(ArtifactProgress),
+		cancel: &CancellationToken,
 	) -> Result<()> {
 		let dest = dest.as_ref();
-
-		// Make the request
-		let request = self.http_client.get(url.clone());
-		let response = request.send().await?;
-
-		// Ensure the request yielded a successful response
-		let status = response.status();
-		if !status.is_success() {
-			return Err(Error::Http(status));
+		let description = format!("download to {}", dest.display());
+		let result = select! {
+			result = self.retry.run(description, || self.try_download(url.clone(), dest, checksum, &progress)) => result,
+			() = cancel.cancelled() => Err(Error::Cancelled),
+		};
+
+		// A canceled download won't be resumed, so get rid of whatever was downloaded so far
+		if let Err(Error::Cancelled) = result {
+			info!("Canceled download to {}", dest.display());
+			let resume_dest = ModArtifact::resume_dest(dest)?;
+			for path in [dest, &resume_dest] {
+				if let Err(err) = artifacts::delete(path, true).await {
+					warn!("Unable to delete partial download {}: {}", path.display(), err);
+				}
+			}
 		}
 
-		// Prep the file, stream, and hasher
-		let total_bytes = response.content_length().unwrap_or(0);
-		let mut file = BufWriter::new(fs::File::create(dest).await?);
-		let mut stream = response.bytes_stream();
+		result
+	}
+
+	/// Makes a single attempt at downloading a file to a destination path (see [`Self::download`])
+	async fn try_download(
+		&self,
+		url: Url,
+		dest: &Path,
+		checksum: &str,
+		progress: impl Fn(ArtifactProgress),
+	) -> Result<()> {
+		let source = self.source_for(&url)?;
+		let resume_dest = ModArtifact::resume_dest(dest)?;
+
+		// Check for a partial download that can be resumed. Only a partial download from the same URL can be, since a
+		// validator from one source means nothing to another (such as a mirror).
+		let partial_len = fs::metadata(dest).await.map_or(0, |metadata| metadata.len());
+		let resume_info = if partial_len > 0 {
+			fs::read_to_string(&resume_dest).await.ok()
+		} else {
+			None
+		};
+		let validator = resume_info.as_deref().and_then(|info| {
+			let (resume_url, validator) = info.split_once('\n')?;
+			(resume_url == url.as_str()).then_some(validator)
+		});
+		let partial = validator.map(|validator| Partial {
+			len: partial_len,
+			validator,
+		});
+
+		// Start fetching and prep the file and hasher - when resuming, the hash needs to include what was already
+		// downloaded
+		let fetched = source.fetch(&url, partial).await?;
+		let existing_bytes = fetched.offset;
 		let mut hasher = Sha256::new();
+		let file = if existing_bytes > 0 {
+			let mut file = fs::OpenOptions::new().read(true).append(true).open(dest).await?;
+			hash_existing(&mut file, &mut hasher).await?;
+			file
+		} else {
+			// Record how to resume the download and where from if the source supports it
+			match &fetched.validator {
+				Some(validator) => fs::write(&resume_dest, format!("{url}\n{validator}")).await?,
+				None => {
+					artifacts::delete(&resume_dest, true).await?;
+				}
+			}
 
-		// Write each chunk to the file and send a progress update
-		while let Some(chunk) = stream.try_next().await? {
-			file.write_all(&chunk).await?;
-			hasher.update(&chunk);
-			progress(chunk.len() as u64, total_bytes);
+			fs::File::create(dest).await?
+		};
+		let total_bytes = fetched.total;
+		let mut downloaded = existing_bytes;
+		let report = |downloaded| {
+			progress(ArtifactProgress::Downloading {
+				url: url.clone(),
+				downloaded,
+				total: total_bytes,
+			});
+		};
+		report(downloaded);
+
+		// Write each chunk to the file and send a progress update at most once per interval, so that large downloads
+		// don't flood the receiver with updates. If the download is interrupted, the partial file is kept so that it
+		// can be resumed later.
+		let mut file = BufWriter::new(file);
+		let mut stream = fetched.body;
+		let mut reported = (Instant::now(), downloaded);
+		let result = async {
+			while let Some(chunk) = stream.try_next().await? {
+				file.write_all(&chunk).await?;
+				hasher.update(&chunk);
+				downloaded = downloaded.saturating_add(chunk.len() as u64);
+				if reported.0.elapsed() >= Self::PROGRESS_INTERVAL {
+					report(downloaded);
+					reported = (Instant::now(), downloaded);
+				}
+			}
+			Ok::<_, Error>(())
 		}
+		.await;
 		file.flush().await?;
+		if reported.1 != downloaded {
+			report(downloaded);
+		}
+		if let Err(err) = result {
+			warn!("Download to {} was interrupted: {}", dest.display(), err);
+			return Err(err);
+		}
+		artifacts::delete(&resume_dest, true).await?;
 
 		// Verify the integrity of the downloaded file - if it doesn't match, delete the file
+		progress(ArtifactProgress::Verifying);
 		let digest = hasher.finalize();
 		let actual = format!("{digest:x}");
 		if actual != checksum.to_lowercase() {
@@ -191,7 +510,7 @@ impl Downloader {
 			return Err(Error::Checksum {
 				expected: checksum.to_owned(),
 				checksum: actual,
-				file: url.into(),
+				file: url.to_string(),
 			});
 		}
 
@@ -200,11 +519,60 @@ impl Downloader {
 	}
 }
 
+/// Builds the sources that every downloader starts with: HTTP for http:// and https:// URLs, and the local filesystem
+/// for file:// URLs
+fn default_sources(http_client: Client) -> HashMap<String, Arc<dyn ArtifactSource>> {
+	let http: Arc<dyn ArtifactSource> = Arc::new(HttpSource::new(http_client));
+	let mut sources: HashMap<String, Arc<dyn ArtifactSource>> = HttpSource::SCHEMES
+		.into_iter()
+		.map(|scheme| (scheme.to_owned(), Arc::clone(&http)))
+		.collect();
+	sources.insert(FileSource::SCHEME.to_owned(), Arc::new(FileSource::new()));
+	sources
+}
+
+/// Feeds the existing contents of a file into a hasher
+async fn hash_existing(file: &mut fs::File, hasher: &mut Sha256) -> Result<()> {
+	let mut buf = vec![0; 64 * 1024];
+	loop {
+		let read = file.read(&mut buf).await?;
+		if read == 0 {
+			return Ok(());
+		}
+		hasher.update(buf.get(..read).unwrap_or_default());
+	}
+}
+
+/// Cancels downloaded artifacts, logging any errors
+pub(crate) async fn cancel_artifacts(downloaded: Vec<DownloadedArtifact<'_>>) {
+	for dl_artifact in downloaded {
+		let artifact = dl_artifact.artifact;
+		if let Err(err) = dl_artifact.cancel().await {
+			error!("Error canceling downloaded artifact ({}.new): {}", artifact, err);
+		}
+	}
+}
+
+/// Undoes finalized artifacts, logging any errors
+pub(crate) async fn undo_artifacts(finalized: Vec<FinalizedArtifact<'_>>) {
+	for final_artifact in finalized {
+		let artifact = final_artifact.artifact;
+		if let Err(err) = final_artifact.undo().await {
+			error!("Error undoing actions for finalized artifact ({}): {}", artifact, err);
+		}
+	}
+}
+
 /// Builder for a [Downloader]
 #[derive(Default, Debug)]
 pub struct DownloaderBuilder {
 	base_dest: PathBuf,
 	http_client: Client,
+	sources: HashMap<String, Arc<dyn ArtifactSource>>,
+	store: Option<ArtifactStore>,
+	concurrency: Option<usize>,
+	retry: RetryPolicy,
+	mirrors: Vec<Mirror>,
 }
 
 impl DownloaderBuilder {
@@ -228,10 +596,57 @@ impl DownloaderBuilder {
 		self
 	}
 
+	/// Registers a source to fetch artifacts with URLs of a scheme from, replacing any source for it that would
+	/// otherwise be used (including the default HTTP and file sources)
+	#[must_use]
+	pub fn source(mut self, scheme: impl Into<String>, source: impl ArtifactSource + 'static) -> Self {
+		self.sources.insert(scheme.into().to_lowercase(), Arc::new(source));
+		self
+	}
+
+	/// Sets the store to take artifacts from, add downloaded artifacts to, and keep replaced artifact files in
+	#[must_use]
+	pub fn store(mut self, store: ArtifactStore) -> Self {
+		self.store = Some(store);
+		self
+	}
+
+	/// Sets the policy for retrying failed downloads
+	#[must_use]
+	pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+		self.retry = policy;
+		self
+	}
+
+	/// Adds a mirror to try when an artifact can't be downloaded from its own URL.
+	/// Mirrors are tried in the order they're added.
+	#[must_use]
+	pub fn mirror(mut self, mirror: Mirror) -> Self {
+		self.mirrors.push(mirror);
+		self
+	}
+
+	/// Sets the maximum number of artifacts to download at the same time (at least one)
+	#[must_use]
+	pub fn concurrency(mut self, limit: usize) -> Self {
+		self.concurrency = Some(limit.max(1));
+		self
+	}
+
 	/// Creates a Client using this builder's configuration and HTTP client
 	#[must_use]
 	pub fn build(self) -> Downloader {
-		Downloader::new(self.base_dest, self.http_client)
+		let mut sources = default_sources(self.http_client);
+		sources.extend(self.sources);
+
+		Downloader {
+			base_dest: self.base_dest,
+			sources,
+			store: self.store,
+			concurrency: self.concurrency.unwrap_or(Downloader::DEFAULT_CONCURRENCY),
+			retry: self.retry,
+			mirrors: self.mirrors,
+		}
 	}
 }
 
@@ -330,6 +745,15 @@ impl FinalizedArtifact<'_> {
 		Ok(())
 	}
 
+	/// Moves the old artifact file into a store to keep it, if there was one.
+	/// Fails if there was no old artifact or if there is an issue while moving it.
+	pub async fn keep_old(self, store: &ArtifactStore) -> Result<()> {
+		let old_dest = self.old_dest.ok_or_else(|| Error::NoOldArtifact)?;
+		store.keep(&old_dest).await?;
+		debug!("Kept old artifact file {}", old_dest.display());
+		Ok(())
+	}
+
 	/// Checks whether there is an old artifact file present that was renamed
 	#[must_use]
 	pub const fn has_old(&self) -> bool {
@@ -343,3 +767,194 @@ impl fmt::Display for FinalizedArtifact<'_> {
 		self.artifact.fmt(f)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		env, fs,
+		path::PathBuf,
+		process,
+		sync::{Arc, Mutex},
+		time::Duration,
+	};
+
+	use bytes::Bytes;
+	use futures_util::{future::BoxFuture, stream, FutureExt, StreamExt};
+	use reqwest::Client;
+	use semver::Version;
+	use sha2::{Digest, Sha256};
+	use tokio::time;
+	use tokio_util::sync::CancellationToken;
+	use url::Url;
+
+	use super::Downloader;
+	use crate::{
+		manager::{ArtifactSource, Fetched, Partial},
+		models::{ModArtifact, ModDependencyMap, ModVersion},
+		retry::RetryPolicy,
+		Error, Result,
+	};
+
+	/// Source that fails to fetch anything
+	#[derive(Debug)]
+	struct FailingSource;
+
+	impl ArtifactSource for FailingSource {
+		fn fetch<'a>(&'a self, _url: &'a Url, _partial: Option<Partial<'a>>) -> BoxFuture<'a, Result<Fetched>> {
+			async { Err(Error::Url("unavailable".to_owned())) }.boxed()
+		}
+	}
+
+	/// Source that starts fetching files but never provides any of their contents
+	#[derive(Debug)]
+	struct StallingSource;
+
+	impl ArtifactSource for StallingSource {
+		fn fetch<'a>(&'a self, _url: &'a Url, _partial: Option<Partial<'a>>) -> BoxFuture<'a, Result<Fetched>> {
+			async { Ok(Fetched::new(stream::pending().boxed())) }.boxed()
+		}
+	}
+
+	/// Source for a file that gets cut off partway through the first time it's fetched, and that supports resuming it
+	#[derive(Debug, Default)]
+	struct InterruptingSource {
+		/// Length of the partial file that each fetch was asked to resume from
+		resumed_from: Arc<Mutex<Vec<Option<u64>>>>,
+	}
+
+	impl InterruptingSource {
+		const START: &'static [u8] = b"0123";
+		const REST: &'static [u8] = b"456789";
+		const VALIDATOR: &'static str = "etag";
+	}
+
+	impl ArtifactSource for InterruptingSource {
+		fn fetch<'a>(&'a self, _url: &'a Url, partial: Option<Partial<'a>>) -> BoxFuture<'a, Result<Fetched>> {
+			async move {
+				let mut resumed_from = self.resumed_from.lock().expect("lock resumed fetches");
+				resumed_from.push(partial.map(|partial| partial.len));
+				let first = resumed_from.len() == 1;
+				drop(resumed_from);
+
+				let start = Ok(Bytes::from_static(Self::START));
+				let rest = Ok(Bytes::from_static(Self::REST));
+				let fetched = if first {
+					let interrupted = Err(Error::Url("connection lost".to_owned()));
+					Fetched::new(stream::iter([start, interrupted]).boxed())
+				} else if partial.is_some_and(|partial| {
+					partial.validator == Self::VALIDATOR && partial.len == Self::START.len() as u64
+				}) {
+					Fetched::new(stream::iter([rest]).boxed()).offset(Self::START.len() as u64)
+				} else {
+					Fetched::new(stream::iter([start, rest]).boxed())
+				};
+				Ok(fetched.total(Some(10)).validator(Some(Self::VALIDATOR.to_owned())))
+			}
+			.boxed()
+		}
+	}
+
+	/// Creates an empty temporary directory for a test
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = env::temp_dir().join(format!("resolute-test-{}-{name}", process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).expect("create temporary directory");
+		dir
+	}
+
+	fn artifact(url: &str) -> ModArtifact {
+		ModArtifact {
+			url: Url::parse(url).expect("parse artifact url"),
+			sha256: "0".repeat(64),
+			filename: None,
+			install_location: Some("/rml_mods".to_owned()),
+			override_filename: None,
+		}
+	}
+
+	#[tokio::test]
+	async fn aborts_downloads_in_flight_when_one_fails() {
+		let dir = temp_dir("download-abort");
+		let mut downloader = Downloader::new(&dir, Client::new());
+		downloader.register_source("fail", FailingSource);
+		downloader.register_source("stall", StallingSource);
+
+		let version = ModVersion {
+			semver: Version::new(1, 0, 0),
+			artifacts: vec![
+				artifact("stall://example/Stalled.dll"),
+				artifact("fail://example/Failed.dll"),
+			],
+			dependencies: ModDependencyMap::new(),
+			conflicts: ModDependencyMap::new(),
+			release_url: None,
+			changelog: None,
+		};
+
+		let cancel = CancellationToken::new();
+		let staged = time::timeout(
+			Duration::from_secs(10),
+			downloader.stage_versions(&[&version], |_progress| {}, &cancel),
+		)
+		.await
+		.expect("stalled download should be aborted");
+		let Err(err) = staged else {
+			panic!("staging should fail");
+		};
+		assert!(!matches!(err, Error::Cancelled), "unexpected error: {err}");
+		assert!(!cancel.is_cancelled());
+		assert!(!dir.join("rml_mods").join("Stalled.dll.new").exists());
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+
+	#[tokio::test]
+	async fn resumes_interrupted_downloads() {
+		let dir = temp_dir("download-resume");
+		let source = InterruptingSource::default();
+		let resumed_from = Arc::clone(&source.resumed_from);
+		let mut downloader = Downloader::new(&dir, Client::new());
+		downloader.register_source("flaky", source);
+		downloader.retry = RetryPolicy::never();
+
+		let url = Url::parse("flaky://example/Mod.dll").expect("parse url");
+		let dest = dir.join("Mod.dll.new");
+		let resume_dest = ModArtifact::resume_dest(&dest).expect("resume destination");
+		let checksum = format!("{:x}", Sha256::digest(b"0123456789"));
+		let cancel = CancellationToken::new();
+
+		// The interrupted download leaves what it got in place, along with how to resume it
+		downloader
+			.download(url.clone(), &dest, &checksum, |_progress| {}, &cancel)
+			.await
+			.expect_err("download should be interrupted");
+		assert_eq!(fs::read(&dest).expect("read partial file"), b"0123");
+		assert_eq!(
+			fs::read_to_string(&resume_dest).expect("read resume info"),
+			"flaky://example/Mod.dll\netag"
+		);
+
+		// The next download picks up where it left off, and the whole file is verified
+		downloader
+			.download(url.clone(), &dest, &checksum, |_progress| {}, &cancel)
+			.await
+			.expect("resume download");
+		assert_eq!(fs::read(&dest).expect("read downloaded file"), b"0123456789");
+		assert!(!resume_dest.exists());
+
+		// A partial download from another URL is started over instead
+		fs::write(&dest, b"0123").expect("write partial file");
+		fs::write(&resume_dest, "flaky://example/Other.dll\netag").expect("write resume info");
+		downloader
+			.download(url, &dest, &checksum, |_progress| {}, &cancel)
+			.await
+			.expect("restart download");
+		assert_eq!(fs::read(&dest).expect("read downloaded file"), b"0123456789");
+		assert_eq!(
+			*resumed_from.lock().expect("lock resumed fetches"),
+			[None, Some(4), None]
+		);
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use url::Url;
+
+use crate::models::ModArtifact;
+use crate::{Error, Result};
+
+/// An alternative location to download artifacts from when their own URLs fail.
+/// Artifacts downloaded from mirrors are verified against their checksums just the same, so mirrors don't need to be
+/// trusted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Mirror {
+	/// Mirror that hosts files at the same paths as their original URLs, under a different base URL.
+	/// For example, with a base of `https://mirror.example/files/`, an artifact at
+	/// `https://github.com/owner/repo/releases/download/v1.0.0/Mod.dll` is looked for at
+	/// `https://mirror.example/files/owner/repo/releases/download/v1.0.0/Mod.dll`.
+	Base(Url),
+
+	/// Mirror that hosts files by their SHA-256 checksums (in lowercase hex), under a base URL.
+	/// For example, with a base of `https://mirror.example/sha256/`, an artifact is looked for at
+	/// `https://mirror.example/sha256/<checksum>`.
+	Hashed(Url),
+}
+
+impl Mirror {
+	/// Builds the URL to download an artifact from on this mirror
+	pub fn url_for(&self, artifact: &ModArtifact) -> Result<Url> {
+		let (base, path) = match self {
+			Self::Base(base) => (base, artifact.url.path().trim_start_matches('/').to_owned()),
+			Self::Hashed(base) => (base, artifact.sha256.to_lowercase()),
+		};
+
+		// Make sure the base is treated as a directory, otherwise its last segment would be replaced
+		let mut base = base.clone();
+		if !base.path().ends_with('/') {
+			base.set_path(&format!("{}/", base.path()));
+		}
+
+		// Prefix the path so that a first segment containing a colon isn't taken as a URL scheme
+		let mut url = base
+			.join(&format!("./{path}"))
+			.map_err(|err| Error::Url(format!("mirror url for {path} on {self}: {err}")))?;
+		if let Self::Base(_) = self {
+			url.set_query(artifact.url.query());
+		}
+		Ok(url)
+	}
+}
+
+impl fmt::Display for Mirror {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Base(base) => write!(f, "{base}"),
+			Self::Hashed(base) => write!(f, "{base} (by checksum)"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use url::Url;
+
+	use super::Mirror;
+	use crate::models::ModArtifact;
+
+	fn artifact(url: &str) -> ModArtifact {
+		ModArtifact {
+			url: Url::parse(url).expect("parse artifact url"),
+			sha256: "ABCDEF0123".to_owned(),
+			filename: None,
+			install_location: None,
+			override_filename: None,
+		}
+	}
+
+	fn mirror_url(mirror: &Mirror, url: &str) -> String {
+		mirror.url_for(&artifact(url)).expect("build mirror url").to_string()
+	}
+
+	#[test]
+	fn joins_artifact_paths_onto_base() {
+		let url = "https://github.com/owner/repo/releases/download/v1.0.0/Mod.dll";
+		let expected = "https://mirror.example/files/owner/repo/releases/download/v1.0.0/Mod.dll";
+
+		let with_slash = Mirror::Base(Url::parse("https://mirror.example/files/").expect("parse base"));
+		assert_eq!(mirror_url(&with_slash, url), expected);
+		let without_slash = Mirror::Base(Url::parse("https://mirror.example/files").expect("parse base"));
+		assert_eq!(mirror_url(&without_slash, url), expected);
+	}
+
+	#[test]
+	fn keeps_artifact_queries() {
+		let mirror = Mirror::Base(Url::parse("https://mirror.example/?ignored=1").expect("parse base"));
+		assert_eq!(
+			mirror_url(&mirror, "https://example.com/download/Mod.dll?version=2"),
+			"https://mirror.example/download/Mod.dll?version=2"
+		);
+	}
+
+	#[test]
+	fn keeps_paths_that_look_like_urls_relative() {
+		let mirror = Mirror::Base(Url::parse("https://mirror.example/files/").expect("parse base"));
+		assert_eq!(
+			mirror_url(&mirror, "https://example.com/c:/Mod%20Name.dll"),
+			"https://mirror.example/files/c:/Mod%20Name.dll"
+		);
+	}
+
+	#[test]
+	fn finds_artifacts_by_checksum() {
+		let mirror = Mirror::Hashed(Url::parse("https://mirror.example/sha256").expect("parse base"));
+		assert_eq!(
+			mirror_url(&mirror, "https://example.com/Mod.dll?version=2"),
+			"https://mirror.example/sha256/abcdef0123"
+		);
+	}
+}
@@ -1,22 +1,61 @@
 pub(crate) mod artifacts;
 mod delete;
 mod download;
+mod mirror;
+mod progress;
+mod recover;
+mod source;
+mod store;
+mod toggle;
+mod transaction;
+mod verify;
 
-use std::path::{Path, PathBuf};
+#[cfg(feature = "db")]
+use std::{
+	cmp::Reverse,
+	time::{SystemTime, UNIX_EPOCH},
+};
+use std::{
+	collections::{BTreeMap, HashMap, HashSet},
+	path::{Path, PathBuf},
+};
 
-use log::debug;
+use log::{debug, error, info, warn};
 use semver::Version;
+#[cfg(feature = "db")]
+use semver::VersionReq;
 use serde::{Deserialize, Serialize};
 use tokio::task;
 
 #[cfg(feature = "db")]
 use crate::db::ResoluteDatabase;
-use crate::models::{self, ResoluteMod, ResoluteModMap};
+use crate::lockfile::Lockfile;
+#[cfg(feature = "db")]
+use crate::models::Snapshot;
+use crate::models::{
+	self, ChannelPolicy, InstallReason, ModArtifact, ModFlag, ModProfile, ModVersion, Platform, ResoluteMod,
+	ResoluteModMap,
+};
+use crate::resolver::{InstallPlan, PlannedInstall, PlannedUninstall, ProfilePlan, Resolver, UninstallPlan};
+use crate::retry::RetryPolicy;
 use crate::{discover, manifest, Error, Result};
 
+pub use self::artifacts::{hash_file, hash_file_blocking};
 pub use self::delete::Deleter;
 pub use self::download::Downloader;
 pub use self::download::DownloaderBuilder;
+pub use self::mirror::Mirror;
+pub use self::progress::{ArtifactProgress, Progress};
+pub use self::recover::RecoveryReport;
+pub use self::source::{ArtifactSource, DirectorySource, Fetched, FileSource, HttpSource, Partial};
+pub use self::store::ArtifactStore;
+pub use self::toggle::Toggler;
+pub use self::transaction::Transaction;
+pub use self::verify::{IntegrityIssue, IntegrityReport};
+pub use tokio_util::sync::CancellationToken;
+
+use self::delete::SetAsideArtifact;
+use self::verify::ExpectedFile;
 
 /// Main entry point for all mod-related operations that need to be persisted
 #[allow(missing_debug_implementations, clippy::module_name_repetitions)]
@@ -25,10 +64,19 @@ pub struct ModManager<#[cfg(feature = "db")] 'a> {
 	pub db: ResoluteDatabase<'a>,
 	pub downloader: Downloader,
 	pub deleter: Deleter,
+	pub toggler: Toggler,
 	base_dest: PathBuf,
 	http_client: reqwest::Client,
+	channels: ChannelPolicy,
+	platform: Option<Platform>,
+	#[cfg(feature = "db")]
+	snapshot_limit: usize,
 }
 
+/// Number of snapshots a [`ModManager`] keeps by default
+#[cfg(feature = "db")]
+pub const DEFAULT_SNAPSHOT_LIMIT: usize = 20;
+
 macro_rules! impl_ModManager_with_without_db {
 	{ impl ModManager $implementations:tt } => {
 		#[cfg(feature = "db")]
@@ -55,8 +103,13 @@ impl_ModManager_with_without_db! {
 					.http_client(http_client.clone())
 					.build(),
 				deleter: Deleter::new(&base_dest),
+				toggler: Toggler::new(&base_dest),
 				http_client,
 				base_dest: base_dest.as_ref().to_path_buf(),
+				channels: ChannelPolicy::default(),
+				platform: None,
+				#[cfg(feature = "db")]
+				snapshot_limit: DEFAULT_SNAPSHOT_LIMIT,
 			}
 		}
 
@@ -72,7 +125,15 @@ impl_ModManager_with_without_db! {
 					.map(|rmod| (rmod.id.clone(), rmod))
 					.collect();
 
-				Ok(LoadedMods { mods, removed: None })
+				let unsupported = self.unsupported_mods(&mods);
+				let flagged = flagged_mods(&mods);
+				Ok(LoadedMods {
+					mods,
+					removed: None,
+					unsupported,
+					flagged,
+					newly_flagged: Vec::new(),
+				})
 			})?;
 			Ok(mods)
 		}
@@ -99,11 +160,83 @@ impl_ModManager_with_without_db! {
 			.await??;
 
 			#[cfg(feature = "db")]
-			let removed = self.mark_installed_mods(&mut mods).await?;
+			let (removed, newly_flagged) = (
+				self.mark_installed_mods(&mut mods).await?,
+				self.refresh_installed_flags(&mods).await?,
+			);
 			#[cfg(not(feature = "db"))]
-			let removed = None;
+			let (removed, newly_flagged) = (None, Vec::new());
 
-			Ok(LoadedMods { mods, removed })
+			let unsupported = self.unsupported_mods(&mods);
+			let flagged = flagged_mods(&mods);
+			Ok(LoadedMods {
+				mods,
+				removed,
+				unsupported,
+				flagged,
+				newly_flagged,
+			})
+		}
+
+		/// Updates the stored flags of installed mods to match those in a freshly loaded mod map, warning about any
+		/// installed mods that have become deprecated or vulnerable since they were stored.
+		/// Returns the IDs of those mods, sorted.
+		#[cfg(feature = "db")]
+		#[allow(clippy::unused_async)]
+		async fn refresh_installed_flags(&self, mods: &ResoluteModMap) -> Result<Vec<String>> {
+			task::block_in_place(|| {
+				let mut newly_flagged = Vec::new();
+
+				for mut installed in self.db.get_installed_mods()? {
+					let Some(rmod) = mods.get(&installed.id) else {
+						continue;
+					};
+					if rmod.flags == installed.flags {
+						continue;
+					}
+
+					// Warn about any flags that need attention which weren't present before
+					let old_flags = installed.parsed_flags();
+					let new_flags: Vec<ModFlag> = rmod
+						.parsed_flags()
+						.into_iter()
+						.filter(|flag| flag.needs_confirmation() && !old_flags.contains(flag))
+						.collect();
+					if !new_flags.is_empty() {
+						let text = new_flags.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+						warn!("Installed mod {} has been newly flagged as {}", rmod, text);
+						newly_flagged.push(rmod.id.clone());
+					}
+
+					// Store the new flags so the same flags aren't reported again
+					installed.flags.clone_from(&rmod.flags);
+					self.db.store_mod(installed)?;
+				}
+
+				newly_flagged.sort_unstable();
+				Ok(newly_flagged)
+			})
+		}
+
+		/// Finds all mods in a map that don't support the manager's target platform (if it's known), warning about any
+		/// of them that are installed. Returns their IDs, sorted.
+		fn unsupported_mods(&self, mods: &ResoluteModMap) -> Vec<String> {
+			let Some(platform) = self.platform else {
+				return Vec::new();
+			};
+
+			let mut unsupported: Vec<String> = mods
+				.values()
+				.filter(|rmod| !rmod.supports_platform(platform))
+				.inspect(|rmod| {
+					if let Some(semver) = &rmod.installed_version {
+						warn!("Installed mod {} v{} doesn't support the {} platform", rmod, semver, platform);
+					}
+				})
+				.map(|rmod| rmod.id.clone())
+				.collect();
+			unsupported.sort_unstable();
+			unsupported
 		}
 
 		/// Fills in the installed_version field for all mods in a map that are installed and
@@ -113,7 +246,7 @@ impl_ModManager_with_without_db! {
 		pub async fn mark_installed_mods(&self, mods: &mut ResoluteModMap) -> Result<Option<ResoluteModMap>> {
 			use std::{collections::HashSet, ffi::OsString};
 
-			use crate::models::{ModArtifact, ModVersion};
+			use crate::models::{InstallReason, ModArtifact, ModVersion, UNRECOGNIZED_SEMVER};
 
 			// Load the installed mods
 			let LoadedMods {
@@ -128,9 +261,12 @@ impl_ModManager_with_without_db! {
 
 			for (id, rmod) in mods.iter_mut() {
 				if let Some(installed) = installed_mods.get(id) {
-					// Set the installed version from the stored mod
+					// Set the installed version, install reason, pin, and active state from the stored mod
 					let semver = installed.installed_version.clone();
 					rmod.installed_version.clone_from(&semver);
+					rmod.install_reason = installed.install_reason;
+					rmod.pin.clone_from(&installed.pin);
+					rmod.active = installed.active;
 
 					// Add the version to the mod's version map if it doesn't have it
 					if let Some(semver) = semver {
@@ -149,7 +285,7 @@ impl_ModManager_with_without_db! {
 
 				if !unrecognized_mods.is_empty() {
 					// Get the latest version of the known mod
-					let Some(latest_version) = rmod.latest_version() else {
+					let Some(latest_version) = rmod.latest_version(&self.channels) else {
 						continue;
 					};
 
@@ -164,13 +300,13 @@ impl_ModManager_with_without_db! {
 					let unrecognized_matches: Vec<&ResoluteMod> = unrecognized_mods
 						.iter()
 						.filter(|umod| {
-							// Get the latest version of the unrecognized mod
-							let Some(latest_version) = umod.latest_version() else {
+							// Get the version of the unrecognized mod
+							let Some(unrecognized_version) = umod.versions.get(&UNRECOGNIZED_SEMVER) else {
 								return false;
 							};
 
 							// Build a set of artifact filenames for the unrecognized mod
-							let artifact_names: HashSet<OsString> = latest_version
+							let artifact_names: HashSet<OsString> = unrecognized_version
 								.artifacts
 								.iter()
 								.map(ModArtifact::usable_filename)
@@ -190,8 +326,12 @@ impl_ModManager_with_without_db! {
 					let unrecognized_artifacts: Vec<ModArtifact> = unrecognized_matches
 						.iter()
 						.flat_map(|umod| {
-							let latest_version = umod.latest_version().expect("no latest version for unrecognized mod");
-							latest_version.artifacts.iter().cloned()
+							umod.versions
+								.get(&UNRECOGNIZED_SEMVER)
+								.expect("no version for unrecognized mod")
+								.artifacts
+								.iter()
+								.cloned()
 						})
 						.collect();
 					let unrecognized_version = ModVersion::new_unrecognized_with_artifacts(unrecognized_artifacts);
@@ -200,6 +340,7 @@ impl_ModManager_with_without_db! {
 					let semver = unrecognized_version.semver.clone();
 					rmod.versions.insert(semver.clone(), unrecognized_version);
 					rmod.installed_version = Some(semver);
+					rmod.install_reason = Some(InstallReason::Explicit);
 
 					// Replace the unrecognized mods with the known mod in the database
 					task::block_in_place(|| -> Result<()> {
@@ -228,80 +369,1173 @@ impl_ModManager_with_without_db! {
 			})
 		}
 
-		/// Installs a mod, and if the "db" feature is active, stores it as installed in the database
+		/// Installs a mod along with any dependencies it needs, and if the "db" feature is active, stores them as
+		/// installed in the database. Dependencies are resolved against the given mod map.
+		/// Returns the plan that was carried out.
 		pub async fn install_mod(
 			&self,
+			mods: &ResoluteModMap,
 			rmod: &ResoluteMod,
 			version: impl AsRef<str>,
-			progress: impl Fn(u64, u64),
-		) -> Result<()> {
-			// Determine the version to install
+			options: &InstallOptions,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<InstallPlan> {
+			// Resolve the dependencies of the version to install
+			progress(Progress::Resolving);
 			let semver = Version::parse(version.as_ref())?;
-			let version = rmod
-				.versions
-				.get(&semver)
-				.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), semver))?;
+			let resolver = Resolver::new(mods, &self.channels);
+			let plan = resolver.resolve(rmod, &semver)?;
 
-			// Download the version and add the mod to the database
-			self.downloader.download_version(version, progress).await?;
-			#[cfg(feature = "db")]
-			{
-				let mut rmod = rmod.clone();
-				rmod.installed_version = Some(version.semver.clone());
-				task::block_in_place(|| self.db.store_mod(rmod))?;
+			// Refuse to install anything that would conflict with the installed mods
+			let installed = self.installed_mods_within(mods).await?;
+			let conflicts = resolver.conflicts(Some(rmod), &plan, &installed);
+			if !conflicts.is_empty() {
+				warn!("Refusing to install mod {} v{} due to conflicts: {}", rmod, semver, conflicts);
+				return Err(Error::Conflicts(Box::new(conflicts)));
 			}
 
-			Ok(())
+			// Install everything in the plan
+			self.check_plan(mods, Some(rmod), &plan, options)?;
+			self.apply_plan(mods, Some(rmod), &plan, progress, cancel).await?;
+			Ok(plan)
 		}
 
-		/// Installs a new version of a mod and removes any remaining artifacts from the previous version
+		/// Installs a new version of a mod (along with any dependencies it needs) and removes any remaining artifacts
+		/// from the previous version. Returns the plan that was carried out.
 		pub async fn update_mod(
 			&self,
+			mods: &ResoluteModMap,
 			rmod: &ResoluteMod,
 			version: impl AsRef<str>,
-			progress: impl Fn(u64, u64),
+			options: &InstallOptions,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<InstallPlan> {
+			// Ensure the mod is actually installed
+			if rmod.installed_version.is_none() {
+				return Err(Error::ModNotInstalled(Box::new(rmod.clone())));
+			}
+
+			// Install the new version - the plan will take care of removing any left over artifacts
+			self.install_mod(mods, rmod, version, options, progress, cancel).await
+		}
+
+		/// Plans an update of every installed mod in the given mod map to the newest versions that are compatible with
+		/// each other, without changing anything. The plan can be reviewed and then carried out with [`Self::apply_plan`].
+		pub async fn plan_update_all(&self, mods: &ResoluteModMap) -> Result<InstallPlan> {
+			let resolver = Resolver::new(mods, &self.channels);
+			let plan = resolver.resolve_update_all()?;
+
+			// The resolver avoids conflicts with the mods it knows about, but double check against the installed mods
+			let installed = self.installed_mods_within(mods).await?;
+			let conflicts = resolver.conflicts(None, &plan, &installed);
+			if !conflicts.is_empty() {
+				warn!("Refusing to update all mods due to conflicts: {}", conflicts);
+				return Err(Error::Conflicts(Box::new(conflicts)));
+			}
+
+			Ok(plan)
+		}
+
+		/// Updates every installed mod in the given mod map to the newest versions that are compatible with each other.
+		/// Returns the plan that was carried out.
+		pub async fn update_all_mods(
+			&self,
+			mods: &ResoluteModMap,
+			options: &InstallOptions,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<InstallPlan> {
+			progress(Progress::Resolving);
+			let plan = self.plan_update_all(mods).await?;
+			if plan.is_empty() {
+				info!("All mods are already up-to-date");
+				return Ok(plan);
+			}
+
+			self.check_plan(mods, None, &plan, options)?;
+			self.apply_plan(mods, None, &plan, progress, cancel).await?;
+			Ok(plan)
+		}
+
+		/// Checks whether an install plan is allowed to be carried out with the given options.
+		/// Plans that would newly install deprecated or vulnerable mods, or install mods which don't support the
+		/// manager's target platform, are refused unless that's explicitly allowed. If a mod is given, it takes
+		/// precedence over any entry for the same ID in the mod map.
+		pub fn check_plan(
+			&self,
+			mods: &ResoluteModMap,
+			rmod: Option<&ResoluteMod>,
+			plan: &InstallPlan,
+			options: &InstallOptions,
+		) -> Result<()> {
+			let planned_mods: Vec<(&PlannedInstall, &ResoluteMod)> = plan
+				.steps
+				.iter()
+				.filter_map(|step| match rmod {
+					Some(rmod) if rmod.id == step.id => Some((step, rmod)),
+					_ => Some((step, mods.get(&step.id)?)),
+				})
+				.collect();
+
+			// Refuse to newly install any mods with flags that need confirmation unless it's been given
+			let flagged: BTreeMap<String, Vec<ModFlag>> = planned_mods
+				.iter()
+				.filter(|(step, _)| step.replaces.is_none())
+				.filter_map(|(_, planned)| {
+					let flags: Vec<ModFlag> = planned
+						.parsed_flags()
+						.into_iter()
+						.filter(ModFlag::needs_confirmation)
+						.collect();
+					(!flags.is_empty()).then(|| (planned.id.clone(), flags))
+				})
+				.collect();
+			if !flagged.is_empty() {
+				if !options.allow_flagged {
+					warn!("Refusing to install flagged mods without confirmation: {:?}", flagged);
+					return Err(Error::Flagged {
+						flagged: Box::new(flagged),
+					});
+				}
+
+				warn!("Installing flagged mods as explicitly confirmed: {:?}", flagged);
+			}
+
+			if let Some(platform) = self.platform {
+				let unsupported: Vec<String> = planned_mods
+					.iter()
+					.filter(|(_, planned)| !planned.supports_platform(platform))
+					.map(|(_, planned)| planned.id.clone())
+					.collect();
+
+				if !unsupported.is_empty() {
+					if !options.allow_unsupported_platform {
+						warn!("Refusing to install mods that don't support the {} platform: {:?}", platform, unsupported);
+						return Err(Error::UnsupportedPlatform {
+							platform,
+							ids: unsupported,
+						});
+					}
+
+					warn!(
+						"Installing mods that don't support the {} platform as explicitly allowed: {:?}",
+						platform, unsupported
+					);
+				}
+			}
+
+			Ok(())
+		}
+
+		/// Carries out an install plan as a single operation.
+		/// The artifacts for every step are downloaded before any of them are put in place, and if anything fails, all
+		/// of them are reverted. Any step that replaces an installed version then removes the artifacts left over from
+		/// the old version. If a mod is given, it's marked as explicitly installed and takes precedence over any entry
+		/// for the same ID in the mod map.
+		pub async fn apply_plan(
+			&self,
+			mods: &ResoluteModMap,
+			rmod: Option<&ResoluteMod>,
+			plan: &InstallPlan,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
 		) -> Result<()> {
-			// Ensure the mod is actually installed and determine which version
-			let old_version = {
-				let Some(version) = &rmod.installed_version else {
-					return Err(Error::ModNotInstalled(Box::new(rmod.clone())));
+			#[cfg(feature = "db")]
+			let snapshot = self.capture_snapshot(format!("Install {plan}"))?;
+
+			let explicit: Vec<&str> = rmod.iter().map(|rmod| rmod.id.as_str()).collect();
+			self.apply_plan_marking(mods, rmod, plan, &explicit, progress, cancel)
+				.await?;
+
+			#[cfg(feature = "db")]
+			self.record_snapshot(snapshot).await;
+			Ok(())
+		}
+
+		/// Carries out an install plan as a single operation (see [`Self::apply_plan`]), marking the mods with the given
+		/// IDs as explicitly installed
+		async fn apply_plan_marking(
+			&self,
+			mods: &ResoluteModMap,
+			rmod: Option<&ResoluteMod>,
+			plan: &InstallPlan,
+			explicit: &[&str],
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<()> {
+			let transaction = Self::plan_transaction(mods, rmod, plan, explicit)?;
+			info!("Installing plan {}", plan);
+			self.commit(&transaction, progress, cancel).await
+		}
+
+		/// Builds a transaction that carries out an install plan, marking the mods with the given IDs as explicitly
+		/// installed. If a mod is given, it takes precedence over any entry for the same ID in the mod map.
+		fn plan_transaction(
+			mods: &ResoluteModMap,
+			rmod: Option<&ResoluteMod>,
+			plan: &InstallPlan,
+			explicit: &[&str],
+		) -> Result<Transaction> {
+			let mut transaction = Transaction::new();
+			for step in &plan.steps {
+				let step_mod = match rmod {
+					Some(rmod) if rmod.id == step.id => rmod,
+					_ => mods
+						.get(&step.id)
+						.ok_or_else(|| Error::UnknownMod(step.id.clone()))?,
 				};
-				rmod.versions
-					.get(version)
-					.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), version.to_owned()))?
+				transaction.install(step_mod, &step.version, explicit.contains(&step.id.as_str()))?;
+			}
+
+			Ok(transaction)
+		}
+
+		/// Carries out a transaction as a single operation. The artifacts of every install are downloaded before any of
+		/// them are put in place, leftover artifacts from replaced and uninstalled versions are set aside, artifacts
+		/// are renamed to or from their disabled names as needed, and if the "db" feature is active, every database
+		/// change is made in a single write transaction. If any part fails, everything that was already done is undone.
+		/// The set aside artifacts are only discarded once everything else has succeeded.
+		pub async fn commit(
+			&self,
+			transaction: &Transaction,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<()> {
+			if transaction.is_empty() {
+				debug!("Nothing to commit in transaction");
+				return Ok(());
+			}
+			info!("Committing transaction {}", transaction);
+
+			// Download all of the new versions, then put them in place together
+			let versions: Vec<&ModVersion> = transaction.installs.iter().map(|install| &install.version).collect();
+			let downloaded = self.downloader.stage_versions(&versions, &progress, cancel).await?;
+			if cancel.is_cancelled() {
+				info!("Canceled transaction {}", transaction);
+				download::cancel_artifacts(downloaded).await;
+				return Err(Error::Cancelled);
+			}
+			progress(Progress::Finalizing {
+				count: downloaded.len(),
+			});
+			let finalized = self.downloader.finalize_artifacts(downloaded).await?;
+			if cancel.is_cancelled() {
+				info!("Canceled transaction {}, undoing", transaction);
+				download::undo_artifacts(finalized).await;
+				return Err(Error::Cancelled);
+			}
+
+			// Move any artifacts that are no longer needed out of the way, then disable or enable anything that needs it
+			#[cfg_attr(not(feature = "db"), allow(unused_variables))]
+			let (set_aside, toggled) = match self.rearrange_artifacts(transaction, &progress).await {
+				Ok(rearranged) => rearranged,
+				Err(err) => {
+					error!("Error committing transaction, undoing: {}", err);
+					download::undo_artifacts(finalized).await;
+					return Err(err);
+				}
 			};
 
-			// Determine the new version to install
-			let semver = Version::parse(version.as_ref())?;
-			let new_version = rmod
-				.versions
-				.get(&semver)
-				.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), semver))?;
-
-			// Install the new version and remove any left over artifacts
-			self.install_mod(rmod, new_version.semver.to_string(), progress).await?;
-			self.deleter
-				.delete_artifacts_diff(&old_version.artifacts, &new_version.artifacts)
-				.await?;
+			// Record every change in the database at once
+			#[cfg(feature = "db")]
+			{
+				progress(Progress::WritingDatabase);
+				if let Err(err) = self.store_transaction(transaction) {
+					error!("Error committing transaction to the database, undoing: {}", err);
+					self.revert_renames(toggled).await;
+					delete::restore_artifacts(set_aside).await;
+					download::undo_artifacts(finalized).await;
+					return Err(err);
+				}
+			}
+
+			// Everything is in place, so the replaced and left over artifacts can go
+			progress(Progress::DeletingOldFiles);
+			self.downloader.discard_old_artifacts(finalized).await;
+			for artifact in set_aside {
+				let path = artifact.path.clone();
+				if let Err(err) = artifact.discard(self.deleter.store.as_ref()).await {
+					error!("Error discarding left over artifact ({}): {}", path.display(), err);
+				}
+			}
+
+			if let Err(err) = self.prune_artifact_store().await {
+				warn!("Error pruning artifact store: {}", err);
+			}
+
+			info!("Committed transaction {}", transaction);
+			Ok(())
+		}
+
+		/// Recovers from operations that were interrupted (such as by a crash) partway through changing artifact files.
+		/// Every install location is scanned for leftover temporary and old artifact files, and each interrupted change
+		/// is either completed or rolled back depending on which of the files matches the installed mods. Installed mods
+		/// are taken from the database if the "db" feature is active, otherwise from the given mod map. The checksums and
+		/// paths of every artifact in the mod map are used to tell complete downloads apart from partial ones, and
+		/// leftover artifact files apart from the user's own files, which are reported as unresolved and left alone.
+		/// Any temporary files left in the artifact store are deleted as well.
+		pub async fn recover_interrupted(&self, mods: &ResoluteModMap) -> Result<RecoveryReport> {
+			let installed = self.installed_mods_within(mods).await?;
+
+			// Determine which file should be at each installed artifact's path
+			let mut expected = HashMap::new();
+			for rmod in installed.values() {
+				let Ok(version) = Self::installed_version_of(rmod) else {
+					continue;
+				};
+				for artifact in &version.artifacts {
+					let path = artifact.dest_within(&self.base_dest)?;
+					let path = if rmod.active {
+						path
+					} else {
+						ModArtifact::disabled_dest(&path)?
+					};
+					expected.insert(path, artifact.sha256.to_lowercase());
+				}
+			}
+
+			// Gather every known artifact's checksum, paths and install location
+			let mut known = recover::KnownArtifacts::default();
+			let mut dirs = HashSet::new();
+			let artifacts = mods
+				.values()
+				.chain(installed.values())
+				.flat_map(|rmod| rmod.versions.values())
+				.flat_map(|version| &version.artifacts);
+			for artifact in artifacts {
+				let path = artifact.dest_within(&self.base_dest)?;
+				if let Some(dir) = path.parent() {
+					dirs.insert(dir.to_owned());
+				}
+				known.checksums.insert(artifact.sha256.to_lowercase());
+				known.paths.insert(ModArtifact::disabled_dest(&path)?);
+				known.paths.insert(path);
+			}
+
+			info!("Recovering from interrupted operations in {} directories", dirs.len());
+			let report = recover::recover_dirs(&dirs, &expected, &known, self.deleter.store.as_ref()).await?;
+			if report.is_empty() {
+				debug!("Nothing to recover from interrupted operations");
+			} else {
+				info!("Recovered from interrupted operations ({})", report);
+			}
+
+			if let Some(store) = &self.deleter.store {
+				if let Err(err) = store.remove_temporary_files().await {
+					warn!("Error removing temporary files from artifact store: {}", err);
+				}
+			}
+
+			Ok(report)
+		}
+
+		/// Verifies the files of every installed mod by comparing them against the checksums of their artifacts, reporting
+		/// any that are missing or modified, copies of them at other paths, and any other DLL files in the install
+		/// locations that no installed mod accounts for. Installed mods are taken from the database if the "db" feature
+		/// is active, otherwise from the given mod map. Nothing is changed on disk.
+		pub async fn verify_installed(&self, mods: &ResoluteModMap) -> Result<IntegrityReport> {
+			let installed = self.installed_mods_within(mods).await?;
+
+			// Determine which file should be at each installed artifact's path and which directories to check
+			let mut expected = Vec::new();
+			let mut dirs = HashSet::new();
+			for rmod in installed.values() {
+				let version = match Self::installed_version_of(rmod) {
+					Ok(version) => version,
+					Err(err) => {
+						warn!("Unable to verify the files of installed mod {}: {}", rmod, err);
+						continue;
+					}
+				};
+				for artifact in &version.artifacts {
+					let path = artifact.dest_within(&self.base_dest)?;
+					if let Some(dir) = path.parent() {
+						dirs.insert(dir.to_owned());
+					}
+					let path = if rmod.active {
+						path
+					} else {
+						ModArtifact::disabled_dest(&path)?
+					};
+					expected.push(ExpectedFile {
+						id: &rmod.id,
+						path,
+						sha256: artifact.sha256.to_lowercase(),
+					});
+				}
+			}
+
+			info!(
+				"Verifying {} artifact files of {} installed mods",
+				expected.len(),
+				installed.len()
+			);
+			let report = verify::verify_files(&expected, &dirs, &self.base_dest).await?;
+			if report.is_empty() {
+				info!("Verified the files of all installed mods without finding any problems");
+			} else {
+				warn!("Found problems with the files of installed mods: {}", report);
+			}
+
+			Ok(report)
+		}
+
+		/// Repairs the files of installed mods by verifying them with [`Self::verify_installed`], then downloading any
+		/// missing or modified artifacts again as a single transaction (see [`Self::commit`]). Disabled mods' artifacts
+		/// are put back with their disabled names. Copies of artifacts at other paths are only discarded if requested,
+		/// since they may be deliberate copies, and extra files aren't touched, since they may belong to mods that were
+		/// installed by other means. Returns the report of the problems that were found before repairing.
+		pub async fn repair(
+			&self,
+			mods: &ResoluteModMap,
+			remove_duplicates: bool,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<IntegrityReport> {
+			let report = self.verify_installed(mods).await?;
+			if report.mods.is_empty() {
+				debug!("No installed mod files to repair");
+				return Ok(report);
+			}
+			let installed = self.installed_mods_within(mods).await?;
+
+			// Stage downloading only the artifacts that are broken again
+			let mut transaction = Transaction::new();
+			for (id, rmod) in &installed {
+				let broken_paths: HashSet<&Path> = report.broken_paths(id).collect();
+				if broken_paths.is_empty() {
+					continue;
+				}
+				let Ok(version) = Self::installed_version_of(rmod) else {
+					continue;
+				};
+
+				let mut broken = Vec::new();
+				for artifact in &version.artifacts {
+					let path = artifact.dest_within(&self.base_dest)?;
+					let path = if rmod.active {
+						path
+					} else {
+						ModArtifact::disabled_dest(&path)?
+					};
+					if broken_paths.contains(path.as_path()) {
+						broken.push(artifact.clone());
+					}
+				}
+				info!("Repairing {} artifact files of mod {} v{}", broken.len(), rmod, version);
+				transaction.reinstall_artifacts(rmod, broken)?;
+			}
+			self.commit(&transaction, &progress, cancel).await?;
+
+			// Deal with any copies of artifacts at other paths
+			for issue in report.mods.values().flatten() {
+				if let IntegrityIssue::Duplicate { path, .. } = issue {
+					if remove_duplicates {
+						artifacts::discard(path, self.deleter.store.as_ref()).await?;
+						info!("Discarded duplicate artifact file {}", path.display());
+					} else {
+						info!("Leaving duplicate artifact file {} alone", path.display());
+					}
+				}
+			}
+
+			info!("Repaired the files of installed mods");
+			Ok(report)
+		}
+
+		/// Sets aside the artifacts of a transaction's replaced and uninstalled versions that are no longer needed
+		async fn set_aside_leftovers(&self, transaction: &Transaction) -> Result<Vec<SetAsideArtifact>> {
+			let mut paths = Vec::new();
+			for install in &transaction.installs {
+				if let Some(old_version) = &install.replaces {
+					paths.extend(
+						self.deleter
+							.leftover_paths(&old_version.artifacts, &install.version.artifacts)?,
+					);
+				}
+			}
+			for uninstall in &transaction.uninstalls {
+				paths.extend(self.deleter.leftover_paths(&uninstall.version.artifacts, &[])?);
+			}
+
+			self.deleter.set_aside(paths).await
+		}
+
+		/// Sets aside the leftover artifacts of a transaction (see [`Self::set_aside_leftovers`]), then renames artifacts
+		/// to or from their disabled names as needed (see [`Self::toggle_staged`]). If renaming fails, the set aside
+		/// artifacts are restored.
+		async fn rearrange_artifacts<'t>(
+			&self,
+			transaction: &'t Transaction,
+			progress: impl Fn(Progress),
+		) -> Result<(Vec<SetAsideArtifact>, Vec<(&'t ModVersion, bool)>)> {
+			if !transaction.uninstalls.is_empty() {
+				progress(Progress::Removing {
+					count: transaction.uninstalls.len(),
+				});
+			}
+			let set_aside = self.set_aside_leftovers(transaction).await?;
+			match self.toggle_staged(transaction).await {
+				Ok(toggled) => Ok((set_aside, toggled)),
+				Err(err) => {
+					delete::restore_artifacts(set_aside).await;
+					Err(err)
+				}
+			}
+		}
+
+		/// Records every mod change of a transaction in the database in a single write transaction
+		#[cfg(feature = "db")]
+		fn store_transaction(&self, transaction: &Transaction) -> Result<()> {
+			let store = transaction
+				.installs
+				.iter()
+				.map(|install| install.rmod.clone())
+				.chain(transaction.changes.iter().map(|change| change.rmod.clone()))
+				.collect();
+			let remove: Vec<String> = transaction
+				.uninstalls
+				.iter()
+				.map(|uninstall| uninstall.rmod.id.clone())
+				.collect();
+			task::block_in_place(|| self.db.commit_mod_changes(store, &remove))
+		}
+
+		/// Renames the artifacts of a transaction's installs and changes to or from their disabled names to match the
+		/// enabled state they'll be stored with. If any of them can't be renamed, the ones that were are renamed back.
+		/// Returns the versions that were renamed, along with whether they were enabled or disabled.
+		async fn toggle_staged<'t>(&self, transaction: &'t Transaction) -> Result<Vec<(&'t ModVersion, bool)>> {
+			let installs = transaction
+				.installs
+				.iter()
+				.filter(|install| !install.rmod.active)
+				.map(|install| (&install.version, false));
+			let changes = transaction
+				.changes
+				.iter()
+				.filter(|change| change.rmod.active != change.was_active)
+				.map(|change| (&change.version, change.rmod.active));
+
+			let mut toggled = Vec::new();
+			for (version, active) in installs.chain(changes) {
+				let result = if active {
+					self.toggler.enable_version(version).await
+				} else {
+					self.toggler.disable_version(version).await
+				};
+				if let Err(err) = result {
+					self.revert_renames(toggled).await;
+					return Err(err);
+				}
+				toggled.push((version, active));
+			}
+
+			Ok(toggled)
+		}
+
+		/// Renames versions' artifacts that were enabled or disabled back to how they were, in reverse order
+		async fn revert_renames(&self, toggled: Vec<(&ModVersion, bool)>) {
+			for (version, active) in toggled.into_iter().rev() {
+				let result = if active {
+					self.toggler.disable_version(version).await
+				} else {
+					self.toggler.enable_version(version).await
+				};
+				if let Err(err) = result {
+					error!("Error undoing rename of artifacts for version {}: {}", version, err);
+				}
+			}
+		}
+
+		/// Pins an installed mod to a version requirement, preventing it from being updated or changed by dependency
+		/// resolution to any version the requirement doesn't allow
+		#[cfg(feature = "db")]
+		#[allow(clippy::unused_async)]
+		pub async fn pin_mod(&self, rmod: &ResoluteMod, pin: VersionReq) -> Result<()> {
+			let Some(installed) = &rmod.installed_version else {
+				return Err(Error::ModNotInstalled(Box::new(rmod.clone())));
+			};
+			if !pin.matches(installed) {
+				warn!("Pinning mod {} to {}, which doesn't allow its installed version v{}", rmod, pin, installed);
+			}
+
+			task::block_in_place(|| self.db.set_mod_pin(&rmod.id, Some(pin)))
+		}
 
+		/// Removes the version pin from an installed mod
+		#[cfg(feature = "db")]
+		#[allow(clippy::unused_async)]
+		pub async fn unpin_mod(&self, rmod: &ResoluteMod) -> Result<()> {
+			if rmod.installed_version.is_none() {
+				return Err(Error::ModNotInstalled(Box::new(rmod.clone())));
+			}
+
+			task::block_in_place(|| self.db.set_mod_pin(&rmod.id, None))
+		}
+
+		/// Disables an installed mod without uninstalling it by renaming all of its installed version's artifacts to
+		/// their disabled names, and if the "db" feature is active, marks it as inactive in the database.
+		/// If any of the artifacts can't be renamed, the ones that were are renamed back.
+		pub async fn disable_mod(&self, rmod: &ResoluteMod) -> Result<()> {
+			let version = Self::installed_version_of(rmod)?;
+
+			// Warn about any enabled mods that depend on the mod, since they probably won't work without it
+			#[cfg(feature = "db")]
+			{
+				let LoadedMods { mods: installed, .. } = self.get_installed_mods().await?;
+				let dependents: Vec<String> = Resolver::new(&installed, &self.channels)
+					.dependents(&rmod.id)
+					.into_iter()
+					.filter(|dependent| dependent.active)
+					.map(|dependent| dependent.id.clone())
+					.collect();
+				if !dependents.is_empty() {
+					warn!("Disabling mod {} that enabled mods depend on: {:?}", rmod, dependents);
+				}
+			}
+
+			#[cfg(feature = "db")]
+			let snapshot = self.capture_snapshot(format!("Disable {rmod}"))?;
+
+			info!("Disabling mod {} v{}", rmod, version);
+			self.set_mod_active(rmod, version, false).await?;
+
+			#[cfg(feature = "db")]
+			self.record_snapshot(snapshot).await;
 			Ok(())
 		}
 
-		/// Uninstalls a mod's installed version
-		pub async fn uninstall_mod(&self, rmod: &ResoluteMod) -> Result<()> {
-			// Ensure the mod is actually installed and determine which version
-			let installed_version = match &rmod.installed_version {
-				Some(version) => rmod
-					.versions
-					.get(version)
-					.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), version.clone()))?,
-				None => return Err(Error::ModNotInstalled(Box::new(rmod.clone()))),
+		/// Enables a disabled mod by renaming all of its installed version's artifacts back to their normal names, and if
+		/// the "db" feature is active, marks it as active in the database.
+		/// If any of the artifacts can't be renamed, the ones that were are renamed back.
+		pub async fn enable_mod(&self, rmod: &ResoluteMod) -> Result<()> {
+			let version = Self::installed_version_of(rmod)?;
+
+			#[cfg(feature = "db")]
+			let snapshot = self.capture_snapshot(format!("Enable {rmod}"))?;
+
+			info!("Enabling mod {} v{}", rmod, version);
+			self.set_mod_active(rmod, version, true).await?;
+
+			#[cfg(feature = "db")]
+			self.record_snapshot(snapshot).await;
+			Ok(())
+		}
+
+		/// Renames a mod version's artifacts to or from their disabled names, and if the "db" feature is active, stores
+		/// the mod's new state in the database (renaming the artifacts back if that fails)
+		#[cfg_attr(not(feature = "db"), allow(unused_variables))]
+		async fn set_mod_active(&self, rmod: &ResoluteMod, version: &ModVersion, active: bool) -> Result<()> {
+			if active {
+				self.toggler.enable_version(version).await?;
+			} else {
+				self.toggler.disable_version(version).await?;
+			}
+
+			#[cfg(feature = "db")]
+			if let Err(err) = task::block_in_place(|| self.db.set_mod_active(&rmod.id, active)) {
+				let undo = if active {
+					self.toggler.disable_version(version).await
+				} else {
+					self.toggler.enable_version(version).await
+				};
+				if let Err(undo_err) = undo {
+					error!("Error undoing rename of artifacts for mod {}: {}", rmod, undo_err);
+				}
+				return Err(err);
+			}
+
+			Ok(())
+		}
+
+		/// Gets the installed version of a mod, failing if it isn't installed
+		fn installed_version_of(rmod: &ResoluteMod) -> Result<&ModVersion> {
+			let Some(semver) = &rmod.installed_version else {
+				return Err(Error::ModNotInstalled(Box::new(rmod.clone())));
+			};
+			rmod.versions
+				.get(semver)
+				.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), semver.clone()))
+		}
+
+		/// Gets all mod profiles stored in the database
+		#[cfg(feature = "db")]
+		#[allow(clippy::unused_async)]
+		pub async fn get_profiles(&self) -> Result<Vec<ModProfile>> {
+			task::block_in_place(|| self.db.get_profiles())
+		}
+
+		/// Gets a mod profile stored in the database by its name
+		#[cfg(feature = "db")]
+		#[allow(clippy::unused_async)]
+		pub async fn get_profile(&self, name: impl AsRef<str>) -> Result<ModProfile> {
+			let name = name.as_ref();
+			task::block_in_place(|| self.db.get_profile(name))?.ok_or_else(|| Error::ItemNotFound(name.to_owned()))
+		}
+
+		/// Stores a mod profile in the database, replacing any existing profile with the same name
+		#[cfg(feature = "db")]
+		#[allow(clippy::unused_async)]
+		pub async fn store_profile(&self, profile: ModProfile) -> Result<()> {
+			task::block_in_place(|| self.db.store_profile(profile))
+		}
+
+		/// Removes a mod profile from the database by its name
+		#[cfg(feature = "db")]
+		#[allow(clippy::unused_async)]
+		pub async fn remove_profile(&self, name: impl AsRef<str>) -> Result<()> {
+			task::block_in_place(|| self.db.remove_profile(name))
+		}
+
+		/// Plans switching the installed mods over to a profile without changing anything. The plan can be reviewed and
+		/// then carried out with [`Self::apply_profile`]. Installed mods are taken from the database if the "db" feature
+		/// is active, otherwise from the given mod map.
+		pub async fn plan_profile(&self, mods: &ResoluteModMap, profile: &ModProfile) -> Result<ProfilePlan> {
+			let (_, _, plan) = self.resolve_profile(mods, profile).await?;
+			Ok(plan)
+		}
+
+		/// Switches the installed mods over to a profile: mods in the profile (and their dependencies) are installed,
+		/// updated or enabled as needed, other installed mods are disabled, and any mods that were only installed as
+		/// their dependencies are uninstalled. Installed mods are taken from the database if the "db" feature is active,
+		/// otherwise from the given mod map. Returns the plan that was carried out.
+		///
+		/// Mods are disabled and enabled first, then the installs and uninstalls are carried out together as a single
+		/// transaction. If any of those fail, everything that was disabled or enabled is reverted.
+		pub async fn apply_profile(
+			&self,
+			mods: &ResoluteModMap,
+			profile: &ModProfile,
+			options: &InstallOptions,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<ProfilePlan> {
+			progress(Progress::Resolving);
+			let (all_mods, installed, plan) = self.resolve_profile(mods, profile).await?;
+			if plan.is_empty() {
+				info!("Profile {} is already applied", profile);
+				return Ok(plan);
+			}
+			self.check_plan(&all_mods, None, &plan.install, options)?;
+
+			#[cfg(feature = "db")]
+			let snapshot = self.capture_snapshot(format!("Apply profile {}", profile.name))?;
+
+			info!("Applying profile {}: {}", profile, plan);
+
+			// Disable and enable mods, keeping track of what was changed so it can be reverted
+			let mut toggled = Vec::new();
+			let changes = plan
+				.disable
+				.iter()
+				.map(|id| (id, false))
+				.chain(plan.enable.iter().map(|id| (id, true)));
+			for (id, active) in changes {
+				let result = match installed.get(id) {
+					Some(rmod) => match Self::installed_version_of(rmod) {
+						Ok(version) => self.set_mod_active(rmod, version, active).await.map(|()| (rmod, version)),
+						Err(err) => Err(err),
+					},
+					None => Err(Error::UnknownMod(id.clone())),
+				};
+
+				match result {
+					Ok((rmod, version)) => toggled.push((rmod, version, active)),
+					Err(err) => {
+						error!("Error applying profile {}, reverting: {}", profile, err);
+						self.revert_toggles(toggled).await;
+						return Err(err);
+					}
+				}
+			}
+
+			// Install everything needed for the profile and clean up any mods that are no longer needed
+			let explicit: Vec<&str> = profile.mods.keys().map(String::as_str).collect();
+			let transaction = Self::plan_transaction(&all_mods, None, &plan.install, &explicit).and_then(|mut transaction| {
+				Self::stage_uninstall_plan(&mut transaction, &installed, &plan.remove)?;
+				Ok(transaction)
+			});
+			let result = match transaction {
+				Ok(transaction) => self.commit(&transaction, progress, cancel).await,
+				Err(err) => Err(err),
+			};
+			if let Err(err) = result {
+				error!("Error applying profile {}, reverting: {}", profile, err);
+				self.revert_toggles(toggled).await;
+				return Err(err);
+			}
+
+			#[cfg(feature = "db")]
+			self.record_snapshot(snapshot).await;
+			info!("Applied profile {}", profile);
+			Ok(plan)
+		}
+
+		/// Resolves the plan for switching the installed mods over to a profile.
+		/// Returns the mod map the plan was resolved against, the installed mods, and the plan.
+		async fn resolve_profile(
+			&self,
+			mods: &ResoluteModMap,
+			profile: &ModProfile,
+		) -> Result<(ResoluteModMap, ResoluteModMap, ProfilePlan)> {
+			// Include installed mods that aren't in the given map (such as unrecognized ones) so they can be managed too
+			let installed = self.installed_mods_within(mods).await?;
+			let mut all_mods = mods.clone();
+			for (id, rmod) in &installed {
+				all_mods.entry(id.clone()).or_insert_with(|| rmod.clone());
+			}
+
+			let resolver = Resolver::new(&all_mods, &self.channels);
+			let plan = resolver.resolve_profile(profile)?;
+
+			// The resolver avoids conflicts with the mods it knows about, but double check against the installed mods
+			// that will remain active
+			let remaining: ResoluteModMap = installed
+				.iter()
+				.filter(|(id, rmod)| {
+					(rmod.active || plan.enable.contains(id))
+						&& !plan.disable.contains(id)
+						&& plan.remove.steps.iter().all(|step| step.id != **id)
+				})
+				.map(|(id, rmod)| (id.clone(), rmod.clone()))
+				.collect();
+			let conflicts = resolver.conflicts(None, &plan.install, &remaining);
+			if !conflicts.is_empty() {
+				warn!("Refusing to apply profile {} due to conflicts: {}", profile, conflicts);
+				return Err(Error::Conflicts(Box::new(conflicts)));
+			}
+
+			Ok((all_mods, installed, plan))
+		}
+
+		/// Reverts mods that were disabled or enabled, in reverse order
+		async fn revert_toggles(&self, toggled: Vec<(&ResoluteMod, &ModVersion, bool)>) {
+			for (rmod, version, active) in toggled.into_iter().rev() {
+				if let Err(err) = self.set_mod_active(rmod, version, !active).await {
+					error!("Error reverting {} of mod {}: {}", if active { "enabling" } else { "disabling" }, rmod, err);
+				}
+			}
+		}
+
+		/// Builds a lockfile recording exactly which versions of mods are installed. Installed mods are taken from the
+		/// database if the "db" feature is active, otherwise from the given mod map.
+		pub async fn export_lockfile(&self, mods: &ResoluteModMap) -> Result<Lockfile> {
+			let installed = self.installed_mods_within(mods).await?;
+			let lockfile = Lockfile::from_installed(&installed)?;
+			info!("Exported lockfile with {} mods", lockfile.mods.len());
+			Ok(lockfile)
+		}
+
+		/// Reproduces the installation recorded in a lockfile. Every locked mod that isn't already installed at its
+		/// locked version is installed with exactly its locked artifacts (replacing any other installed version of it),
+		/// and every downloaded artifact's checksum is verified. If pruning is requested, installed mods that aren't in
+		/// the lockfile are uninstalled as well, with the exception of unrecognized mods since those can never be in a
+		/// lockfile. The mods to install are checked against the given options just like any other install plan.
+		/// Everything happens as a single transaction, so nothing changes if any part of it fails. Installed mods are
+		/// taken from the database if the "db" feature is active, otherwise from the given mod map.
+		/// Returns the plans that were carried out.
+		pub async fn restore_lockfile(
+			&self,
+			mods: &ResoluteModMap,
+			lockfile: &Lockfile,
+			prune: bool,
+			options: &InstallOptions,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<(InstallPlan, UninstallPlan)> {
+			// Combine the installed state with what the lockfile records
+			let installed = self.installed_mods_within(mods).await?;
+			let mut known = mods.clone();
+			for (id, rmod) in &installed {
+				known.insert(id.clone(), rmod.clone());
+			}
+			let locked_mods = lockfile.to_mods(&known);
+
+			// Install every locked mod that isn't already installed at its locked version
+			let install = InstallPlan {
+				steps: lockfile
+					.mods
+					.iter()
+					.filter(|(id, locked)| {
+						installed
+							.get(*id)
+							.and_then(|rmod| rmod.installed_version.as_ref())
+							!= Some(&locked.version)
+					})
+					.map(|(id, locked)| PlannedInstall {
+						id: id.clone(),
+						version: locked.version.clone(),
+						replaces: installed.get(id).and_then(|rmod| rmod.installed_version.clone()),
+					})
+					.collect(),
+			};
+			let explicit: Vec<&str> = lockfile
+				.mods
+				.iter()
+				.filter(|(_, locked)| locked.install_reason != Some(InstallReason::Dependency))
+				.map(|(id, _)| id.as_str())
+				.collect();
+
+			// Remove anything that isn't in the lockfile
+			let mut uninstall = UninstallPlan::default();
+			if prune {
+				uninstall.steps = installed
+					.values()
+					.filter(|rmod| !lockfile.mods.contains_key(&rmod.id) && !rmod.is_unrecognized())
+					.filter_map(|rmod| {
+						Some(PlannedUninstall {
+							id: rmod.id.clone(),
+							version: rmod.installed_version.clone()?,
+						})
+					})
+					.collect();
+			}
+
+			if install.is_empty() && uninstall.is_empty() {
+				info!("Installed mods already match the lockfile");
+				return Ok((install, uninstall));
+			}
+
+			self.check_plan(&locked_mods, None, &install, options)?;
+			let mut transaction = Self::plan_transaction(&locked_mods, None, &install, &explicit)?;
+			Self::stage_uninstall_plan(&mut transaction, &installed, &uninstall)?;
+
+			#[cfg(feature = "db")]
+			let snapshot = self.capture_snapshot("Restore lockfile")?;
+
+			info!("Restoring lockfile with {} mods", lockfile.mods.len());
+			self.commit(&transaction, progress, cancel).await?;
+
+			#[cfg(feature = "db")]
+			self.record_snapshot(snapshot).await;
+
+			info!("Restored lockfile (installed: {}, uninstalled: {})", install, uninstall);
+			Ok((install, uninstall))
+		}
+
+		/// Uninstalls a mod's installed version.
+		/// If any installed mods depend on the mod, this fails unless cascading is requested, in which case the
+		/// dependents are uninstalled first. Installed mods are taken from the database if the "db" feature is active,
+		/// otherwise from the given mod map. Returns the plan that was carried out.
+		pub async fn uninstall_mod(
+			&self,
+			mods: &ResoluteModMap,
+			rmod: &ResoluteMod,
+			cascade: bool,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<UninstallPlan> {
+			let mut installed = self.installed_mods_within(mods).await?;
+			installed.insert(rmod.id.clone(), rmod.clone());
+
+			let plan = Resolver::new(&installed, &self.channels).resolve_uninstall(rmod, cascade)?;
+
+			#[cfg(feature = "db")]
+			let snapshot = self.capture_snapshot(format!("Uninstall {plan}"))?;
+			self.apply_uninstall_plan(&installed, &plan, progress, cancel).await?;
+
+			#[cfg(feature = "db")]
+			self.record_snapshot(snapshot).await;
+			Ok(plan)
+		}
+
+		/// Uninstalls every mod that was only installed as a dependency and is no longer needed by any mod that was
+		/// installed explicitly. Installed mods are taken from the database if the "db" feature is active, otherwise
+		/// from the given mod map. Returns the plan that was carried out.
+		pub async fn remove_orphans(
+			&self,
+			mods: &ResoluteModMap,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<UninstallPlan> {
+			let installed = self.installed_mods_within(mods).await?;
+			let plan = Resolver::new(&installed, &self.channels).resolve_orphans();
+			if plan.is_empty() {
+				debug!("No orphaned mods to remove");
+				return Ok(plan);
+			}
+
+			#[cfg(feature = "db")]
+			let snapshot = self.capture_snapshot(format!("Remove orphaned mods {plan}"))?;
+			self.apply_uninstall_plan(&installed, &plan, progress, cancel).await?;
+
+			#[cfg(feature = "db")]
+			self.record_snapshot(snapshot).await;
+			Ok(plan)
+		}
+
+		/// Lists every snapshot of the installed mods that has been taken, newest first
+		#[cfg(feature = "db")]
+		#[allow(clippy::unused_async)]
+		pub async fn list_snapshots(&self) -> Result<Vec<Snapshot>> {
+			let mut snapshots = task::block_in_place(|| self.db.get_snapshots())?;
+			snapshots.sort_unstable_by_key(|snapshot| Reverse(snapshot.id));
+			Ok(snapshots)
+		}
+
+		/// Rolls the installed mods back to the state recorded in a snapshot, restoring their versions, enabled states
+		/// and install reasons. Versions of mods are looked up in the given mod map as well as the installed mods. The
+		/// rollback is carried out as a single transaction, so nothing changes if any part of it fails. Kept copies of
+		/// the artifacts are used where possible, and anything else is downloaded again. A snapshot of the state before
+		/// the rollback is recorded once it succeeds, so that the rollback itself can be undone.
+		/// Returns the snapshot that was rolled back to.
+		#[cfg(feature = "db")]
+		pub async fn rollback_to(
+			&self,
+			mods: &ResoluteModMap,
+			id: u64,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<Snapshot> {
+			let snapshot = task::block_in_place(|| self.db.get_snapshot(id))?
+				.ok_or_else(|| Error::ItemNotFound(id.to_string()))?;
+			let LoadedMods { mods: installed, .. } = self.get_installed_mods().await?;
+
+			// Combine the installed mods with the versions that the mod map knows about
+			let mut known = mods.clone();
+			for (id, rmod) in &installed {
+				let mut rmod = rmod.clone();
+				if let Some(other) = mods.get(id) {
+					for (semver, version) in &other.versions {
+						rmod.versions.entry(semver.clone()).or_insert_with(|| version.clone());
+					}
+				}
+				known.insert(id.clone(), rmod);
+			}
+
+			// Put every mod back how it was at the time, and uninstall anything that wasn't installed then
+			let mut transaction = Transaction::new();
+			for target in &snapshot.mods {
+				let rmod = known
+					.get(&target.id)
+					.ok_or_else(|| Error::UnknownMod(target.id.clone()))?;
+				transaction.restore(rmod, &target.version, target.active, target.install_reason)?;
+			}
+			for rmod in installed
+				.values()
+				.filter(|rmod| snapshot.mods.iter().all(|target| target.id != rmod.id))
+			{
+				transaction.uninstall(rmod)?;
+			}
+			if transaction.is_empty() {
+				info!("Installed mods already match snapshot {}", snapshot);
+				return Ok(snapshot);
+			}
+
+			let before = self.capture_snapshot(format!("Roll back to {}", snapshot.description))?;
+			info!("Rolling back to snapshot {}", snapshot);
+			self.commit(&transaction, progress, cancel).await?;
+
+			self.record_snapshot(before).await;
+			info!("Rolled back to snapshot {}", snapshot);
+			Ok(snapshot)
+		}
+
+		/// Removes the oldest snapshots so that only the given number of them remain, then prunes the artifact store now
+		/// that the removed snapshots no longer need their artifacts. Returns the number of snapshots removed.
+		#[cfg(feature = "db")]
+		pub async fn prune_snapshots(&self, keep: usize) -> Result<usize> {
+			let mut snapshots = task::block_in_place(|| self.db.get_snapshots())?;
+			snapshots.sort_unstable_by_key(|snapshot| snapshot.id);
+			let excess = snapshots.len().saturating_sub(keep);
+			if excess == 0 {
+				return Ok(0);
+			}
+
+			task::block_in_place(|| {
+				for snapshot in snapshots.drain(..excess) {
+					self.db.remove_snapshot(snapshot)?;
+				}
+				Ok::<_, Error>(())
+			})?;
+
+			info!("Pruned {} snapshots", excess);
+			self.prune_artifact_store().await?;
+			Ok(excess)
+		}
+
+		/// Prunes the artifact store down to its size and age limits. If the "db" feature is active, artifacts that any
+		/// snapshot needs are never pruned so that rolling back can work offline. Returns the number of files pruned.
+		pub async fn prune_artifact_store(&self) -> Result<usize> {
+			let Some(store) = &self.deleter.store else {
+				return Ok(0);
 			};
 
-			// Delete the version artifacts and remove the mod from the database
-			self.deleter.delete_version(installed_version).await?;
 			#[cfg(feature = "db")]
-			task::block_in_place(|| self.db.remove_mod_by_id(&rmod.id))?;
+			let protected: HashSet<String> = task::block_in_place(|| self.db.get_snapshots())?
+				.into_iter()
+				.flat_map(|snapshot| snapshot.artifacts)
+				.collect();
+			#[cfg(not(feature = "db"))]
+			let protected = HashSet::new();
+
+			store.prune(&protected).await
+		}
+
+		/// Captures the installed mods in a snapshot before a change. The snapshot is only recorded with
+		/// [`Self::record_snapshot`] once the change has succeeded.
+		#[cfg(feature = "db")]
+		fn capture_snapshot(&self, description: impl Into<String>) -> Result<Snapshot> {
+			let installed = task::block_in_place(|| self.db.get_installed_mods())?;
+
+			// Use the current time as the ID
+			let now = SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map_or(0, |elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX));
+			Ok(Snapshot::new(now, description, &installed))
+		}
+
+		/// Records a snapshot captured before a change that has succeeded, then prunes the oldest snapshots beyond the
+		/// limit. Since the change has already been made, any failure is only logged.
+		#[cfg(feature = "db")]
+		async fn record_snapshot(&self, mut snapshot: Snapshot) {
+			let stored = task::block_in_place(|| {
+				// Make sure the snapshot comes after any existing one
+				let latest = self.db.get_snapshots()?.into_iter().map(|snapshot| snapshot.id).max();
+				if let Some(latest) = latest {
+					snapshot.id = snapshot.id.max(latest.saturating_add(1));
+				}
+				self.db.store_snapshot(snapshot.clone())
+			});
+			if let Err(err) = stored {
+				error!("Error recording snapshot {}: {}", snapshot, err);
+				return;
+			}
+			debug!("Recorded snapshot {}", snapshot);
+
+			if let Err(err) = self.prune_snapshots(self.snapshot_limit).await {
+				warn!("Error pruning snapshots: {}", err);
+			}
+		}
+
+		/// Carries out an uninstall plan as a single operation
+		async fn apply_uninstall_plan(
+			&self,
+			installed: &ResoluteModMap,
+			plan: &UninstallPlan,
+			progress: impl Fn(Progress),
+			cancel: &CancellationToken,
+		) -> Result<()> {
+			let mut transaction = Transaction::new();
+			Self::stage_uninstall_plan(&mut transaction, installed, plan)?;
+			info!("Uninstalling plan {}", plan);
+			self.commit(&transaction, progress, cancel).await
+		}
+
+		/// Stages every step of an uninstall plan in a transaction
+		fn stage_uninstall_plan(
+			transaction: &mut Transaction,
+			installed: &ResoluteModMap,
+			plan: &UninstallPlan,
+		) -> Result<()> {
+			for step in &plan.steps {
+				let rmod = installed
+					.get(&step.id)
+					.ok_or_else(|| Error::UnknownMod(step.id.clone()))?;
+				transaction.uninstall(rmod)?;
+			}
 
 			Ok(())
 		}
@@ -325,19 +1559,84 @@ impl_ModManager_with_without_db! {
 			Ok(discovered)
 		}
 
+		/// Gets all installed mods - from the database if the "db" feature is active, otherwise from the given mod map
+		#[cfg_attr(feature = "db", allow(unused_variables))]
+		#[cfg_attr(not(feature = "db"), allow(clippy::unused_async))]
+		async fn installed_mods_within(&self, mods: &ResoluteModMap) -> Result<ResoluteModMap> {
+			#[cfg(feature = "db")]
+			{
+				let LoadedMods { mods: installed, .. } = self.get_installed_mods().await?;
+				Ok(installed)
+			}
+
+			#[cfg(not(feature = "db"))]
+			Ok(mods
+				.values()
+				.filter(|rmod| rmod.installed_version.is_some())
+				.map(|rmod| (rmod.id.clone(), rmod.clone()))
+				.collect())
+		}
+
 		/// Changes the base destination of mods for the manager
 		pub fn set_base_dest(&mut self, path: impl AsRef<Path>) {
 			let path = path.as_ref();
 			path.clone_into(&mut self.base_dest);
 			path.clone_into(&mut self.downloader.base_dest);
 			path.clone_into(&mut self.deleter.base_dest);
+			path.clone_into(&mut self.toggler.base_dest);
+		}
+
+		/// Changes the platform that the Resonite installation the manager operates on runs on.
+		/// If no platform is set, the platforms that mods support aren't checked at all.
+		pub const fn set_platform(&mut self, platform: Option<Platform>) {
+			self.platform = platform;
+		}
+
+		/// Changes the release channels to consider when updating mods
+		pub fn set_channel_policy(&mut self, channels: ChannelPolicy) {
+			self.channels = channels;
 		}
 
 		/// Changes the HTTP client to use for downloads
 		pub fn set_http_client(&mut self, http_client: reqwest::Client) {
-			self.downloader.http_client = http_client.clone();
+			self.downloader.set_http_client(http_client.clone());
 			self.http_client = http_client;
 		}
+
+		/// Registers a source to fetch artifacts with URLs of a scheme from, replacing any source already registered for
+		/// it. Artifacts from every source are verified against their checksums just the same.
+		pub fn register_artifact_source(&mut self, scheme: impl Into<String>, source: impl ArtifactSource + 'static) {
+			self.downloader.register_source(scheme, source);
+		}
+
+		/// Changes the policy for retrying failed artifact downloads
+		pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+			self.downloader.retry = policy;
+		}
+
+		/// Changes the mirrors to try in order when an artifact can't be downloaded from its own URL
+		pub fn set_mirrors(&mut self, mirrors: Vec<Mirror>) {
+			self.downloader.mirrors = mirrors;
+		}
+
+		/// Changes the maximum number of artifacts to download at the same time (at least one)
+		pub fn set_download_concurrency(&mut self, limit: usize) {
+			self.downloader.concurrency = limit.max(1);
+		}
+
+		/// Changes the store that artifacts are taken from before downloading them, downloaded artifacts are added to,
+		/// and replaced and deleted artifact files are kept in. If there isn't one, everything is downloaded and those
+		/// files are deleted outright.
+		pub fn set_artifact_store(&mut self, store: Option<ArtifactStore>) {
+			self.downloader.store.clone_from(&store);
+			self.deleter.store = store;
+		}
+
+		/// Changes the number of snapshots to keep - the oldest ones are removed when a new one is taken
+		#[cfg(feature = "db")]
+		pub const fn set_snapshot_limit(&mut self, limit: usize) {
+			self.snapshot_limit = limit;
+		}
 	}
 }
 
@@ -347,4 +1646,622 @@ impl_ModManager_with_without_db! {
 pub struct LoadedMods {
 	pub mods: ResoluteModMap,
 	pub removed: Option<ResoluteModMap>,
+	/// IDs of mods that don't support the manager's target platform
+	pub unsupported: Vec<String>,
+	/// Interpreted flags of mods that have any, mapped by their ID
+	pub flagged: HashMap<String, Vec<ModFlag>>,
+	/// IDs of installed mods that have become deprecated or vulnerable since they were last loaded
+	#[serde(rename = "newlyFlagged")]
+	pub newly_flagged: Vec<String>,
+}
+
+/// Interprets the flags of all mods in a map that have any
+fn flagged_mods(mods: &ResoluteModMap) -> HashMap<String, Vec<ModFlag>> {
+	mods.values()
+		.map(|rmod| (rmod.id.clone(), rmod.parsed_flags()))
+		.filter(|(_, flags)| !flags.is_empty())
+		.collect()
+}
+
+/// Options that control which installs a manager allows
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct InstallOptions {
+	/// Whether to allow installing mods that don't support the manager's target platform
+	pub allow_unsupported_platform: bool,
+	/// Whether installing deprecated or vulnerable mods has been confirmed
+	pub allow_flagged: bool,
+}
+
+impl InstallOptions {
+	/// Creates a new set of options that only allows installs without any issues
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets whether installing deprecated or vulnerable mods has been confirmed
+	#[must_use]
+	pub const fn allow_flagged(mut self, allow: bool) -> Self {
+		self.allow_flagged = allow;
+		self
+	}
+
+	/// Sets whether to allow installing mods that don't support the manager's target platform
+	#[must_use]
+	pub const fn allow_unsupported_platform(mut self, allow: bool) -> Self {
+		self.allow_unsupported_platform = allow;
+		self
+	}
+}
+
+#[cfg(test)]
+#[cfg(feature = "db")]
+mod tests {
+	use std::{
+		env, fs,
+		path::{Path, PathBuf},
+		process,
+	};
+
+	use semver::{Version, VersionReq};
+	use sha2::{Digest, Sha256};
+	use url::Url;
+
+	use super::{ArtifactStore, CancellationToken, InstallOptions, IntegrityReport, LoadedMods, ModManager};
+	use crate::{
+		db::ResoluteDatabase,
+		models::{
+			ModArtifact, ModDependencyMap, ModProfile, ModVersion, Platform, ResoluteMod, ResoluteModMap, Snapshot,
+		},
+		resolver::ProfilePlan,
+		Error,
+	};
+
+	/// Temporary directory for a test, holding a database, a Resonite installation to manage mods in, and the files
+	/// that artifacts are fetched from
+	struct TestDir(PathBuf);
+
+	impl TestDir {
+		fn new(name: &str) -> Self {
+			let dir = env::temp_dir().join(format!("resolute-test-{}-{name}", process::id()));
+			let _ = fs::remove_dir_all(&dir);
+			fs::create_dir_all(dir.join("sources")).expect("create temporary directory");
+			fs::create_dir_all(dir.join("resonite")).expect("create temporary directory");
+			Self(dir)
+		}
+
+		/// Creates a manager for the Resonite installation, with a fresh database
+		fn manager(&self) -> ModManager<'static> {
+			let db = ResoluteDatabase::open(self.0.join("resolute.redb")).expect("open database");
+			ModManager::new(db, self.0.join("resonite"), reqwest::Client::new())
+		}
+
+		/// Writes a file for an artifact to be fetched from, returning an artifact that installs it in rml_mods
+		fn artifact(&self, filename: &str, contents: &str) -> ModArtifact {
+			let path = self.0.join("sources").join(filename);
+			fs::write(&path, contents).expect("write artifact source file");
+			ModArtifact {
+				url: Url::from_file_path(&path).expect("file URL"),
+				sha256: format!("{:x}", Sha256::digest(contents)),
+				filename: None,
+				install_location: Some("/rml_mods".to_owned()),
+				override_filename: None,
+			}
+		}
+
+		/// Gets the path that an artifact file is installed at
+		fn installed(&self, filename: &str) -> PathBuf {
+			self.0.join("resonite").join("rml_mods").join(filename)
+		}
+
+		fn remove(self) {
+			fs::remove_dir_all(&self.0).expect("remove temporary directory");
+		}
+	}
+
+	fn semver(version: &str) -> Version {
+		Version::parse(version).expect("parse version")
+	}
+
+	/// Builds a version of a mod with a set of artifacts
+	fn version(semver_str: &str, artifacts: Vec<ModArtifact>) -> ModVersion {
+		ModVersion {
+			semver: semver(semver_str),
+			artifacts,
+			dependencies: ModDependencyMap::new(),
+			conflicts: ModDependencyMap::new(),
+			release_url: None,
+			changelog: None,
+		}
+	}
+
+	/// Builds a mod that isn't installed
+	fn rmod(id: &str, versions: Vec<ModVersion>) -> ResoluteMod {
+		ResoluteMod {
+			id: id.to_owned(),
+			name: id.to_owned(),
+			description: String::new(),
+			category: "Libraries".to_owned(),
+			authors: Vec::new(),
+			source_location: None,
+			website: None,
+			tags: None,
+			flags: None,
+			platforms: None,
+			versions: versions
+				.into_iter()
+				.map(|version| (version.semver.clone(), version))
+				.collect(),
+			installed_version: None,
+			install_reason: None,
+			pin: None,
+			active: false,
+		}
+	}
+
+	fn map(mods: impl IntoIterator<Item = ResoluteMod>) -> ResoluteModMap {
+		mods.into_iter().map(|rmod| (rmod.id.clone(), rmod)).collect()
+	}
+
+	fn read(path: &Path) -> Option<String> {
+		fs::read_to_string(path).ok()
+	}
+
+	/// Lists the mods recorded in each snapshot
+	fn snapshot_mods(snapshots: &[Snapshot]) -> Vec<Vec<String>> {
+		snapshots
+			.iter()
+			.map(|snapshot| snapshot.mods.iter().map(ToString::to_string).collect())
+			.collect()
+	}
+
+	/// Installs a version of a mod in the mod map, then marks the installed mods in the map
+	async fn install(
+		manager: &ModManager<'_>,
+		mods: &mut ResoluteModMap,
+		id: &str,
+		version: &str,
+		options: &InstallOptions,
+	) -> crate::Result<()> {
+		let rmod = mods[id].clone();
+		manager
+			.install_mod(mods, &rmod, version, options, |_| {}, &CancellationToken::new())
+			.await?;
+		manager.mark_installed_mods(mods).await?;
+		Ok(())
+	}
+
+	/// Applies a profile made up of mod IDs and the versions it specifies for them, then marks the installed mods in
+	/// the mod map
+	async fn apply_profile(
+		manager: &ModManager<'_>,
+		mods: &mut ResoluteModMap,
+		profile_mods: &[(&str, Option<&str>)],
+	) -> crate::Result<ProfilePlan> {
+		let mut profile = ModProfile::new("test");
+		profile.mods = profile_mods
+			.iter()
+			.map(|(id, version)| ((*id).to_owned(), version.map(semver)))
+			.collect();
+		let plan = manager
+			.apply_profile(
+				mods,
+				&profile,
+				&InstallOptions::new(),
+				|_| {},
+				&CancellationToken::new(),
+			)
+			.await?;
+		manager.mark_installed_mods(mods).await?;
+		Ok(plan)
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn refuses_mods_that_dont_support_the_platform() {
+		let dir = TestDir::new("manager-platform");
+		let mut manager = dir.manager();
+		manager.set_platform(Some(Platform::LinuxWine));
+
+		let mut android_only = rmod(
+			"android",
+			vec![version("1.0.0", vec![dir.artifact("Android.dll", "a")])],
+		);
+		android_only.platforms = Some(vec!["android".to_owned()]);
+		let mut linux = rmod("linux", vec![version("1.0.0", vec![dir.artifact("Linux.dll", "l")])]);
+		linux.platforms = Some(vec!["linux".to_owned()]);
+		let mut mods = map([android_only, linux]);
+
+		// Mods listing a platform that covers the installation's are installed as usual
+		install(&manager, &mut mods, "linux", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install supported mod");
+		assert_eq!(read(&dir.installed("Linux.dll")).as_deref(), Some("l"));
+
+		// Others are refused unless that's explicitly allowed
+		let err = install(&manager, &mut mods, "android", "1.0.0", &InstallOptions::new())
+			.await
+			.expect_err("refuse unsupported mod");
+		assert!(
+			matches!(&err, Error::UnsupportedPlatform { platform: Platform::LinuxWine, ids } if *ids == ["android"]),
+			"{err}"
+		);
+		assert!(read(&dir.installed("Android.dll")).is_none());
+
+		let options = InstallOptions::new().allow_unsupported_platform(true);
+		install(&manager, &mut mods, "android", "1.0.0", &options)
+			.await
+			.expect("install unsupported mod as allowed");
+		assert_eq!(read(&dir.installed("Android.dll")).as_deref(), Some("a"));
+
+		// The installed mods point out the unsupported one
+		let LoadedMods { unsupported, .. } = manager.get_installed_mods().await.expect("get installed mods");
+		assert_eq!(unsupported, ["android"]);
+
+		drop(manager);
+		dir.remove();
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn disables_and_enables_installed_mods() {
+		let dir = TestDir::new("manager-toggle");
+		let manager = dir.manager();
+		let mut mods = map([rmod("mod", vec![version("1.0.0", vec![dir.artifact("Mod.dll", "m")])])]);
+		install(&manager, &mut mods, "mod", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install mod");
+
+		manager.disable_mod(&mods["mod"]).await.expect("disable mod");
+		assert!(read(&dir.installed("Mod.dll")).is_none());
+		assert_eq!(read(&dir.installed("Mod.dll.disabled")).as_deref(), Some("m"));
+		let LoadedMods { mods: installed, .. } = manager.get_installed_mods().await.expect("get installed mods");
+		assert!(!installed["mod"].active);
+
+		manager.enable_mod(&mods["mod"]).await.expect("enable mod");
+		assert_eq!(read(&dir.installed("Mod.dll")).as_deref(), Some("m"));
+		assert!(read(&dir.installed("Mod.dll.disabled")).is_none());
+		let LoadedMods { mods: installed, .. } = manager.get_installed_mods().await.expect("get installed mods");
+		assert!(installed["mod"].active);
+
+		drop(manager);
+		dir.remove();
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn renames_artifacts_back_when_toggling_fails_to_be_stored() {
+		let dir = TestDir::new("manager-toggle-rollback");
+		let manager = dir.manager();
+
+		// The mod's file is in place, but the database doesn't know about the mod, so storing its new state fails
+		let mut unknown = rmod(
+			"unknown",
+			vec![version("1.0.0", vec![dir.artifact("Unknown.dll", "u")])],
+		);
+		unknown.installed_version = Some(semver("1.0.0"));
+		fs::create_dir_all(dir.0.join("resonite").join("rml_mods")).expect("create mod directory");
+		fs::write(dir.installed("Unknown.dll"), "u").expect("write mod file");
+
+		let err = manager.disable_mod(&unknown).await.expect_err("fail to disable mod");
+		assert!(matches!(err, Error::ItemNotFound(_)), "{err}");
+		assert_eq!(read(&dir.installed("Unknown.dll")).as_deref(), Some("u"));
+		assert!(read(&dir.installed("Unknown.dll.disabled")).is_none());
+
+		drop(manager);
+		dir.remove();
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn switches_between_profiles() {
+		let dir = TestDir::new("manager-profiles");
+		let manager = dir.manager();
+		let mut mods = map([
+			rmod(
+				"first",
+				vec![
+					version("1.0.0", vec![dir.artifact("First-1.dll", "1")]),
+					version("2.0.0", vec![dir.artifact("First-2.dll", "2")]),
+				],
+			),
+			rmod("second", vec![version("1.0.0", vec![dir.artifact("Second.dll", "s")])]),
+		]);
+		install(&manager, &mut mods, "first", "2.0.0", &InstallOptions::new())
+			.await
+			.expect("install first mod");
+
+		// Switching to a profile with an older version of one mod and another mod replaces and installs them
+		apply_profile(&manager, &mut mods, &[("first", Some("1.0.0")), ("second", None)])
+			.await
+			.expect("apply profile");
+		assert_eq!(read(&dir.installed("First-1.dll")).as_deref(), Some("1"));
+		assert!(read(&dir.installed("First-2.dll")).is_none());
+		assert_eq!(read(&dir.installed("Second.dll")).as_deref(), Some("s"));
+
+		// Switching to a profile without one of them disables it rather than uninstalling it
+		let plan = apply_profile(&manager, &mut mods, &[("second", None)])
+			.await
+			.expect("apply profile");
+		assert!(plan.install.is_empty());
+		assert_eq!(plan.disable, ["first"]);
+		assert_eq!(read(&dir.installed("First-1.dll.disabled")).as_deref(), Some("1"));
+		assert_eq!(mods["first"].installed_version, Some(semver("1.0.0")));
+		assert!(!mods["first"].active);
+		assert!(mods["second"].active);
+
+		// Switching back enables it again
+		let plan = apply_profile(&manager, &mut mods, &[("first", None)])
+			.await
+			.expect("apply profile");
+		assert_eq!(plan.enable, ["first"]);
+		assert_eq!(plan.disable, ["second"]);
+		assert_eq!(read(&dir.installed("First-1.dll")).as_deref(), Some("1"));
+		assert_eq!(read(&dir.installed("Second.dll.disabled")).as_deref(), Some("s"));
+
+		drop(manager);
+		dir.remove();
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn rolls_back_to_snapshots() {
+		let dir = TestDir::new("manager-snapshots");
+		let mut manager = dir.manager();
+		manager.set_artifact_store(Some(ArtifactStore::new(dir.0.join("store"))));
+		let mut mods = map([rmod(
+			"mod",
+			vec![
+				version("1.0.0", vec![dir.artifact("Mod-1.dll", "1")]),
+				version("2.0.0", vec![dir.artifact("Mod-2.dll", "2")]),
+			],
+		)]);
+		install(&manager, &mut mods, "mod", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install mod");
+		install(&manager, &mut mods, "mod", "2.0.0", &InstallOptions::new())
+			.await
+			.expect("update mod");
+
+		// A snapshot is taken before each change, newest first
+		let snapshots = manager.list_snapshots().await.expect("list snapshots");
+		assert_eq!(snapshot_mods(&snapshots), [vec!["mod v1.0.0"], vec![]]);
+
+		// Rolling back works offline, using the copies of the artifacts kept in the store
+		fs::remove_dir_all(dir.0.join("sources")).expect("remove artifact source files");
+		manager
+			.rollback_to(&mods, snapshots[0].id, |_| {}, &CancellationToken::new())
+			.await
+			.expect("roll back");
+		assert_eq!(read(&dir.installed("Mod-1.dll")).as_deref(), Some("1"));
+		assert!(read(&dir.installed("Mod-2.dll")).is_none());
+		let LoadedMods { mods: installed, .. } = manager.get_installed_mods().await.expect("get installed mods");
+		assert_eq!(installed["mod"].installed_version, Some(semver("1.0.0")));
+
+		// The rollback gets a snapshot of its own so it can be undone
+		let rolled_back = manager.list_snapshots().await.expect("list snapshots");
+		assert_eq!(
+			snapshot_mods(&rolled_back),
+			[vec!["mod v2.0.0"], vec!["mod v1.0.0"], vec![]]
+		);
+
+		// Rolling back to before anything was installed uninstalls everything
+		manager
+			.rollback_to(&mods, snapshots[1].id, |_| {}, &CancellationToken::new())
+			.await
+			.expect("roll back");
+		assert!(read(&dir.installed("Mod-1.dll")).is_none());
+		let LoadedMods { mods: installed, .. } = manager.get_installed_mods().await.expect("get installed mods");
+		assert!(installed.is_empty());
+
+		drop(manager);
+		dir.remove();
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn verifies_and_repairs_installed_mod_files() {
+		let dir = TestDir::new("manager-repair");
+		let manager = dir.manager();
+		let mut mods = map([
+			rmod(
+				"mod",
+				vec![version(
+					"1.0.0",
+					vec![
+						dir.artifact("Missing.dll", "missing"),
+						dir.artifact("Modified.dll", "modified"),
+						dir.artifact("Copied.dll", "copied"),
+					],
+				)],
+			),
+			rmod(
+				"disabled",
+				vec![version("1.0.0", vec![dir.artifact("Disabled.dll", "d")])],
+			),
+		]);
+		install(&manager, &mut mods, "mod", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install mod");
+		install(&manager, &mut mods, "disabled", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install disabled mod");
+		manager.disable_mod(&mods["disabled"]).await.expect("disable mod");
+		let report = manager.verify_installed(&mods).await.expect("verify installed mods");
+		assert!(report.is_empty(), "{report}");
+
+		fs::remove_file(dir.installed("Missing.dll")).expect("remove mod file");
+		fs::write(dir.installed("Modified.dll"), "tampered").expect("modify mod file");
+		fs::write(dir.installed("Copy of Copied.dll"), "copied").expect("copy mod file");
+		fs::write(dir.installed("Extra.dll"), "extra").expect("write extra file");
+		fs::remove_file(dir.installed("Disabled.dll.disabled")).expect("remove disabled mod file");
+
+		let issues = |report: &IntegrityReport, id: &str| -> Vec<String> {
+			report
+				.mods
+				.get(id)
+				.into_iter()
+				.flatten()
+				.map(ToString::to_string)
+				.collect()
+		};
+		let report = manager.verify_installed(&mods).await.expect("verify installed mods");
+		assert_eq!(
+			issues(&report, "mod"),
+			[
+				format!("missing {}", dir.installed("Missing.dll").display()),
+				format!("modified {}", dir.installed("Modified.dll").display()),
+				format!(
+					"duplicate {} of {}",
+					dir.installed("Copy of Copied.dll").display(),
+					dir.installed("Copied.dll").display()
+				),
+			]
+		);
+		assert_eq!(
+			issues(&report, "disabled"),
+			[format!("missing {}", dir.installed("Disabled.dll.disabled").display())]
+		);
+		assert_eq!(report.extra, [dir.installed("Extra.dll")]);
+
+		// Repairing downloads the broken files again and discards copies if asked to, but leaves extra files alone
+		let repaired = manager
+			.repair(&mods, true, |_| {}, &CancellationToken::new())
+			.await
+			.expect("repair installed mods");
+		assert_eq!(repaired, report);
+		assert_eq!(read(&dir.installed("Missing.dll")).as_deref(), Some("missing"));
+		assert_eq!(read(&dir.installed("Modified.dll")).as_deref(), Some("modified"));
+		assert!(read(&dir.installed("Copy of Copied.dll")).is_none());
+		assert_eq!(read(&dir.installed("Disabled.dll.disabled")).as_deref(), Some("d"));
+		assert!(read(&dir.installed("Disabled.dll")).is_none());
+
+		let report = manager.verify_installed(&mods).await.expect("verify installed mods");
+		assert!(report.mods.is_empty(), "{report}");
+		assert_eq!(report.extra, [dir.installed("Extra.dll")]);
+
+		drop(manager);
+		dir.remove();
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn removes_orphaned_dependencies() {
+		let dir = TestDir::new("manager-orphans");
+		let manager = dir.manager();
+		let mut app = version("1.0.0", vec![dir.artifact("App.dll", "a")]);
+		app.dependencies.insert(
+			"lib".to_owned(),
+			VersionReq::parse("^1").expect("parse version requirement"),
+		);
+		let mut mods = map([
+			rmod("app", vec![app]),
+			rmod("lib", vec![version("1.0.0", vec![dir.artifact("Lib.dll", "l")])]),
+			rmod(
+				"explicit-lib",
+				vec![version("1.0.0", vec![dir.artifact("ExplicitLib.dll", "e")])],
+			),
+		]);
+		install(&manager, &mut mods, "app", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install app");
+		install(&manager, &mut mods, "explicit-lib", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install library");
+
+		// Nothing is orphaned while the app still needs its dependency
+		let plan = manager
+			.remove_orphans(&mods, |_| {}, &CancellationToken::new())
+			.await
+			.expect("remove orphans");
+		assert!(plan.is_empty(), "{plan}");
+		assert_eq!(read(&dir.installed("Lib.dll")).as_deref(), Some("l"));
+
+		// Once the app is gone, only the mod that was installed as its dependency is removed
+		manager
+			.uninstall_mod(&mods, &mods["app"], false, |_| {}, &CancellationToken::new())
+			.await
+			.expect("uninstall app");
+		let plan = manager
+			.remove_orphans(&mods, |_| {}, &CancellationToken::new())
+			.await
+			.expect("remove orphans");
+		assert_eq!(plan.to_string(), "[lib v1.0.0]");
+		assert!(read(&dir.installed("Lib.dll")).is_none());
+		assert_eq!(read(&dir.installed("ExplicitLib.dll")).as_deref(), Some("e"));
+		let LoadedMods { mods: installed, .. } = manager.get_installed_mods().await.expect("get installed mods");
+		assert_eq!(installed.keys().collect::<Vec<_>>(), ["explicit-lib"]);
+
+		drop(manager);
+		dir.remove();
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn restores_lockfiles() {
+		let dir = TestDir::new("manager-lockfile");
+		let manager = dir.manager();
+		let mut mods = map([
+			rmod(
+				"updated",
+				vec![
+					version("1.0.0", vec![dir.artifact("Updated-1.dll", "1")]),
+					version("2.0.0", vec![dir.artifact("Updated-2.dll", "2")]),
+				],
+			),
+			rmod("kept", vec![version("1.0.0", vec![dir.artifact("Kept.dll", "k")])]),
+			rmod("added", vec![version("1.0.0", vec![dir.artifact("Added.dll", "a")])]),
+		]);
+		install(&manager, &mut mods, "updated", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install mod");
+		install(&manager, &mut mods, "kept", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install mod");
+		let lockfile = manager.export_lockfile(&mods).await.expect("export lockfile");
+		assert_eq!(lockfile.mods.keys().collect::<Vec<_>>(), ["kept", "updated"]);
+
+		install(&manager, &mut mods, "updated", "2.0.0", &InstallOptions::new())
+			.await
+			.expect("update mod");
+		install(&manager, &mut mods, "added", "1.0.0", &InstallOptions::new())
+			.await
+			.expect("install mod");
+
+		// Without pruning, only the locked versions are put back
+		let (install, uninstall) = manager
+			.restore_lockfile(
+				&mods,
+				&lockfile,
+				false,
+				&InstallOptions::new(),
+				|_| {},
+				&CancellationToken::new(),
+			)
+			.await
+			.expect("restore lockfile");
+		assert_eq!(install.to_string(), "[updated v2.0.0 -> v1.0.0]");
+		assert!(uninstall.is_empty());
+		assert_eq!(read(&dir.installed("Updated-1.dll")).as_deref(), Some("1"));
+		assert!(read(&dir.installed("Updated-2.dll")).is_none());
+		assert_eq!(read(&dir.installed("Added.dll")).as_deref(), Some("a"));
+
+		// Pruning also uninstalls whatever isn't in the lockfile
+		let (install, uninstall) = manager
+			.restore_lockfile(
+				&mods,
+				&lockfile,
+				true,
+				&InstallOptions::new(),
+				|_| {},
+				&CancellationToken::new(),
+			)
+			.await
+			.expect("restore lockfile");
+		assert!(install.is_empty());
+		assert_eq!(uninstall.to_string(), "[added v1.0.0]");
+		assert!(read(&dir.installed("Added.dll")).is_none());
+		assert_eq!(read(&dir.installed("Kept.dll")).as_deref(), Some("k"));
+		let LoadedMods { mods: installed, .. } = manager.get_installed_mods().await.expect("get installed mods");
+		assert_eq!(installed["updated"].installed_version, Some(semver("1.0.0")));
+		let mut ids: Vec<&String> = installed.keys().collect();
+		ids.sort_unstable();
+		assert_eq!(ids, ["kept", "updated"]);
+
+		drop(manager);
+		dir.remove();
+	}
 }
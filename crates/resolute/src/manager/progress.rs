@@ -0,0 +1,89 @@
+use serde::Serialize;
+use url::Url;
+
+/// Progress update for a long-running [`ModManager`](super::ModManager) or [`Downloader`](super::Downloader)
+/// operation. Each operation only reports the phases that it goes through.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum Progress {
+	/// Dependencies are being resolved to plan the operation
+	Resolving,
+
+	/// An artifact is being downloaded.
+	/// Artifacts are downloaded concurrently, so updates for different artifacts can be interleaved.
+	Downloading {
+		/// Position of the artifact among the ones being downloaded (starting at 1)
+		artifact: usize,
+		/// Number of artifacts being downloaded
+		count: usize,
+		/// URL the artifact is being downloaded from
+		url: Url,
+		/// Number of bytes of the artifact downloaded so far
+		downloaded: u64,
+		/// Total size of the artifact in bytes, if the server reported it
+		total: Option<u64>,
+	},
+
+	/// A downloaded artifact's checksum is being verified
+	Verifying {
+		/// Position of the artifact among the ones being downloaded (starting at 1)
+		artifact: usize,
+		/// Number of artifacts being downloaded
+		count: usize,
+	},
+
+	/// Downloaded artifacts are being put in place
+	Finalizing {
+		/// Number of artifacts being put in place
+		count: usize,
+	},
+
+	/// The artifacts of mods that are being uninstalled are being moved out of the way
+	Removing {
+		/// Number of mods being uninstalled
+		count: usize,
+	},
+
+	/// Changes are being recorded in the database
+	WritingDatabase,
+
+	/// Artifacts that were replaced or are no longer needed are being deleted (or kept in the artifact store)
+	DeletingOldFiles,
+}
+
+/// Progress update for a single artifact download, reported by
+/// [`Downloader::download_artifact`](super::Downloader::download_artifact)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArtifactProgress {
+	/// The artifact is being downloaded from a URL
+	Downloading {
+		/// URL the artifact is being downloaded from
+		url: Url,
+		/// Number of bytes downloaded so far
+		downloaded: u64,
+		/// Total size in bytes, if the server reported it
+		total: Option<u64>,
+	},
+
+	/// The downloaded artifact's checksum is being verified
+	Verifying,
+}
+
+impl ArtifactProgress {
+	/// Turns the update into an overall progress update for the artifact at a position among a number of artifacts
+	#[must_use]
+	pub fn at(self, artifact: usize, count: usize) -> Progress {
+		match self {
+			Self::Downloading { url, downloaded, total } => Progress::Downloading {
+				artifact,
+				count,
+				url,
+				downloaded,
+				total,
+			},
+			Self::Verifying => Progress::Verifying { artifact, count },
+		}
+	}
+}
@@ -0,0 +1,477 @@
+use std::{
+	collections::{BTreeSet, HashMap, HashSet},
+	fmt,
+	io::ErrorKind,
+	path::{Path, PathBuf},
+};
+
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use tokio::fs;
+
+use crate::models::ModArtifact;
+use crate::Result;
+
+use super::artifacts;
+use super::store::ArtifactStore;
+
+/// Summary of the artifact files that were recovered after operations were interrupted partway through replacing or
+/// removing them. Each entry is the path of an artifact file that had leftover temporary (.new) or old (.old) files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct RecoveryReport {
+	/// Artifact files whose interrupted replacement or removal was completed
+	pub completed: Vec<PathBuf>,
+	/// Artifact files whose interrupted replacement or removal was rolled back by putting the old file back in place
+	#[serde(rename = "rolledBack")]
+	pub rolled_back: Vec<PathBuf>,
+	/// Artifact files that were already correct, but had leftover files that were cleaned up
+	#[serde(rename = "cleanedUp")]
+	pub cleaned_up: Vec<PathBuf>,
+	/// Artifact files with leftover files that couldn't be matched against the installed mods, which are left alone
+	pub unresolved: Vec<PathBuf>,
+}
+
+impl RecoveryReport {
+	/// Checks whether nothing needed to be recovered
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.completed.is_empty()
+			&& self.rolled_back.is_empty()
+			&& self.cleaned_up.is_empty()
+			&& self.unresolved.is_empty()
+	}
+}
+
+impl fmt::Display for RecoveryReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"completed: {}, rolled back: {}, cleaned up: {}, unresolved: {}",
+			self.completed.len(),
+			self.rolled_back.len(),
+			self.cleaned_up.len(),
+			self.unresolved.len()
+		)
+	}
+}
+
+/// Checksums and paths of every artifact that the manifest and the database know about
+#[derive(Debug, Default)]
+pub(crate) struct KnownArtifacts {
+	/// Lowercase checksums of the artifacts
+	pub checksums: HashSet<String>,
+	/// Paths that the artifacts are installed at, both enabled and disabled
+	pub paths: HashSet<PathBuf>,
+}
+
+impl KnownArtifacts {
+	/// Checks whether a file's checksum belongs to a known artifact
+	fn has_checksum(&self, hash: Option<&str>) -> bool {
+		hash.is_some_and(|hash| self.checksums.contains(hash))
+	}
+}
+
+/// Recovers every artifact file in the given directories that has leftover files from an interrupted operation.
+/// The expected map holds the checksum of the file that should be at each installed artifact path, and the known
+/// artifacts are used to tell leftover artifact files apart from files that belong to the user. A failure to recover
+/// any single artifact file is logged and reported as unresolved rather than stopping the recovery of the rest.
+pub(crate) async fn recover_dirs(
+	dirs: &HashSet<PathBuf>,
+	expected: &HashMap<PathBuf, String>,
+	known: &KnownArtifacts,
+	store: Option<&ArtifactStore>,
+) -> Result<RecoveryReport> {
+	// Find the artifact files that have leftovers
+	let mut paths = BTreeSet::new();
+	for dir in dirs {
+		let mut entries = match fs::read_dir(dir).await {
+			Ok(entries) => entries,
+			Err(err) if err.kind() == ErrorKind::NotFound => continue,
+			Err(err) => return Err(err.into()),
+		};
+
+		while let Some(entry) = entries.next_entry().await? {
+			let name = entry.file_name();
+			let Some(name) = name.to_str() else {
+				continue;
+			};
+			let main = name
+				.strip_suffix(".new.resume")
+				.or_else(|| name.strip_suffix(".new"))
+				.or_else(|| name.strip_suffix(".old"));
+			if let Some(main) = main {
+				paths.insert(dir.join(main));
+			}
+		}
+	}
+
+	let mut report = RecoveryReport::default();
+	for path in paths {
+		let expected = expected.get(&path).map(String::as_str);
+		match recover_artifact(&path, expected, known, store).await {
+			Ok(Some(Outcome::Completed)) => report.completed.push(path),
+			Ok(Some(Outcome::RolledBack)) => report.rolled_back.push(path),
+			Ok(Some(Outcome::CleanedUp)) => report.cleaned_up.push(path),
+			Ok(Some(Outcome::Unresolved)) => report.unresolved.push(path),
+			Ok(None) => {}
+			Err(err) => {
+				error!("Error recovering artifact file {}: {}", path.display(), err);
+				report.unresolved.push(path);
+			}
+		}
+	}
+
+	Ok(report)
+}
+
+/// What was done to recover an artifact file
+enum Outcome {
+	Completed,
+	RolledBack,
+	CleanedUp,
+	Unresolved,
+}
+
+/// Leftover files of an artifact file, along with the checksums of the ones that exist
+struct Leftovers {
+	new: PathBuf,
+	old: PathBuf,
+	main_hash: Option<String>,
+	new_hash: Option<String>,
+	old_hash: Option<String>,
+}
+
+/// Recovers a single artifact file from its leftover files. Returns None if nothing needed to be done.
+async fn recover_artifact(
+	path: &Path,
+	expected: Option<&str>,
+	known: &KnownArtifacts,
+	store: Option<&ArtifactStore>,
+) -> Result<Option<Outcome>> {
+	let new = ModArtifact::tmp_dest(path)?;
+	let old = ModArtifact::old_dest(path)?;
+	let leftovers = Leftovers {
+		main_hash: hash_if_exists(path).await?,
+		new_hash: hash_if_exists(&new).await?,
+		old_hash: hash_if_exists(&old).await?,
+		new,
+		old,
+	};
+
+	match expected {
+		Some(expected) => recover_installed(path, expected, &leftovers, known, store).await,
+		None => recover_uninstalled(path, &leftovers, known, store).await,
+	}
+}
+
+/// Recovers an artifact file that an installed mod expects to be there. Whichever of the file, its temporary file, and
+/// its old file matches the expected checksum ends up in place, and the others are discarded.
+async fn recover_installed(
+	path: &Path,
+	expected: &str,
+	leftovers: &Leftovers,
+	known: &KnownArtifacts,
+	store: Option<&ArtifactStore>,
+) -> Result<Option<Outcome>> {
+	let Leftovers {
+		new,
+		old,
+		main_hash,
+		new_hash,
+		old_hash,
+	} = leftovers;
+
+	// The file is already the expected one, so the operation got far enough to be recorded
+	if main_hash.as_deref() == Some(expected) {
+		let discarded_old = artifacts::discard(old, store).await?;
+		let discarded_new = discard_tmp(new, new_hash.as_deref(), known, store).await?;
+		return Ok((discarded_old || discarded_new).then_some(Outcome::CleanedUp));
+	}
+
+	// The new file was downloaded and recorded, but never put in place
+	if new_hash.as_deref() == Some(expected) {
+		artifacts::discard(path, store).await?;
+		artifacts::rename(new, path, false).await?;
+		artifacts::discard(old, store).await?;
+		info!("Completed interrupted replacement of artifact file {}", path.display());
+		return Ok(Some(Outcome::Completed));
+	}
+
+	// The old file is still the recorded one, so the operation never finished
+	if old_hash.as_deref() == Some(expected) {
+		artifacts::discard(path, store).await?;
+		artifacts::rename(old, path, false).await?;
+		discard_tmp(new, new_hash.as_deref(), known, store).await?;
+		info!("Rolled back interrupted change to artifact file {}", path.display());
+		return Ok(Some(Outcome::RolledBack));
+	}
+
+	warn!(
+		"None of the leftover files for artifact file {} match the installed mods, leaving them alone",
+		path.display()
+	);
+	Ok(Some(Outcome::Unresolved))
+}
+
+/// Recovers an artifact file that no installed mod expects to be there. If there's an old file, it's put back when
+/// there's a file in its place (an install that was never recorded) and discarded otherwise (a removal that was).
+/// Since the files could just as well be the user's own, they're only touched if the path is a known artifact path or
+/// the checksums of the old file and the file in its place (if any) are known artifact checksums.
+async fn recover_uninstalled(
+	path: &Path,
+	leftovers: &Leftovers,
+	known: &KnownArtifacts,
+	store: Option<&ArtifactStore>,
+) -> Result<Option<Outcome>> {
+	let Leftovers {
+		new,
+		old,
+		main_hash,
+		new_hash,
+		old_hash,
+	} = leftovers;
+
+	let known_path = known.paths.contains(path);
+
+	// Only a temporary file was left behind by a download that was never put in place
+	if old_hash.is_none() {
+		if !known_path && !known.has_checksum(new_hash.as_deref()) {
+			warn!(
+				"Leftover temporary files for file {} don't match any known artifacts, leaving them alone",
+				path.display()
+			);
+			return Ok(Some(Outcome::Unresolved));
+		}
+
+		let discarded = discard_tmp(new, new_hash.as_deref(), known, store).await?;
+		return Ok(discarded.then_some(Outcome::CleanedUp));
+	}
+
+	let known_files =
+		known.has_checksum(old_hash.as_deref()) && (main_hash.is_none() || known.has_checksum(main_hash.as_deref()));
+	if !known_path && !known_files {
+		warn!(
+			"Leftover files for file {} don't match any known artifacts, leaving them alone",
+			path.display()
+		);
+		return Ok(Some(Outcome::Unresolved));
+	}
+
+	// A file was put in place over another one without being recorded
+	if main_hash.is_some() {
+		artifacts::discard(path, store).await?;
+		artifacts::rename(old, path, false).await?;
+		discard_tmp(new, new_hash.as_deref(), known, store).await?;
+		info!("Rolled back unrecorded replacement of file {}", path.display());
+		return Ok(Some(Outcome::RolledBack));
+	}
+
+	// The file was already set aside to be removed
+	artifacts::discard(old, store).await?;
+	discard_tmp(new, new_hash.as_deref(), known, store).await?;
+	info!("Completed interrupted removal of artifact file {}", path.display());
+	Ok(Some(Outcome::Completed))
+}
+
+/// Discards a leftover temporary file of a download along with its resume file. Complete downloads of artifacts in
+/// the manifest are kept in the store if there is one. Partial downloads that can still be resumed are left alone.
+/// Returns whether anything was discarded.
+async fn discard_tmp(
+	tmp: &Path,
+	hash: Option<&str>,
+	known: &KnownArtifacts,
+	store: Option<&ArtifactStore>,
+) -> Result<bool> {
+	let resume = ModArtifact::resume_dest(tmp)?;
+	let complete = known.has_checksum(hash);
+	let resumable = fs::try_exists(&resume).await.unwrap_or(false);
+	if hash.is_some() && !complete && resumable {
+		debug!("Leaving resumable partial download {} alone", tmp.display());
+		return Ok(false);
+	}
+
+	let discarded = match store {
+		Some(store) if complete => artifacts::discard(tmp, Some(store)).await?,
+		_ => artifacts::delete(tmp, true).await?,
+	};
+	let deleted_resume = artifacts::delete(&resume, true).await?;
+	Ok(discarded || deleted_resume)
+}
+
+/// Calculates the checksum of a file if it exists
+async fn hash_if_exists(path: &Path) -> Result<Option<String>> {
+	if !fs::try_exists(path).await? {
+		return Ok(None);
+	}
+	artifacts::hash_file(path).await.map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		collections::{HashMap, HashSet},
+		env, fs,
+		path::{Path, PathBuf},
+		process, slice,
+	};
+
+	use sha2::{Digest, Sha256};
+
+	use super::{recover_dirs, KnownArtifacts, RecoveryReport};
+
+	/// Creates an empty temporary directory for a test
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = env::temp_dir().join(format!("resolute-test-{}-{name}", process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).expect("create temporary directory");
+		dir
+	}
+
+	fn checksum(contents: &str) -> String {
+		format!("{:x}", Sha256::digest(contents))
+	}
+
+	fn write(path: &Path, contents: &str) {
+		fs::write(path, contents).expect("write file");
+	}
+
+	fn read(path: &Path) -> Option<String> {
+		fs::read_to_string(path).ok()
+	}
+
+	/// Runs recovery on a directory, with the given files expected at their paths and the given contents known
+	async fn recover(dir: &Path, expected: &[(&Path, &str)], known: &[&str], paths: &[&Path]) -> RecoveryReport {
+		let dirs = HashSet::from([dir.to_owned()]);
+		let expected: HashMap<PathBuf, String> = expected
+			.iter()
+			.map(|(path, contents)| (path.to_path_buf(), checksum(contents)))
+			.collect();
+		let known = KnownArtifacts {
+			checksums: known.iter().map(|contents| checksum(contents)).collect(),
+			paths: paths.iter().map(|path| path.to_path_buf()).collect(),
+		};
+		recover_dirs(&dirs, &expected, &known, None).await.expect("recover")
+	}
+
+	#[tokio::test]
+	async fn cleans_up_after_recorded_replacement() {
+		let dir = temp_dir("recover-cleaned");
+		let path = dir.join("Mod.dll");
+		write(&path, "v2");
+		write(&dir.join("Mod.dll.old"), "v1");
+
+		let report = recover(&dir, &[(&path, "v2")], &["v1", "v2"], &[]).await;
+		assert_eq!(report.cleaned_up, slice::from_ref(&path));
+		assert_eq!(read(&path).as_deref(), Some("v2"));
+		assert!(read(&dir.join("Mod.dll.old")).is_none());
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+
+	#[tokio::test]
+	async fn completes_recorded_replacement() {
+		let dir = temp_dir("recover-completed");
+		let path = dir.join("Mod.dll");
+		write(&path, "v1");
+		write(&dir.join("Mod.dll.new"), "v2");
+
+		let report = recover(&dir, &[(&path, "v2")], &["v1", "v2"], &[]).await;
+		assert_eq!(report.completed, slice::from_ref(&path));
+		assert_eq!(read(&path).as_deref(), Some("v2"));
+		assert!(read(&dir.join("Mod.dll.new")).is_none());
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+
+	#[tokio::test]
+	async fn rolls_back_unrecorded_replacement() {
+		let dir = temp_dir("recover-rolled-back");
+		let path = dir.join("Mod.dll");
+		write(&path, "v2");
+		write(&dir.join("Mod.dll.old"), "v1");
+
+		let report = recover(&dir, &[(&path, "v1")], &["v1", "v2"], &[]).await;
+		assert_eq!(report.rolled_back, slice::from_ref(&path));
+		assert_eq!(read(&path).as_deref(), Some("v1"));
+		assert!(read(&dir.join("Mod.dll.old")).is_none());
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+
+	#[tokio::test]
+	async fn rolls_back_uninstalled_known_artifacts() {
+		let dir = temp_dir("recover-uninstalled-known");
+		let path = dir.join("Mod.dll");
+		write(&path, "v2");
+		write(&dir.join("Mod.dll.old"), "v1");
+
+		let report = recover(&dir, &[], &["v1", "v2"], &[]).await;
+		assert_eq!(report.rolled_back, slice::from_ref(&path));
+		assert_eq!(read(&path).as_deref(), Some("v1"));
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+
+	#[tokio::test]
+	async fn completes_removal_of_known_artifact() {
+		let dir = temp_dir("recover-removal");
+		let old = dir.join("Mod.dll.old");
+		write(&old, "v1");
+
+		let report = recover(&dir, &[], &["v1"], &[]).await;
+		assert_eq!(report.completed, [dir.join("Mod.dll")]);
+		assert!(read(&old).is_none());
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+
+	#[tokio::test]
+	async fn leaves_unknown_files_alone() {
+		let dir = temp_dir("recover-unknown");
+		let path = dir.join("Mine.dll");
+		write(&path, "current");
+		write(&dir.join("Mine.dll.old"), "backup");
+		write(&dir.join("Other.dll.old"), "backup");
+		write(&dir.join("Other.dll.new"), "download");
+
+		let report = recover(&dir, &[], &["v1"], &[]).await;
+		assert_eq!(report.unresolved, [path.clone(), dir.join("Other.dll")]);
+		assert!(report.completed.is_empty() && report.rolled_back.is_empty() && report.cleaned_up.is_empty());
+		assert_eq!(read(&path).as_deref(), Some("current"));
+		assert_eq!(read(&dir.join("Mine.dll.old")).as_deref(), Some("backup"));
+		assert_eq!(read(&dir.join("Other.dll.old")).as_deref(), Some("backup"));
+		assert_eq!(read(&dir.join("Other.dll.new")).as_deref(), Some("download"));
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+
+	#[tokio::test]
+	async fn leaves_unknown_files_over_known_artifacts_alone() {
+		let dir = temp_dir("recover-unknown-main");
+		let path = dir.join("Mod.dll");
+		write(&path, "user's own build");
+		write(&dir.join("Mod.dll.old"), "v1");
+
+		let report = recover(&dir, &[], &["v1"], &[]).await;
+		assert_eq!(report.unresolved, slice::from_ref(&path));
+		assert_eq!(read(&path).as_deref(), Some("user's own build"));
+		assert_eq!(read(&dir.join("Mod.dll.old")).as_deref(), Some("v1"));
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+
+	#[tokio::test]
+	async fn recovers_files_at_known_artifact_paths() {
+		let dir = temp_dir("recover-known-path");
+		let path = dir.join("Mod.dll");
+		write(&path, "partial");
+		write(&dir.join("Mod.dll.old"), "v1");
+
+		let report = recover(&dir, &[], &[], &[&path]).await;
+		assert_eq!(report.rolled_back, slice::from_ref(&path));
+		assert_eq!(read(&path).as_deref(), Some("v1"));
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+}
@@ -0,0 +1,389 @@
+use std::{
+	fmt,
+	path::{Component, Path, PathBuf},
+	time::UNIX_EPOCH,
+};
+
+use bytes::Bytes;
+use futures_util::{future::BoxFuture, stream::BoxStream, StreamExt, TryStreamExt};
+use log::{debug, warn};
+use reqwest::{
+	header::{ACCEPT_RANGES, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE},
+	Client, Response, StatusCode,
+};
+use tokio::{
+	fs,
+	io::{AsyncSeekExt, SeekFrom},
+};
+use tokio_util::io::ReaderStream;
+use url::Url;
+
+use crate::retry::check_response;
+use crate::{Error, Result};
+
+/// A place that artifact files can be fetched from, chosen by the scheme of an artifact's URL.
+/// Sources are registered with a [`Downloader`](super::Downloader) for each scheme they handle. Whatever a source
+/// provides is verified against the artifact's checksum just the same, so sources don't need to be trusted.
+pub trait ArtifactSource: fmt::Debug + Send + Sync {
+	/// Starts fetching the file at a URL. If part of the file was already fetched and the source supports resuming, it
+	/// can provide just the rest of the file, indicating where it continues from with [`Fetched::offset`].
+	fn fetch<'a>(&'a self, url: &'a Url, partial: Option<Partial<'a>>) -> BoxFuture<'a, Result<Fetched>>;
+}
+
+/// Part of a file that was already fetched by an earlier attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Partial<'a> {
+	/// Number of bytes already fetched
+	pub len: u64,
+	/// Validator the source gave for the file when the earlier attempt started (see [`Fetched::validator`])
+	pub validator: &'a str,
+}
+
+/// A file that's being fetched from an [`ArtifactSource`]
+#[non_exhaustive]
+pub struct Fetched {
+	/// Stream of the file's contents, starting from the offset
+	pub body: BoxStream<'static, Result<Bytes>>,
+	/// Number of bytes at the start of the file that the body skips, since they were already fetched
+	pub offset: u64,
+	/// Total size of the file in bytes, if known
+	pub total: Option<u64>,
+	/// Validator to give back to the source to resume fetching the file if this attempt is interrupted, if the source
+	/// supports resuming it
+	pub validator: Option<String>,
+}
+
+impl Fetched {
+	/// Creates a fetched file for the whole contents of a stream, with an unknown size and no support for resuming
+	#[must_use]
+	pub fn new(body: BoxStream<'static, Result<Bytes>>) -> Self {
+		Self {
+			body,
+			offset: 0,
+			total: None,
+			validator: None,
+		}
+	}
+
+	/// Sets the number of bytes at the start of the file that the body skips
+	#[must_use]
+	pub const fn offset(mut self, offset: u64) -> Self {
+		self.offset = offset;
+		self
+	}
+
+	/// Sets the total size of the file in bytes
+	#[must_use]
+	pub const fn total(mut self, total: Option<u64>) -> Self {
+		self.total = total;
+		self
+	}
+
+	/// Sets the validator to resume fetching the file with
+	#[must_use]
+	pub fn validator(mut self, validator: Option<String>) -> Self {
+		self.validator = validator;
+		self
+	}
+}
+
+impl fmt::Debug for Fetched {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Fetched")
+			.field("offset", &self.offset)
+			.field("total", &self.total)
+			.field("validator", &self.validator)
+			.finish_non_exhaustive()
+	}
+}
+
+/// Source that fetches files from HTTP servers, for http:// and https:// URLs.
+/// Downloads are resumed with range requests when the server supports them.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct HttpSource {
+	pub client: Client,
+}
+
+impl HttpSource {
+	/// URL schemes that the source handles
+	pub const SCHEMES: [&'static str; 2] = ["http", "https"];
+
+	#[must_use]
+	pub const fn new(client: Client) -> Self {
+		Self { client }
+	}
+
+	/// Sends the request for a file, asking for just the rest of the file if there's a partial download that can be
+	/// resumed. Returns the successful response along with the number of bytes already downloaded that it continues
+	/// from (zero if it's for the whole file).
+	async fn send_request(&self, url: &Url, partial: Option<Partial<'_>>) -> Result<(Response, u64)> {
+		let Some(partial) = partial.filter(|partial| partial.len > 0) else {
+			let response = self.client.get(url.clone()).send().await?;
+			return Ok((check_response(response)?, 0));
+		};
+
+		debug!("Resuming download of {} from byte {}", url, partial.len);
+		let response = self
+			.client
+			.get(url.clone())
+			.header(RANGE, format!("bytes={}-", partial.len))
+			.header(IF_RANGE, partial.validator)
+			.send()
+			.await?;
+
+		match response.status() {
+			// The server is providing the rest of the file as requested
+			StatusCode::PARTIAL_CONTENT if content_range_start(&response) == Some(partial.len) => {
+				Ok((response, partial.len))
+			}
+
+			// The server can't provide the rest of the file, so start over from scratch
+			StatusCode::RANGE_NOT_SATISFIABLE | StatusCode::PARTIAL_CONTENT => {
+				warn!("Unable to resume download of {}, starting over", url);
+				let response = self.client.get(url.clone()).send().await?;
+				Ok((check_response(response)?, 0))
+			}
+
+			// The server is providing the whole file (likely because it has changed since the partial download), or
+			// the request failed
+			_ => Ok((check_response(response)?, 0)),
+		}
+	}
+}
+
+impl ArtifactSource for HttpSource {
+	fn fetch<'a>(&'a self, url: &'a Url, partial: Option<Partial<'a>>) -> BoxFuture<'a, Result<Fetched>> {
+		Box::pin(async move {
+			let (response, offset) = self.send_request(url, partial).await?;
+			let total = response.content_length().map(|length| length.saturating_add(offset));
+			let validator = resume_validator(&response);
+			let body = response.bytes_stream().map_err(Error::from).boxed();
+			Ok(Fetched::new(body).offset(offset).total(total).validator(validator))
+		})
+	}
+}
+
+/// Gets the validator to resume a download with from its response, if the server supports resuming it.
+/// Strong entity tags are preferred, since weak ones can't be used to resume.
+fn resume_validator(response: &Response) -> Option<String> {
+	let headers = response.headers();
+	let accepts_ranges = headers
+		.get(ACCEPT_RANGES)
+		.and_then(|value| value.to_str().ok())
+		.is_some_and(|value| value.eq_ignore_ascii_case("bytes"));
+	if !accepts_ranges {
+		return None;
+	}
+
+	headers
+		.get(ETAG)
+		.filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+		.or_else(|| headers.get(LAST_MODIFIED))
+		.and_then(|value| value.to_str().ok())
+		.map(ToOwned::to_owned)
+}
+
+/// Gets the first byte position of a partial response's content range
+fn content_range_start(response: &Response) -> Option<u64> {
+	response
+		.headers()
+		.get(CONTENT_RANGE)?
+		.to_str()
+		.ok()?
+		.strip_prefix("bytes ")?
+		.split('-')
+		.next()?
+		.parse()
+		.ok()
+}
+
+/// Source that reads files from the local filesystem (including mounted network shares), for file:// URLs
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct FileSource;
+
+impl FileSource {
+	/// URL scheme that the source handles
+	pub const SCHEME: &'static str = "file";
+
+	#[must_use]
+	pub const fn new() -> Self {
+		Self
+	}
+}
+
+impl ArtifactSource for FileSource {
+	fn fetch<'a>(&'a self, url: &'a Url, partial: Option<Partial<'a>>) -> BoxFuture<'a, Result<Fetched>> {
+		Box::pin(async move {
+			let path = url
+				.to_file_path()
+				.map_err(|()| Error::Url(format!("{url} doesn't point to a local file")))?;
+			read_file(&path, partial).await
+		})
+	}
+}
+
+/// Source that reads files from a local directory for URLs with a custom scheme, such as for a USB stick or a mirror
+/// of artifacts copied from another machine. The host and path of each URL are taken as a path relative to the
+/// directory. For example, with a directory of `/media/usb/mods` registered for the `usb` scheme, an artifact at
+/// `usb://owner/Mod.dll` is read from `/media/usb/mods/owner/Mod.dll`. Paths that lead outside of the directory are
+/// refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DirectorySource {
+	pub root: PathBuf,
+}
+
+impl DirectorySource {
+	#[must_use]
+	pub fn new(root: impl AsRef<Path>) -> Self {
+		Self {
+			root: root.as_ref().to_owned(),
+		}
+	}
+
+	/// Determines the path of the file that a URL points to within the directory
+	pub fn path_for(&self, url: &Url) -> Result<PathBuf> {
+		let relative: PathBuf = url
+			.host_str()
+			.into_iter()
+			.chain(url.path_segments().into_iter().flatten())
+			.filter(|segment| !segment.is_empty())
+			.collect();
+		if relative.as_os_str().is_empty()
+			|| !relative
+				.components()
+				.all(|component| matches!(component, Component::Normal(_)))
+		{
+			return Err(Error::Path(format!(
+				"{url} doesn't point to a file within {}",
+				self.root.display()
+			)));
+		}
+
+		Ok(self.root.join(relative))
+	}
+}
+
+impl ArtifactSource for DirectorySource {
+	fn fetch<'a>(&'a self, url: &'a Url, partial: Option<Partial<'a>>) -> BoxFuture<'a, Result<Fetched>> {
+		Box::pin(async move {
+			let path = self.path_for(url)?;
+			read_file(&path, partial).await
+		})
+	}
+}
+
+/// Reads a local file, continuing from where a partial read left off if the file hasn't been modified since.
+/// The file's modification time (in seconds and nanoseconds since the Unix epoch) and size are used as its validator.
+async fn read_file(path: &Path, partial: Option<Partial<'_>>) -> Result<Fetched> {
+	let mut file = fs::File::open(path).await?;
+	let metadata = file.metadata().await?;
+	if !metadata.is_file() {
+		return Err(Error::Path(format!("{} isn't a file", path.display())));
+	}
+	let validator = metadata
+		.modified()
+		.ok()
+		.and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+		.map(|modified| {
+			format!(
+				"{}.{:09}/{}",
+				modified.as_secs(),
+				modified.subsec_nanos(),
+				metadata.len()
+			)
+		});
+
+	let offset = match partial {
+		Some(partial)
+			if partial.len > 0 && partial.len <= metadata.len() && validator.as_deref() == Some(partial.validator) =>
+		{
+			debug!("Resuming read of {} from byte {}", path.display(), partial.len);
+			file.seek(SeekFrom::Start(partial.len)).await?
+		}
+		_ => 0,
+	};
+
+	let body = ReaderStream::new(file).map_err(Error::from).boxed();
+	Ok(Fetched::new(body)
+		.offset(offset)
+		.total(Some(metadata.len()))
+		.validator(validator))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{env, fs, path::Path, process};
+
+	use futures_util::TryStreamExt;
+	use url::Url;
+
+	use super::{read_file, DirectorySource, Partial};
+
+	fn url(url: &str) -> Url {
+		Url::parse(url).expect("valid URL")
+	}
+
+	#[test]
+	fn directory_source_joins_host_and_path() {
+		let source = DirectorySource::new("/media/usb/mods");
+		let path = source
+			.path_for(&url("usb://owner/sub/Mod.dll"))
+			.expect("path within directory");
+		assert_eq!(path, Path::new("/media/usb/mods/owner/sub/Mod.dll"));
+	}
+
+	#[test]
+	fn directory_source_rejects_paths_outside_directory() {
+		let source = DirectorySource::new("/media/usb/mods");
+		assert!(source.path_for(&url("usb://../secret.dll")).is_err());
+		assert!(source.path_for(&url("usb://")).is_err());
+
+		// Dot segments in the path are resolved by URL parsing, so they can't climb out of the directory either
+		let path = source
+			.path_for(&url("usb://owner/../../../etc/passwd"))
+			.expect("path within directory");
+		assert!(path.starts_with("/media/usb/mods"), "{}", path.display());
+	}
+
+	#[tokio::test]
+	async fn read_file_resumes_with_stable_validator() {
+		let path = env::temp_dir().join(format!("resolute-test-{}-read-file", process::id()));
+		fs::write(&path, b"0123456789").expect("write file");
+
+		let fetched = read_file(&path, None).await.expect("read file");
+		let validator = fetched.validator.expect("validator");
+		let (modified, len) = validator.split_once('/').expect("size in validator");
+		let (secs, nanos) = modified.split_once('.').expect("nanoseconds in validator");
+		assert!(secs.parse::<u64>().is_ok() && nanos.len() == 9 && nanos.parse::<u32>().is_ok());
+		assert_eq!(len, "10");
+
+		let partial = Partial {
+			len: 4,
+			validator: &validator,
+		};
+		let fetched = read_file(&path, Some(partial)).await.expect("resume reading file");
+		assert_eq!(fetched.offset, 4);
+		let rest: Vec<u8> = fetched
+			.body
+			.try_fold(Vec::new(), |mut rest, chunk| async move {
+				rest.extend_from_slice(&chunk);
+				Ok(rest)
+			})
+			.await
+			.expect("read rest of file");
+		assert_eq!(rest, b"456789");
+
+		let stale = Partial {
+			len: 4,
+			validator: "0.000000000/10",
+		};
+		let fetched = read_file(&path, Some(stale)).await.expect("read file again");
+		assert_eq!(fetched.offset, 0);
+
+		fs::remove_file(&path).expect("remove file");
+	}
+}
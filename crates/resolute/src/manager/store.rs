@@ -0,0 +1,317 @@
+use core::result;
+use std::{
+	collections::HashSet,
+	io::ErrorKind,
+	path::{Path, PathBuf},
+	time::{Duration, SystemTime},
+};
+
+use log::{debug, info, warn};
+use rand::Rng;
+use tokio::fs;
+
+use crate::{Error, Result};
+
+use super::artifacts::{self, ArtifactAction, ArtifactError, MappableToArtifactError};
+
+/// Local cache of artifact files, stored by their SHA-256 checksums so that each distinct file is only ever kept once.
+/// Downloads are taken from the store whenever possible and verified downloads are added to it, and artifact files that
+/// have been replaced or deleted are kept in it so that they can be put back later.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ArtifactStore {
+	pub dir: PathBuf,
+	/// Total size in bytes that the kept files are pruned down to
+	pub max_size: Option<u64>,
+	/// Time since a kept file was last used after which it's pruned
+	pub max_age: Option<Duration>,
+}
+
+impl ArtifactStore {
+	/// Default total size that the kept files are pruned down to (2 GiB)
+	pub const DEFAULT_MAX_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+	/// Default time since a kept file was last used after which it's pruned (30 days)
+	// Duration::from_hours is too new for the crate's supported Rust versions
+	#[allow(clippy::duration_suboptimal_units)]
+	pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+	pub fn new(dir: impl AsRef<Path>) -> Self {
+		Self {
+			dir: dir.as_ref().to_owned(),
+			max_size: Some(Self::DEFAULT_MAX_SIZE),
+			max_age: Some(Self::DEFAULT_MAX_AGE),
+		}
+	}
+
+	/// Sets the total size in bytes that the kept files are pruned down to, or removes the limit
+	#[must_use]
+	pub const fn with_max_size(mut self, max_size: Option<u64>) -> Self {
+		self.max_size = max_size;
+		self
+	}
+
+	/// Sets the time since a kept file was last used after which it's pruned, or removes the limit
+	#[must_use]
+	pub const fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+		self.max_age = max_age;
+		self
+	}
+
+	/// Gets the path that a file with the given checksum is kept at
+	#[must_use]
+	pub fn path_of(&self, sha256: &str) -> PathBuf {
+		self.dir.join(sha256.to_lowercase())
+	}
+
+	/// Checks whether a file with the given checksum is kept in the store
+	pub async fn contains(&self, sha256: &str) -> bool {
+		fs::try_exists(self.path_of(sha256)).await.unwrap_or(false)
+	}
+
+	/// Moves a file into the store. If an identical file is already kept, the file is just deleted instead.
+	/// Returns the checksum of the file, or None if it doesn't exist.
+	pub async fn keep(&self, path: &Path) -> result::Result<Option<String>, ArtifactError> {
+		if !fs::try_exists(path).await.unwrap_or(false) {
+			return Ok(None);
+		}
+
+		let hash = artifacts::hash_file(path)
+			.await
+			.map_artifact_err(ArtifactAction::Delete, path)?;
+		let dest = self.path_of(&hash);
+		if fs::try_exists(&dest).await.unwrap_or(false) {
+			artifacts::delete(path, true).await?;
+			debug!(
+				"Deleted artifact file {} that is already kept as {}",
+				path.display(),
+				hash
+			);
+			return Ok(Some(hash));
+		}
+
+		// Move the file into the store, falling back to copying it for when the store is on a different filesystem
+		fs::create_dir_all(&self.dir)
+			.await
+			.map_artifact_err(ArtifactAction::Delete, &self.dir)?;
+		if let Err(err) = fs::rename(path, &dest).await {
+			debug!(
+				"Unable to move {} into the store ({}), copying instead",
+				path.display(),
+				err
+			);
+			fs::copy(path, &dest)
+				.await
+				.map_artifact_err(ArtifactAction::Delete, path)?;
+			artifacts::delete(path, true).await?;
+		}
+
+		info!("Kept artifact file {} as {}", path.display(), hash);
+		Ok(Some(hash))
+	}
+
+	/// Copies a downloaded file into the store under its checksum, which must already have been verified.
+	/// Returns whether the file was added, or false if an identical file was already kept.
+	pub async fn add(&self, path: &Path, sha256: &str) -> Result<bool> {
+		let dest = self.path_of(sha256);
+		if fs::try_exists(&dest).await.unwrap_or(false) {
+			touch(&dest).await;
+			return Ok(false);
+		}
+
+		// Copy the file under a temporary name first so that a partial copy is never mistaken for a kept file. The name
+		// is unique to this copy since the same file can be added by several downloads at once.
+		fs::create_dir_all(&self.dir).await?;
+		let tmp_dest = self.dir.join(format!(
+			"{}.{:016x}.new",
+			sha256.to_lowercase(),
+			rand::thread_rng().gen::<u64>()
+		));
+		let result = match fs::copy(path, &tmp_dest).await {
+			Ok(_bytes) => fs::rename(&tmp_dest, &dest).await,
+			Err(err) => Err(err),
+		};
+
+		if let Err(err) = result {
+			if let Err(err) = artifacts::delete(&tmp_dest, true).await {
+				warn!("Unable to delete temporary store file {}: {}", tmp_dest.display(), err);
+			}
+
+			// Another download may have added an identical file in the meantime, which is just as good
+			if fs::try_exists(&dest).await.unwrap_or(false) {
+				touch(&dest).await;
+				return Ok(false);
+			}
+			return Err(err.into());
+		}
+
+		debug!("Added artifact file {} to the store as {}", path.display(), sha256);
+		Ok(true)
+	}
+
+	/// Copies a kept file with the given checksum to a destination, verifying its integrity along the way.
+	/// Returns whether the file was kept in the store.
+	pub async fn copy_to(&self, sha256: &str, dest: &Path) -> Result<bool> {
+		let path = self.path_of(sha256);
+		match fs::copy(&path, dest).await {
+			Ok(_bytes) => {}
+			Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+			Err(err) => return Err(err.into()),
+		}
+
+		// Ensure the copy matches the expected checksum - if it doesn't, the kept file is no good either
+		let actual = artifacts::hash_file(dest).await?;
+		if actual != sha256.to_lowercase() {
+			warn!("Kept artifact file {} is corrupt, removing it", path.display());
+			for corrupt in [dest, &path] {
+				if let Err(err) = artifacts::delete(corrupt, true).await {
+					warn!("Unable to delete corrupt artifact file {}: {}", corrupt.display(), err);
+				}
+			}
+			return Err(Error::Checksum {
+				checksum: actual,
+				expected: sha256.to_owned(),
+				file: path.display().to_string(),
+			});
+		}
+
+		touch(&path).await;
+		debug!("Copied kept artifact file {} to {}", path.display(), dest.display());
+		Ok(true)
+	}
+
+	/// Deletes kept files that exceed the store's limits: first any that haven't been used within the maximum age,
+	/// then the least recently used ones until the total size is within the maximum size. Files whose checksums are in
+	/// the given set are never deleted, but they do count towards the total size. A maximum size of zero deletes every
+	/// file that isn't protected. Returns the number of files deleted.
+	pub async fn prune(&self, protected: &HashSet<String>) -> Result<usize> {
+		let mut entries = match fs::read_dir(&self.dir).await {
+			Ok(entries) => entries,
+			Err(err) if err.kind() == ErrorKind::NotFound => return Ok(0),
+			Err(err) => return Err(err.into()),
+		};
+
+		// Gather the size and last use time of every kept file
+		let mut files = Vec::new();
+		let mut total_size = 0_u64;
+		while let Some(entry) = entries.next_entry().await? {
+			let metadata = entry.metadata().await?;
+			let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+			total_size = total_size.saturating_add(metadata.len());
+			if !entry.file_name().to_str().is_some_and(|hash| protected.contains(hash)) {
+				files.push((entry.path(), metadata.len(), used));
+			}
+		}
+		files.sort_unstable_by_key(|(_, _, used)| *used);
+
+		let cutoff = self.max_age.and_then(|max_age| SystemTime::now().checked_sub(max_age));
+		let mut pruned = 0_usize;
+		for (path, size, used) in files {
+			let too_old = cutoff.is_some_and(|cutoff| used < cutoff);
+			let too_big = self
+				.max_size
+				.is_some_and(|max_size| max_size == 0 || total_size > max_size);
+			if !too_old && !too_big {
+				continue;
+			}
+
+			artifacts::delete(&path, true).await?;
+			total_size = total_size.saturating_sub(size);
+			pruned = pruned.saturating_add(1);
+		}
+
+		if pruned > 0 {
+			info!("Pruned {} kept artifact files from {}", pruned, self.dir.display());
+		}
+		Ok(pruned)
+	}
+
+	/// Deletes any temporary files left behind by additions to the store that were interrupted.
+	/// Returns the number of files deleted.
+	pub async fn remove_temporary_files(&self) -> Result<usize> {
+		let mut entries = match fs::read_dir(&self.dir).await {
+			Ok(entries) => entries,
+			Err(err) if err.kind() == ErrorKind::NotFound => return Ok(0),
+			Err(err) => return Err(err.into()),
+		};
+
+		let mut removed = 0_usize;
+		while let Some(entry) = entries.next_entry().await? {
+			if entry.path().extension().is_some_and(|ext| ext == "new") {
+				artifacts::delete(&entry.path(), true).await?;
+				removed = removed.saturating_add(1);
+			}
+		}
+
+		if removed > 0 {
+			info!("Removed {} temporary files from {}", removed, self.dir.display());
+		}
+		Ok(removed)
+	}
+}
+
+/// Marks a kept file as just used by updating its modification time, which pruning goes by.
+/// Failures are only logged, since they just mean the file may be pruned sooner.
+async fn touch(path: &Path) {
+	let result = match fs::OpenOptions::new().write(true).open(path).await {
+		Ok(file) => file.into_std().await.set_modified(SystemTime::now()),
+		Err(err) => Err(err),
+	};
+	if let Err(err) = result {
+		debug!(
+			"Unable to update modification time of kept artifact file {}: {}",
+			path.display(),
+			err
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{env, fs, path::PathBuf, process};
+
+	use futures_util::future;
+	use sha2::{Digest, Sha256};
+
+	use super::ArtifactStore;
+
+	/// Creates an empty temporary directory for a test
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = env::temp_dir().join(format!("resolute-test-{}-{name}", process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).expect("create temporary directory");
+		dir
+	}
+
+	#[tokio::test]
+	async fn adds_the_same_file_concurrently() {
+		let dir = temp_dir("store-concurrent");
+		let contents = "mod contents".repeat(64 * 1024);
+		let sha256 = format!("{:x}", Sha256::digest(&contents));
+		let sources: Vec<PathBuf> = (0..8).map(|i| dir.join(format!("download-{i}.dll"))).collect();
+		for source in &sources {
+			fs::write(source, &contents).expect("write download");
+		}
+
+		let store = ArtifactStore::new(dir.join("store"));
+		let results = future::join_all(sources.iter().map(|source| store.add(source, &sha256))).await;
+		let added = results
+			.into_iter()
+			.map(|result| result.expect("add file to store"))
+			.filter(|added| *added)
+			.count();
+		assert!(added >= 1);
+
+		assert_eq!(
+			fs::read_to_string(store.path_of(&sha256)).expect("read kept file"),
+			contents
+		);
+		let kept: Vec<_> = fs::read_dir(&store.dir)
+			.expect("list store")
+			.map(|entry| entry.expect("read store entry").file_name())
+			.collect();
+		assert_eq!(kept, [sha256.as_str()]);
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+}
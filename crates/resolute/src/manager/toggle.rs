@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+
+use log::{debug, error, info};
+use tokio::fs;
+
+use crate::models::{ModArtifact, ModVersion};
+use crate::Result;
+
+use super::artifacts::{self, ArtifactAction, MappableToArtifactError};
+
+/// Handles enabling and disabling mods
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Toggler {
+	pub base_dest: PathBuf,
+}
+
+impl Toggler {
+	pub fn new(base_dest: impl AsRef<Path>) -> Self {
+		Self {
+			base_dest: base_dest.as_ref().to_owned(),
+		}
+	}
+
+	/// Disables all installed artifacts for a specific mod version by renaming them to their disabled names
+	pub async fn disable_version(&self, version: &ModVersion) -> Result<()> {
+		self.toggle_version(version, false).await
+	}
+
+	/// Enables all disabled artifacts for a specific mod version by renaming them back to their normal names
+	pub async fn enable_version(&self, version: &ModVersion) -> Result<()> {
+		self.toggle_version(version, true).await
+	}
+
+	/// Renames all artifacts for a specific mod version to or from their disabled names.
+	/// Either all of the artifacts are renamed or none of them are - if any rename fails, the successful ones are
+	/// renamed back.
+	async fn toggle_version(&self, version: &ModVersion, enable: bool) -> Result<()> {
+		// Rename all of the artifacts and track any successful ones - on an error, abort any further ones
+		let mut renamed = Vec::new();
+		let mut toggle_error = None;
+		for artifact in &version.artifacts {
+			match self.toggle_artifact(artifact, enable).await {
+				Ok(Some(toggled)) => renamed.push(toggled),
+				Ok(None) => {}
+				Err(err) => {
+					toggle_error = Some(err);
+					break;
+				}
+			}
+		}
+
+		// If an error was encountered, rename the successful ones back
+		if let Some(err) = toggle_error {
+			error!("Error renaming artifacts, undoing any successful ones: {}", err);
+
+			for toggled in renamed {
+				let artifact = toggled.artifact;
+				if let Err(err) = toggled.undo().await {
+					error!("Error undoing rename of artifact ({}): {}", artifact, err);
+				}
+			}
+
+			return Err(err);
+		}
+
+		Ok(())
+	}
+
+	/// Renames a single artifact to or from its disabled name.
+	/// If the artifact is already in the requested state, nothing is renamed and None is returned.
+	pub async fn toggle_artifact<'a>(
+		&self,
+		artifact: &'a ModArtifact,
+		enable: bool,
+	) -> Result<Option<ToggledArtifact<'a>>> {
+		let enabled_dest = artifact
+			.dest_within(&self.base_dest)
+			.map_pathless_artifact_err(ArtifactAction::Rename)?;
+		let disabled_dest =
+			ModArtifact::disabled_dest(&enabled_dest).map_pathless_artifact_err(ArtifactAction::Rename)?;
+		let (from, to) = if enable {
+			(disabled_dest, enabled_dest)
+		} else {
+			(enabled_dest, disabled_dest)
+		};
+
+		// Skip the artifact if it's already been renamed
+		if !fs::try_exists(&from).await.unwrap_or(false) && fs::try_exists(&to).await.unwrap_or(false) {
+			debug!("Artifact file {} is already in place, skipping rename", to.display());
+			return Ok(None);
+		}
+
+		artifacts::rename(&from, &to, false).await?;
+		info!("Renamed artifact file {} to {}", from.display(), to.display());
+
+		Ok(Some(ToggledArtifact { artifact, from, to }))
+	}
+}
+
+/// An artifact that has been renamed to or from its disabled name
+#[derive(Debug)]
+#[must_use]
+pub struct ToggledArtifact<'a> {
+	pub artifact: &'a ModArtifact,
+	from: PathBuf,
+	to: PathBuf,
+}
+
+impl ToggledArtifact<'_> {
+	/// Renames the artifact back to its original name
+	pub async fn undo(self) -> Result<()> {
+		artifacts::rename(&self.to, &self.from, false).await?;
+		debug!(
+			"Renamed artifact file {} back to {}",
+			self.to.display(),
+			self.from.display()
+		);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		env, fs,
+		path::{Path, PathBuf},
+		process,
+	};
+
+	use semver::Version;
+	use url::Url;
+
+	use super::Toggler;
+	use crate::models::{ModArtifact, ModDependencyMap, ModVersion};
+
+	/// Creates an empty temporary directory for a test
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = env::temp_dir().join(format!("resolute-test-{}-{name}", process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(dir.join("rml_mods")).expect("create temporary directory");
+		dir
+	}
+
+	/// Builds a version with an artifact installed in rml_mods for each filename
+	fn version(filenames: &[&str]) -> ModVersion {
+		ModVersion {
+			semver: Version::new(1, 0, 0),
+			artifacts: filenames
+				.iter()
+				.map(|filename| ModArtifact {
+					url: Url::parse(&format!("https://example.com/{filename}")).expect("valid URL"),
+					sha256: String::new(),
+					filename: None,
+					install_location: Some("/rml_mods".to_owned()),
+					override_filename: None,
+				})
+				.collect(),
+			dependencies: ModDependencyMap::new(),
+			conflicts: ModDependencyMap::new(),
+			release_url: None,
+			changelog: None,
+		}
+	}
+
+	fn exists(path: &Path) -> bool {
+		fs::metadata(path).is_ok()
+	}
+
+	#[tokio::test]
+	async fn renames_artifacts_to_and_from_disabled_names() {
+		let dir = temp_dir("toggle-renames");
+		let mods = dir.join("rml_mods");
+		fs::write(mods.join("A.dll"), "a").expect("write file");
+		fs::write(mods.join("B.dll"), "b").expect("write file");
+		let toggler = Toggler::new(&dir);
+		let version = version(&["A.dll", "B.dll"]);
+
+		toggler.disable_version(&version).await.expect("disable version");
+		assert!(!exists(&mods.join("A.dll")) && !exists(&mods.join("B.dll")));
+		assert!(exists(&mods.join("A.dll.disabled")) && exists(&mods.join("B.dll.disabled")));
+
+		// Artifacts that are already disabled are left alone
+		toggler.disable_version(&version).await.expect("disable version again");
+		assert!(exists(&mods.join("A.dll.disabled")) && exists(&mods.join("B.dll.disabled")));
+
+		toggler.enable_version(&version).await.expect("enable version");
+		assert!(exists(&mods.join("A.dll")) && exists(&mods.join("B.dll")));
+		assert!(!exists(&mods.join("A.dll.disabled")) && !exists(&mods.join("B.dll.disabled")));
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+
+	#[tokio::test]
+	async fn undoes_renames_when_one_fails() {
+		let dir = temp_dir("toggle-undo");
+		let mods = dir.join("rml_mods");
+		fs::write(mods.join("A.dll"), "a").expect("write file");
+		let toggler = Toggler::new(&dir);
+
+		// B.dll is missing, so renaming it fails after A.dll has already been renamed
+		toggler
+			.disable_version(&version(&["A.dll", "B.dll"]))
+			.await
+			.expect_err("fail to disable version");
+		assert!(exists(&mods.join("A.dll")));
+		assert!(!exists(&mods.join("A.dll.disabled")));
+
+		fs::remove_dir_all(&dir).expect("remove temporary directory");
+	}
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+
+use semver::Version;
+
+use crate::models::{InstallReason, ModArtifact, ModVersion, ResoluteMod};
+use crate::{Error, Result};
+
+/// A set of mod installs, uninstalls and changes to installed mods to carry out together as a single operation with
+/// [`ModManager::commit`](super::ModManager::commit). The artifacts of every install are downloaded before any of them
+/// are put in place, all database changes are made in a single write transaction, and if any part fails, everything
+/// is undone.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Transaction {
+	pub(crate) installs: Vec<StagedInstall>,
+	pub(crate) uninstalls: Vec<StagedUninstall>,
+	pub(crate) changes: Vec<StagedChange>,
+}
+
+impl Transaction {
+	/// Creates a new empty transaction
+	#[inline]
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Stages the install of a mod version, replacing the mod's installed version if it has one.
+	/// Mods that were already installed explicitly stay that way, others are marked as explicitly installed only if
+	/// requested.
+	pub fn install(&mut self, rmod: &ResoluteMod, version: &Version, explicit: bool) -> Result<&mut Self> {
+		let install_reason = if explicit {
+			Some(InstallReason::Explicit)
+		} else {
+			rmod.install_reason.or(Some(InstallReason::Dependency))
+		};
+		self.stage_install(rmod, version, install_reason, true)
+	}
+
+	/// Stages putting a mod at a version with the given enabled state and install reason, such as when rolling back to
+	/// a snapshot. If the mod already has the version installed, only its enabled state and install reason are changed,
+	/// and if those already match too, nothing is staged.
+	pub fn restore(
+		&mut self,
+		rmod: &ResoluteMod,
+		version: &Version,
+		active: bool,
+		install_reason: Option<InstallReason>,
+	) -> Result<&mut Self> {
+		if rmod.installed_version.as_ref() != Some(version) {
+			return self.stage_install(rmod, version, install_reason, active);
+		}
+		if rmod.active == active && rmod.install_reason == install_reason {
+			return Ok(self);
+		}
+
+		let installed_version = rmod
+			.versions
+			.get(version)
+			.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), version.clone()))?
+			.clone();
+		let mut stored = rmod.clone();
+		stored.install_reason = install_reason;
+		stored.active = active;
+
+		self.changes.push(StagedChange {
+			rmod: stored,
+			version: installed_version,
+			was_active: rmod.active,
+		});
+		Ok(self)
+	}
+
+	/// Stages the install of a mod version with the given install reason and enabled state
+	fn stage_install(
+		&mut self,
+		rmod: &ResoluteMod,
+		version: &Version,
+		install_reason: Option<InstallReason>,
+		active: bool,
+	) -> Result<&mut Self> {
+		let new_version = rmod
+			.versions
+			.get(version)
+			.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), version.clone()))?
+			.clone();
+		let old_version = match &rmod.installed_version {
+			Some(semver) => Some(
+				rmod.versions
+					.get(semver)
+					.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), semver.clone()))?
+					.clone(),
+			),
+			None => None,
+		};
+
+		let mut stored = rmod.clone();
+		stored.install_reason = install_reason;
+		stored.installed_version = Some(version.clone());
+		stored.active = active;
+
+		self.installs.push(StagedInstall {
+			rmod: stored,
+			version: new_version,
+			replaces: old_version,
+		});
+		Ok(self)
+	}
+
+	/// Stages downloading some of the artifacts of a mod's installed version again and putting them in place of the
+	/// current files, such as to repair them. The mod itself is stored unchanged.
+	pub fn reinstall_artifacts(&mut self, rmod: &ResoluteMod, artifacts: Vec<ModArtifact>) -> Result<&mut Self> {
+		let Some(semver) = &rmod.installed_version else {
+			return Err(Error::ModNotInstalled(Box::new(rmod.clone())));
+		};
+		let mut version = rmod
+			.versions
+			.get(semver)
+			.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), semver.clone()))?
+			.clone();
+		version.artifacts = artifacts;
+
+		self.installs.push(StagedInstall {
+			rmod: rmod.clone(),
+			version: version.clone(),
+			replaces: Some(version),
+		});
+		Ok(self)
+	}
+
+	/// Stages the uninstall of a mod's installed version
+	pub fn uninstall(&mut self, rmod: &ResoluteMod) -> Result<&mut Self> {
+		let Some(semver) = &rmod.installed_version else {
+			return Err(Error::ModNotInstalled(Box::new(rmod.clone())));
+		};
+		let version = rmod
+			.versions
+			.get(semver)
+			.ok_or_else(|| Error::UnknownVersion(rmod.id.clone(), semver.clone()))?
+			.clone();
+
+		self.uninstalls.push(StagedUninstall {
+			rmod: rmod.clone(),
+			version,
+		});
+		Ok(self)
+	}
+
+	/// Checks whether the transaction has nothing staged
+	#[inline]
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.installs.is_empty() && self.uninstalls.is_empty() && self.changes.is_empty()
+	}
+}
+
+impl fmt::Display for Transaction {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let installs = self
+			.installs
+			.iter()
+			.map(|install| match &install.replaces {
+				Some(old) => format!("{} v{} -> v{}", install.rmod.id, old.semver, install.version.semver),
+				None => format!("{} v{}", install.rmod.id, install.version.semver),
+			})
+			.collect::<Vec<_>>()
+			.join(", ");
+		let uninstalls = self
+			.uninstalls
+			.iter()
+			.map(|uninstall| format!("{} v{}", uninstall.rmod.id, uninstall.version.semver))
+			.collect::<Vec<_>>()
+			.join(", ");
+		let changes = self
+			.changes
+			.iter()
+			.map(|change| {
+				let state = if change.rmod.active { "enabled" } else { "disabled" };
+				format!("{} v{} ({state})", change.rmod.id, change.version.semver)
+			})
+			.collect::<Vec<_>>()
+			.join(", ");
+		write!(f, "install [{installs}], uninstall [{uninstalls}], change [{changes}]")
+	}
+}
+
+/// A mod version staged to be installed as part of a [`Transaction`]
+#[derive(Debug, Clone)]
+pub(crate) struct StagedInstall {
+	/// Mod as it will be stored once installed
+	pub(crate) rmod: ResoluteMod,
+	/// Version to install
+	pub(crate) version: ModVersion,
+	/// Installed version being replaced, if any
+	pub(crate) replaces: Option<ModVersion>,
+}
+
+/// An installed mod version staged to be uninstalled as part of a [`Transaction`]
+#[derive(Debug, Clone)]
+pub(crate) struct StagedUninstall {
+	/// Mod being uninstalled
+	pub(crate) rmod: ResoluteMod,
+	/// Installed version to uninstall
+	pub(crate) version: ModVersion,
+}
+
+/// A change to the enabled state or install reason of an installed mod version staged as part of a [`Transaction`]
+#[derive(Debug, Clone)]
+pub(crate) struct StagedChange {
+	/// Mod as it will be stored once changed
+	pub(crate) rmod: ResoluteMod,
+	/// Installed version of the mod
+	pub(crate) version: ModVersion,
+	/// Whether the mod was enabled before the change
+	pub(crate) was_active: bool,
+}
@@ -0,0 +1,252 @@
+use std::{
+	collections::{BTreeMap, HashMap, HashSet},
+	fmt,
+	io::ErrorKind,
+	path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use tokio::fs;
+
+use crate::Result;
+
+use super::artifacts;
+
+/// Result of verifying the files of the installed mods against the checksums of their artifacts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct IntegrityReport {
+	/// Problems with each installed mod's files, by mod ID. Mods without any problems are left out.
+	pub mods: BTreeMap<String, Vec<IntegrityIssue>>,
+	/// DLL files in the install locations that don't belong to any installed mod
+	pub extra: Vec<PathBuf>,
+}
+
+impl IntegrityReport {
+	/// Checks whether no problems were found
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.mods.is_empty() && self.extra.is_empty()
+	}
+
+	/// Gets the paths of a mod's artifact files that are missing or modified
+	pub fn broken_paths(&self, id: &str) -> impl Iterator<Item = &Path> {
+		self.mods.get(id).into_iter().flatten().filter_map(|issue| match issue {
+			IntegrityIssue::Missing { path } | IntegrityIssue::Modified { path, .. } => Some(path.as_path()),
+			IntegrityIssue::Duplicate { .. } => None,
+		})
+	}
+}
+
+impl fmt::Display for IntegrityReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let issues: Vec<String> = self
+			.mods
+			.iter()
+			.flat_map(|(id, issues)| issues.iter().map(move |issue| format!("{id}: {issue}")))
+			.chain(self.extra.iter().map(|path| format!("extra file {}", path.display())))
+			.collect();
+		if issues.is_empty() {
+			write!(f, "no problems")
+		} else {
+			write!(f, "[{}]", issues.join(", "))
+		}
+	}
+}
+
+/// Problem with one of an installed mod's files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum IntegrityIssue {
+	/// An artifact file doesn't exist
+	Missing { path: PathBuf },
+
+	/// An artifact file's contents don't match the artifact's checksum
+	Modified {
+		path: PathBuf,
+		expected: String,
+		actual: String,
+	},
+
+	/// A copy of one of the mod's artifact files exists at another path
+	Duplicate { path: PathBuf, original: PathBuf },
+}
+
+impl fmt::Display for IntegrityIssue {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Missing { path } => write!(f, "missing {}", path.display()),
+			Self::Modified { path, .. } => write!(f, "modified {}", path.display()),
+			Self::Duplicate { path, original } => {
+				write!(f, "duplicate {} of {}", path.display(), original.display())
+			}
+		}
+	}
+}
+
+/// An installed artifact file that's expected to exist
+pub(crate) struct ExpectedFile<'a> {
+	/// ID of the mod the artifact belongs to
+	pub id: &'a str,
+	/// Path the artifact file should be at (its disabled path if the mod is disabled)
+	pub path: PathBuf,
+	/// Checksum of the artifact
+	pub sha256: String,
+}
+
+/// Verifies expected artifact files, then looks for copies of them and other DLL files in the given directories.
+/// Files directly in the base directory are only checked if they're expected, since it holds much more than mods.
+pub(crate) async fn verify_files(
+	expected: &[ExpectedFile<'_>],
+	dirs: &HashSet<PathBuf>,
+	base_dest: &Path,
+) -> Result<IntegrityReport> {
+	// Hash every file in the install locations, leaving any leftovers of interrupted operations to be recovered
+	let mut hashes = HashMap::new();
+	for dir in dirs.iter().filter(|dir| *dir != base_dest) {
+		let mut entries = match fs::read_dir(dir).await {
+			Ok(entries) => entries,
+			Err(err) if err.kind() == ErrorKind::NotFound => continue,
+			Err(err) => return Err(err.into()),
+		};
+
+		while let Some(entry) = entries.next_entry().await? {
+			let path = entry.path();
+			let leftover = path
+				.extension()
+				.is_some_and(|ext| ext == "new" || ext == "old" || ext == "resume");
+			if !leftover && !entry.file_type().await?.is_dir() {
+				let hash = artifacts::hash_file(&path).await?;
+				hashes.insert(path, hash);
+			}
+		}
+	}
+
+	// Check each expected file against its artifact's checksum
+	let mut report = IntegrityReport::default();
+	for file in expected {
+		let actual = match hashes.get(&file.path) {
+			Some(hash) => Some(hash.clone()),
+			None if fs::try_exists(&file.path).await? => Some(artifacts::hash_file(&file.path).await?),
+			None => None,
+		};
+
+		let issue = match actual {
+			None => IntegrityIssue::Missing {
+				path: file.path.clone(),
+			},
+			Some(actual) if actual != file.sha256 => IntegrityIssue::Modified {
+				path: file.path.clone(),
+				expected: file.sha256.clone(),
+				actual,
+			},
+			Some(_) => continue,
+		};
+		report.mods.entry(file.id.to_owned()).or_default().push(issue);
+	}
+
+	// Any other file is either a copy of an expected one or an extra file
+	let expected_paths: HashSet<&Path> = expected.iter().map(|file| file.path.as_path()).collect();
+	let originals: HashMap<&str, &ExpectedFile<'_>> =
+		expected.iter().map(|file| (file.sha256.as_str(), file)).collect();
+	let mut others: Vec<(&PathBuf, &String)> = hashes
+		.iter()
+		.filter(|(path, _)| !expected_paths.contains(path.as_path()))
+		.collect();
+	others.sort_unstable();
+	for (path, hash) in others {
+		if let Some(original) = originals.get(hash.as_str()) {
+			report
+				.mods
+				.entry(original.id.to_owned())
+				.or_default()
+				.push(IntegrityIssue::Duplicate {
+					path: path.clone(),
+					original: original.path.clone(),
+				});
+		} else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dll")) {
+			report.extra.push(path.clone());
+		}
+	}
+
+	Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{collections::HashSet, env, fs, path::PathBuf, process};
+
+	use sha2::{Digest, Sha256};
+
+	use super::{verify_files, ExpectedFile, IntegrityIssue};
+
+	/// Creates an empty temporary directory for a test
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = env::temp_dir().join(format!("resolute-test-{}-{name}", process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).expect("create temporary directory");
+		dir
+	}
+
+	fn expected(path: PathBuf, contents: &str) -> ExpectedFile<'static> {
+		ExpectedFile {
+			id: "mod",
+			path,
+			sha256: format!("{:x}", Sha256::digest(contents)),
+		}
+	}
+
+	#[tokio::test]
+	async fn reports_missing_modified_duplicate_and_extra_files() {
+		let base = temp_dir("verify-files");
+		let mods_dir = base.join("rml_mods");
+		fs::create_dir_all(&mods_dir).expect("create mod directory");
+		for (filename, contents) in [
+			("Intact.dll", "intact"),
+			("Modified.dll", "tampered"),
+			("Copy.dll", "intact"),
+			("Extra.dll", "extra"),
+			("Notes.txt", "notes"),
+			("Intact.dll.new", "intact"),
+		] {
+			fs::write(mods_dir.join(filename), contents).expect("write mod file");
+		}
+		fs::write(base.join("Resonite.dll"), "resonite").expect("write base file");
+
+		let files = [
+			expected(mods_dir.join("Intact.dll"), "intact"),
+			expected(mods_dir.join("Modified.dll"), "modified"),
+			expected(mods_dir.join("Missing.dll"), "missing"),
+		];
+		let dirs = HashSet::from([mods_dir.clone(), base.clone()]);
+		let report = verify_files(&files, &dirs, &base).await.expect("verify files");
+
+		// Leftovers of interrupted operations, files that aren't DLLs and anything directly in the base directory
+		// aren't reported
+		assert_eq!(
+			report.mods["mod"],
+			[
+				IntegrityIssue::Modified {
+					path: mods_dir.join("Modified.dll"),
+					expected: files[1].sha256.clone(),
+					actual: format!("{:x}", Sha256::digest("tampered")),
+				},
+				IntegrityIssue::Missing {
+					path: mods_dir.join("Missing.dll"),
+				},
+				IntegrityIssue::Duplicate {
+					path: mods_dir.join("Copy.dll"),
+					original: mods_dir.join("Intact.dll"),
+				},
+			]
+		);
+		assert_eq!(report.extra, [mods_dir.join("Extra.dll")]);
+		assert_eq!(
+			report.broken_paths("mod").collect::<Vec<_>>(),
+			[mods_dir.join("Modified.dll"), mods_dir.join("Missing.dll")]
+		);
+
+		fs::remove_dir_all(&base).expect("remove temporary directory");
+	}
+}
@@ -16,7 +16,10 @@ use tokio::{
 };
 use url::Url;
 
-use crate::{Error, Result};
+use crate::{
+	retry::{check_response, RetryPolicy},
+	Error, Result,
+};
 
 /// Default Resonite mod manifest JSON file URL (from the Resonite Modding Group repository)
 pub const MANIFEST_URL: &str =
@@ -46,12 +49,15 @@ impl Client {
 	pub async fn download(&self) -> Result<String> {
 		// Download the manifest
 		info!("Downloading manifest from {}", self.config.remote_url);
-		let response = self.http_client.get(self.config.remote_url.clone()).send().await?;
-		let status = response.status();
-		if !status.is_success() {
-			return Err(Error::Http(status));
-		}
-		let json = response.text().await?;
+		let description = format!("manifest download from {}", self.config.remote_url);
+		let json = self
+			.config
+			.retry
+			.run(description, || async {
+				let response = self.http_client.get(self.config.remote_url.clone()).send().await?;
+				Ok(check_response(response)?.text().await?)
+			})
+			.await?;
 
 		// Cache the JSON to disk if necessary
 		if let Some(cache) = &self.config.cache_file_path {
@@ -176,6 +182,13 @@ impl ClientBuilder {
 		self
 	}
 
+	/// Sets the policy for retrying failed manifest downloads
+	#[must_use]
+	pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+		self.config.retry = policy;
+		self
+	}
+
 	/// Creates a Client using this builder's configuration and HTTP client
 	#[must_use]
 	pub fn build(self) -> Client {
@@ -190,6 +203,7 @@ pub struct Config {
 	pub remote_url: Url,
 	pub cache_file_path: Option<PathBuf>,
 	pub cache_stale_after: Option<Duration>,
+	pub retry: RetryPolicy,
 }
 
 impl Config {
@@ -212,6 +226,7 @@ impl Default for Config {
 			remote_url: Url::parse(MANIFEST_URL).expect("cannot parse default manifest url"),
 			cache_file_path: None,
 			cache_stale_after: Some(Duration::from_secs(60 * 60 * 6)),
+			retry: RetryPolicy::default(),
 		}
 	}
 }
@@ -1,5 +1,7 @@
 #[cfg(all(feature = "db", feature = "models_v1"))]
 pub mod v1;
+#[cfg(all(feature = "db", feature = "models_v2"))]
 pub mod v2;
+pub mod v3;
 
-pub use v2::*;
+pub use v3::*;
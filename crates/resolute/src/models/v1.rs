@@ -95,7 +95,7 @@ pub struct ModVersion {
 	pub release_url: Option<Url>,
 }
 
-impl From<ModVersion> for super::v2::ModVersion {
+impl From<ModVersion> for super::ModVersion {
 	fn from(value: ModVersion) -> Self {
 		Self {
 			semver: value.semver,
@@ -108,8 +108,8 @@ impl From<ModVersion> for super::v2::ModVersion {
 	}
 }
 
-impl From<super::v2::ModVersion> for ModVersion {
-	fn from(value: super::v2::ModVersion) -> Self {
+impl From<super::ModVersion> for ModVersion {
+	fn from(value: super::ModVersion) -> Self {
 		Self {
 			semver: value.semver,
 			artifacts: value.artifacts.into_iter().map(Into::into).collect(),
@@ -130,7 +130,7 @@ pub struct ModArtifact {
 	pub install_location: Option<String>,
 }
 
-impl From<ModArtifact> for super::v2::ModArtifact {
+impl From<ModArtifact> for super::ModArtifact {
 	fn from(value: ModArtifact) -> Self {
 		Self {
 			url: value.url,
@@ -142,8 +142,8 @@ impl From<ModArtifact> for super::v2::ModArtifact {
 	}
 }
 
-impl From<super::v2::ModArtifact> for ModArtifact {
-	fn from(value: super::v2::ModArtifact) -> Self {
+impl From<super::ModArtifact> for ModArtifact {
+	fn from(value: super::ModArtifact) -> Self {
 		Self {
 			url: value.url,
 			sha256: value.sha256,
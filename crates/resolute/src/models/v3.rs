@@ -0,0 +1,1170 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	env,
+	ffi::OsString,
+	fmt,
+	path::{Path, PathBuf},
+};
+
+#[cfg(feature = "db")]
+use native_db::{native_db, ToKey};
+#[cfg(feature = "db")]
+use native_model::{native_model, Model};
+use once_cell::sync::Lazy;
+use path_clean::PathClean;
+use semver::{BuildMetadata, Prerelease, Version, VersionReq};
+use serde::{Deserialize, Serialize, Serializer};
+use url::Url;
+
+use crate::{
+	manifest::{
+		ManifestAuthors, ManifestData, ManifestEntryArtifact, ManifestEntryDependencies, ManifestEntryVersions,
+	},
+	Error,
+};
+
+/// Group string for unrecognized mods
+pub const UNRECOGNIZED_GROUP: &str = "dev.gawdl3y.resolute.unrecognized";
+
+/// Semver representing an unknown version
+pub static UNRECOGNIZED_SEMVER: Lazy<Version> = Lazy::new(|| Version {
+	major: 0,
+	minor: 0,
+	patch: 0,
+	pre: Prerelease::new("unknown").expect("unable to create prerelease struct for unrecognized semver"),
+	build: BuildMetadata::default(),
+});
+
+/// Base URL for an unrecognized artifact
+pub static UNRECOGNIZED_ARTIFACT_BASE_URL: Lazy<Url> = Lazy::new(|| {
+	Url::parse("resolute://unrecognized/artifact").expect("unable to parse unrecognized artifact base url")
+});
+
+/// Builds a mod map from the given raw manifest data
+#[must_use]
+pub fn load_manifest(manifest: ManifestData) -> ResoluteModMap {
+	manifest
+		.objects
+		.into_values()
+		.flat_map(|object| {
+			// Build the list of authors for the group
+			let group_authors = build_mod_authors(object.authors);
+
+			// Build the list of mods
+			object.entries.into_iter().map(move |(id, entry)| {
+				// Combine the group authors and mod's additional authors
+				let mut authors = group_authors.clone();
+				if let Some(additional_authors) = entry.additional_authors {
+					authors.append(&mut build_mod_authors(additional_authors));
+				}
+
+				ResoluteMod {
+					id,
+					authors,
+					versions: build_mod_versions_map(entry.versions, &entry.category),
+					name: entry.name,
+					description: entry.description,
+					category: entry.category,
+					source_location: entry.source_location,
+					website: entry.website,
+					tags: entry.tags,
+					flags: entry.flags,
+					platforms: entry.platforms,
+					installed_version: None,
+					install_reason: None,
+					pin: None,
+					active: false,
+				}
+			})
+		})
+		.map(|rmod| (rmod.id.clone(), rmod))
+		.collect()
+}
+
+/// Build an authors list from manifest data
+fn build_mod_authors(authors: ManifestAuthors) -> Vec<ModAuthor> {
+	authors
+		.into_iter()
+		.map(|(name, author)| ModAuthor {
+			name,
+			url: author.url,
+			icon: author.icon,
+			support: author.support,
+		})
+		.collect()
+}
+
+/// Build a versions map from manifest data
+fn build_mod_versions_map(versions: ManifestEntryVersions, category: &str) -> HashMap<Version, ModVersion> {
+	versions
+		.into_iter()
+		.map(|(semver, version)| ModVersion {
+			semver,
+			dependencies: build_mod_version_dependencies(version.dependencies),
+			conflicts: build_mod_version_dependencies(version.conflicts),
+			artifacts: build_mod_version_artifacts(version.artifacts, category),
+			release_url: version.release_url,
+			changelog: version.changelog,
+		})
+		.map(|version| (version.semver.clone(), version))
+		.collect()
+}
+
+/// Build a dependencies map from manifest data for a mod version
+fn build_mod_version_dependencies(dependencies: Option<ManifestEntryDependencies>) -> HashMap<String, VersionReq> {
+	if let Some(depends) = dependencies {
+		depends
+			.into_iter()
+			.map(|(depend_id, depend)| (depend_id, depend.version))
+			.collect()
+	} else {
+		HashMap::new()
+	}
+}
+
+/// Build an artifacts map from manifest data for a mod version
+fn build_mod_version_artifacts(artifacts: Vec<ManifestEntryArtifact>, category: &str) -> Vec<ModArtifact> {
+	artifacts
+		.into_iter()
+		.map(|artifact| ModArtifact::from_manifest_and_category(artifact, category))
+		.collect()
+}
+
+/// Parses a version pin, which can either be an exact version (such as "1.2.3") or a version requirement (such as
+/// "^1.2" or ">=1.0, <2.0"). Exact versions are treated as requiring that exact version rather than as caret
+/// requirements like semver would normally treat them.
+pub fn parse_pin(pin: &str) -> Result<VersionReq, semver::Error> {
+	let pin = pin.trim();
+	match Version::parse(pin) {
+		Ok(version) => VersionReq::parse(&format!("={version}")),
+		Err(_) => VersionReq::parse(pin),
+	}
+}
+
+/// `ResoluteMod`s mapped by their ID
+pub type ResoluteModMap = HashMap<String, ResoluteMod>;
+
+/// A single Resonite mod with all information relevant to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(all(feature = "db", feature = "models_v2"), native_model(id = 1, version = 3, from = super::v2::ResoluteMod))]
+#[cfg_attr(all(feature = "db", not(feature = "models_v2")), native_model(id = 1, version = 3))]
+#[cfg_attr(feature = "db", native_db)]
+#[non_exhaustive]
+pub struct ResoluteMod {
+	// The primary_key and secondary_key macros don't work with cfg_attr for whatever reason
+	#[cfg(feature = "db")]
+	#[primary_key]
+	pub id: String,
+	#[cfg(not(feature = "db"))]
+	pub id: String,
+
+	// The primary_key and secondary_key macros don't work with cfg_attr for whatever reason
+	#[cfg(feature = "db")]
+	#[secondary_key]
+	pub name: String,
+	#[cfg(not(feature = "db"))]
+	pub name: String,
+
+	pub description: String,
+	pub category: String,
+	pub authors: Vec<ModAuthor>,
+	#[serde(rename = "sourceLocation")]
+	pub source_location: Option<Url>,
+	pub website: Option<Url>,
+	pub tags: Option<Vec<String>>,
+	pub flags: Option<Vec<String>>,
+	pub platforms: Option<Vec<String>>,
+	#[serde(serialize_with = "serialize_sorted")]
+	pub versions: HashMap<Version, ModVersion>,
+	#[serde(rename = "installedVersion")]
+	pub installed_version: Option<Version>,
+	#[serde(rename = "installReason")]
+	pub install_reason: Option<InstallReason>,
+	pub pin: Option<VersionReq>,
+	pub active: bool,
+}
+
+impl ResoluteMod {
+	/// Gets the latest version available for the mod that its release channel allows
+	#[must_use]
+	pub fn latest_version(&self, channels: &ChannelPolicy) -> Option<&ModVersion> {
+		self.versions
+			.values()
+			.filter(|version| channels.allows(&self.id, &version.semver))
+			.max_by(|a, b| a.semver.cmp(&b.semver))
+	}
+
+	/// Gets the latest version available for the mod that both its release channel and its pin (if any) allow
+	#[must_use]
+	pub fn latest_allowed_version(&self, channels: &ChannelPolicy) -> Option<&ModVersion> {
+		self.versions
+			.values()
+			.filter(|version| channels.allows(&self.id, &version.semver) && self.allows_version(&version.semver))
+			.max_by(|a, b| a.semver.cmp(&b.semver))
+	}
+
+	/// Checks whether the mod's pin (if any) allows a version of it to be installed
+	#[must_use]
+	pub fn allows_version(&self, version: &Version) -> bool {
+		match &self.pin {
+			Some(pin) => pin.matches(version),
+			None => true,
+		}
+	}
+
+	/// Checks whether there is a newer version of the mod available than the installed version that both its release
+	/// channel and its pin (if any) allow. If the mod isn't installed, None is returned.
+	#[must_use]
+	pub fn has_update(&self, channels: &ChannelPolicy) -> Option<bool> {
+		match &self.installed_version {
+			Some(installed_version) => Some(self.latest_allowed_version(channels)?.semver.gt(installed_version)),
+			None => None,
+		}
+	}
+
+	/// Checks whether this mod is installed only because other mods depend on it
+	#[must_use]
+	pub fn is_dependency_only(&self) -> bool {
+		self.installed_version.is_some() && self.install_reason == Some(InstallReason::Dependency)
+	}
+
+	/// Checks whether this mod is unrecognized (ID begins with [`UNRECOGNIZED_GROUP`])
+	#[must_use]
+	pub fn is_unrecognized(&self) -> bool {
+		self.id.starts_with(UNRECOGNIZED_GROUP)
+	}
+
+	/// Interprets the mod's flags from the manifest
+	#[must_use]
+	pub fn parsed_flags(&self) -> Vec<ModFlag> {
+		self.flags.iter().flatten().map(|flag| ModFlag::parse(flag)).collect()
+	}
+
+	/// Checks whether this mod has been deprecated
+	#[must_use]
+	pub fn is_deprecated(&self) -> bool {
+		self.parsed_flags().contains(&ModFlag::Deprecated)
+	}
+
+	/// Checks whether this mod has any known vulnerabilities
+	#[must_use]
+	pub fn is_vulnerable(&self) -> bool {
+		self.parsed_flags().iter().any(ModFlag::is_vulnerability)
+	}
+
+	/// Checks whether this mod supports a platform.
+	/// Mods that don't list any platforms are assumed to support all of them.
+	#[must_use]
+	pub fn supports_platform(&self, platform: Platform) -> bool {
+		match &self.platforms {
+			Some(platforms) if !platforms.is_empty() => platforms.iter().any(|listed| platform.is_covered_by(listed)),
+			_ => true,
+		}
+	}
+
+	/// Creates a new unrecognized mod from details about an encountered artifact file
+	pub fn new_unrecognized(
+		artifact_filename: impl AsRef<str>,
+		artifact_install_location: impl AsRef<str>,
+		artifact_sha256: impl AsRef<str>,
+	) -> Self {
+		let artifact_filename = artifact_filename.as_ref();
+		let artifact_path = PathBuf::from(artifact_filename);
+		let artifact_stem = artifact_path.file_stem().map_or(artifact_filename, |stem| {
+			stem.to_str()
+				.expect("unable to convert artifact filename stem to string")
+		});
+
+		let mut versions = HashMap::new();
+		let version = ModVersion::new_unrecognized(artifact_filename, &artifact_install_location, artifact_sha256);
+		let semver = version.semver.clone();
+		versions.insert(semver.clone(), version);
+
+		Self {
+			id: format!("{}.{}", UNRECOGNIZED_GROUP, artifact_stem.replace(' ', "-")),
+			name: artifact_stem.to_owned(),
+			description: format!("Unrecognized mod discovered in {}", artifact_install_location.as_ref()),
+			category: "Unrecognized".to_owned(),
+			authors: vec![ModAuthor::unknown()],
+			source_location: None,
+			website: None,
+			tags: Some(vec!["unrecognized".to_owned()]),
+			flags: None,
+			platforms: None,
+			versions,
+			installed_version: Some(semver),
+			install_reason: Some(InstallReason::Explicit),
+			pin: None,
+			active: true,
+		}
+	}
+}
+
+impl fmt::Display for ResoluteMod {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} ({})", self.name, self.id)
+	}
+}
+
+/// A named set of mods (with optionally pinned versions) that can be applied to an installation in one step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "db", native_model(id = 2, version = 1))]
+#[cfg_attr(feature = "db", native_db)]
+#[non_exhaustive]
+pub struct ModProfile {
+	// The primary_key and secondary_key macros don't work with cfg_attr for whatever reason
+	#[cfg(feature = "db")]
+	#[primary_key]
+	pub name: String,
+	#[cfg(not(feature = "db"))]
+	pub name: String,
+
+	/// Mod IDs mapped to the version to use for each of them. Mods without a version keep whatever version is
+	/// installed, or get the latest version allowed for them if they aren't installed.
+	pub mods: BTreeMap<String, Option<Version>>,
+}
+
+impl ModProfile {
+	/// Creates a new empty profile
+	pub fn new(name: impl Into<String>) -> Self {
+		Self {
+			name: name.into(),
+			mods: BTreeMap::new(),
+		}
+	}
+
+	/// Creates a new profile from the active mods in a mod map, with each of them pinned to its installed version
+	pub fn from_installed(name: impl Into<String>, mods: &ResoluteModMap) -> Self {
+		Self {
+			name: name.into(),
+			mods: mods
+				.values()
+				.filter(|rmod| rmod.active)
+				.filter_map(|rmod| Some((rmod.id.clone(), Some(rmod.installed_version.clone()?))))
+				.collect(),
+		}
+	}
+
+	/// Adds a mod to the profile, optionally pinned to a specific version
+	#[must_use]
+	pub fn with_mod(mut self, id: impl Into<String>, version: Option<Version>) -> Self {
+		self.mods.insert(id.into(), version);
+		self
+	}
+}
+
+impl fmt::Display for ModProfile {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} ({} mods)", self.name, self.mods.len())
+	}
+}
+
+/// A record of the installed mods at a point in time, taken before they're changed so that the change can be rolled
+/// back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "db", native_model(id = 3, version = 1))]
+#[cfg_attr(feature = "db", native_db)]
+#[non_exhaustive]
+pub struct Snapshot {
+	/// Unix timestamp (in milliseconds) of when the snapshot was taken
+	// The primary_key and secondary_key macros don't work with cfg_attr for whatever reason
+	#[cfg(feature = "db")]
+	#[primary_key]
+	pub id: u64,
+	#[cfg(not(feature = "db"))]
+	pub id: u64,
+
+	/// Summary of the change the snapshot was taken before
+	pub description: String,
+	/// Mods that were installed at the time
+	pub mods: Vec<SnapshotMod>,
+	/// Checksums of every artifact of the mod versions that were installed at the time
+	pub artifacts: Vec<String>,
+}
+
+impl Snapshot {
+	/// Creates a snapshot of the installed mods among the given ones
+	#[must_use]
+	pub fn new(id: u64, description: impl Into<String>, mods: &[ResoluteMod]) -> Self {
+		let mut snapshot_mods = Vec::new();
+		let mut artifacts = Vec::new();
+		for rmod in mods {
+			let Some(semver) = &rmod.installed_version else {
+				continue;
+			};
+
+			snapshot_mods.push(SnapshotMod {
+				id: rmod.id.clone(),
+				version: semver.clone(),
+				active: rmod.active,
+				install_reason: rmod.install_reason,
+			});
+			if let Some(version) = rmod.versions.get(semver) {
+				artifacts.extend(version.artifacts.iter().map(|artifact| artifact.sha256.to_lowercase()));
+			}
+		}
+		snapshot_mods.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+
+		Self {
+			id,
+			description: description.into(),
+			mods: snapshot_mods,
+			artifacts,
+		}
+	}
+}
+
+impl fmt::Display for Snapshot {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} ({})", self.description, self.id)
+	}
+}
+
+/// State of a single installed mod recorded in a [`Snapshot`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SnapshotMod {
+	pub id: String,
+	pub version: Version,
+	pub active: bool,
+	#[serde(rename = "installReason")]
+	pub install_reason: Option<InstallReason>,
+}
+
+impl fmt::Display for SnapshotMod {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} v{}", self.id, self.version)?;
+		if !self.active {
+			write!(f, " (disabled)")?;
+		}
+		Ok(())
+	}
+}
+
+/// Reason a mod was installed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum InstallReason {
+	/// Installed explicitly by the user (or discovered already installed)
+	Explicit,
+	/// Installed only to satisfy the dependencies of other mods
+	Dependency,
+}
+
+/// Meaning of a flag on a mod in the manifest
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ModFlag {
+	/// The mod is no longer maintained or has been superseded
+	Deprecated,
+	/// The mod has a known security vulnerability
+	Vulnerable { severity: Option<VulnerabilitySeverity> },
+	/// The mod is a plugin rather than a regular mod
+	Plugin,
+	/// The mod won't receive any further updates
+	Final,
+	/// Any flag that isn't known
+	Other { flag: String },
+}
+
+impl ModFlag {
+	/// Interprets a flag string from the manifest
+	#[must_use]
+	pub fn parse(flag: &str) -> Self {
+		match flag {
+			"deprecated" => Self::Deprecated,
+			"vulnerable" | "vulnerability" => Self::Vulnerable { severity: None },
+			"plugin" => Self::Plugin,
+			"final" => Self::Final,
+			_ => match flag.strip_prefix("vulnerability:") {
+				Some(severity) => Self::Vulnerable {
+					severity: VulnerabilitySeverity::parse(severity),
+				},
+				None => Self::Other { flag: flag.to_owned() },
+			},
+		}
+	}
+
+	/// Checks whether this flag marks a security vulnerability
+	#[must_use]
+	pub const fn is_vulnerability(&self) -> bool {
+		matches!(self, Self::Vulnerable { .. })
+	}
+
+	/// Checks whether this flag is a warning that installing the mod needs to be confirmed for
+	#[must_use]
+	pub const fn needs_confirmation(&self) -> bool {
+		matches!(self, Self::Deprecated | Self::Vulnerable { .. })
+	}
+}
+
+impl fmt::Display for ModFlag {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Deprecated => write!(f, "deprecated"),
+			Self::Vulnerable { severity: None } => write!(f, "vulnerable"),
+			Self::Vulnerable {
+				severity: Some(severity),
+			} => write!(f, "vulnerable ({severity} severity)"),
+			Self::Plugin => write!(f, "plugin"),
+			Self::Final => write!(f, "final"),
+			Self::Other { flag } => write!(f, "{flag}"),
+		}
+	}
+}
+
+/// Severity of a security vulnerability in a mod
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum VulnerabilitySeverity {
+	Low,
+	Medium,
+	High,
+	Critical,
+}
+
+impl VulnerabilitySeverity {
+	/// Interprets a severity string from the manifest
+	#[must_use]
+	pub fn parse(severity: &str) -> Option<Self> {
+		match severity {
+			"low" => Some(Self::Low),
+			"medium" => Some(Self::Medium),
+			"high" => Some(Self::High),
+			"critical" => Some(Self::Critical),
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Display for VulnerabilitySeverity {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Low => write!(f, "low"),
+			Self::Medium => write!(f, "medium"),
+			Self::High => write!(f, "high"),
+			Self::Critical => write!(f, "critical"),
+		}
+	}
+}
+
+/// Platform that a Resonite installation runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Platform {
+	Android,
+	Headless,
+	/// Linux, without knowing whether Resonite runs natively or through Wine/Proton
+	Linux,
+	LinuxNative,
+	LinuxWine,
+	Windows,
+	Other,
+}
+
+impl Platform {
+	/// Gets the platform that the current OS most likely runs Resonite on
+	#[must_use]
+	pub fn current() -> Self {
+		match env::consts::OS {
+			"windows" => Self::Windows,
+			"linux" => Self::Linux,
+			"android" => Self::Android,
+			_ => Self::Other,
+		}
+	}
+
+	/// Gets the name of the platform as used in the manifest
+	#[must_use]
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Android => "android",
+			Self::Headless => "headless",
+			Self::Linux => "linux",
+			Self::LinuxNative => "linux-native",
+			Self::LinuxWine => "linux-wine",
+			Self::Windows => "windows",
+			Self::Other => "other",
+		}
+	}
+
+	/// Checks whether a platform name listed in the manifest covers this platform.
+	/// A generic "linux" listing covers both Linux variants, and any Linux variant listing covers generic Linux.
+	#[must_use]
+	pub fn is_covered_by(self, listed: &str) -> bool {
+		match self {
+			Self::Linux => listed == "linux" || listed.starts_with("linux-"),
+			Self::LinuxNative | Self::LinuxWine => listed == self.as_str() || listed == "linux",
+			_ => listed == self.as_str(),
+		}
+	}
+}
+
+impl fmt::Display for Platform {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+/// Release channel that determines which versions of a mod are offered as updates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ReleaseChannel {
+	/// Only stable releases
+	#[default]
+	Stable,
+	/// Stable releases and prereleases
+	Prerelease,
+}
+
+impl ReleaseChannel {
+	/// Checks whether a version belongs to this channel.
+	/// The version used for unrecognized mods never does.
+	#[must_use]
+	pub fn allows(self, version: &Version) -> bool {
+		*version != *UNRECOGNIZED_SEMVER && (self == Self::Prerelease || version.pre.is_empty())
+	}
+}
+
+impl fmt::Display for ReleaseChannel {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Stable => write!(f, "stable"),
+			Self::Prerelease => write!(f, "prerelease"),
+		}
+	}
+}
+
+/// Release channels to use for mods: a default channel for all mods, with per-mod overrides
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChannelPolicy {
+	pub default: ReleaseChannel,
+	pub mods: HashMap<String, ReleaseChannel>,
+}
+
+impl ChannelPolicy {
+	/// Creates a new policy that uses the given channel for all mods
+	#[must_use]
+	pub fn new(default: ReleaseChannel) -> Self {
+		Self {
+			default,
+			mods: HashMap::new(),
+		}
+	}
+
+	/// Overrides the channel to use for a specific mod
+	#[must_use]
+	pub fn with_mod(mut self, id: impl Into<String>, channel: ReleaseChannel) -> Self {
+		self.mods.insert(id.into(), channel);
+		self
+	}
+
+	/// Gets the channel to use for a mod
+	#[must_use]
+	pub fn channel(&self, id: &str) -> ReleaseChannel {
+		self.mods.get(id).copied().unwrap_or(self.default)
+	}
+
+	/// Checks whether a version of a mod belongs to the channel to use for it
+	#[must_use]
+	pub fn allows(&self, id: &str, version: &Version) -> bool {
+		self.channel(id).allows(version)
+	}
+}
+
+/// Details for an author of a mod
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ModAuthor {
+	pub name: String,
+	pub url: Option<Url>,
+	pub icon: Option<Url>,
+	pub support: Option<Url>,
+}
+
+impl ModAuthor {
+	/// Creates a new unknown author
+	#[must_use]
+	pub fn unknown() -> Self {
+		Self {
+			name: "Unknown".to_owned(),
+			url: None,
+			icon: None,
+			support: None,
+		}
+	}
+}
+
+impl fmt::Display for ModAuthor {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.name)
+	}
+}
+
+/// Details for a released version of a mod
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ModVersion {
+	pub semver: Version,
+	pub artifacts: Vec<ModArtifact>,
+	#[serde(serialize_with = "serialize_sorted")]
+	pub dependencies: ModDependencyMap,
+	#[serde(serialize_with = "serialize_sorted")]
+	pub conflicts: ModDependencyMap,
+	#[serde(rename = "releaseUrl")]
+	pub release_url: Option<Url>,
+	pub changelog: Option<String>,
+}
+
+impl ModVersion {
+	/// Checks whether this version is unrecognized (semver equals [`UNRECOGNIZED_SEMVER`])
+	#[must_use]
+	pub fn is_unrecognized(&self) -> bool {
+		self.semver.eq(&UNRECOGNIZED_SEMVER)
+	}
+
+	/// Creates a new unrecognized version from details about a single encountered artifact file
+	pub fn new_unrecognized(
+		artifact_filename: impl AsRef<str>,
+		artifact_install_location: impl AsRef<str>,
+		artifact_sha256: impl AsRef<str>,
+	) -> Self {
+		let artifacts = vec![ModArtifact::new_unrecognized(
+			artifact_filename,
+			artifact_install_location,
+			artifact_sha256,
+		)];
+
+		Self {
+			semver: UNRECOGNIZED_SEMVER.clone(),
+			artifacts,
+			dependencies: ModDependencyMap::new(),
+			conflicts: ModDependencyMap::new(),
+			release_url: None,
+			changelog: None,
+		}
+	}
+
+	/// Creates a new unrecognized version with a list of artifacts
+	pub fn new_unrecognized_with_artifacts(artifacts: Vec<ModArtifact>) -> Self {
+		Self {
+			semver: UNRECOGNIZED_SEMVER.clone(),
+			artifacts,
+			dependencies: ModDependencyMap::new(),
+			conflicts: ModDependencyMap::new(),
+			release_url: None,
+			changelog: None,
+		}
+	}
+}
+
+impl fmt::Display for ModVersion {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.semver)
+	}
+}
+
+/// Details for a release artifact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ModArtifact {
+	pub url: Url,
+	pub sha256: String,
+	pub filename: Option<String>,
+	#[serde(rename = "installLocation")]
+	pub install_location: Option<String>,
+	#[serde(rename = "overrideFilename")]
+	pub override_filename: Option<String>,
+}
+
+impl ModArtifact {
+	/// Gets the filename from the end of the artifact's URL
+	#[must_use]
+	pub fn infer_filename(&self) -> Option<OsString> {
+		let path = Path::new(self.url.path());
+		path.file_name().map(ToOwned::to_owned)
+	}
+
+	/// Gets the default install location for the artifact, influenced by the category of the mod if available
+	pub fn infer_install_location(&self, category: Option<impl AsRef<str>>) -> PathBuf {
+		match category {
+			Some(category) => match category.as_ref() {
+				"Plugins" => PathBuf::from("Libraries"),
+				_ => PathBuf::from("rml_mods"),
+			},
+			None => PathBuf::from("rml_mods"),
+		}
+	}
+
+	/// Gets the filename or inferred filename. Panics if neither can be obtained.
+	#[must_use]
+	pub fn usable_filename(&self) -> OsString {
+		self.filename
+			.as_ref()
+			.map(|filename| OsString::from(&filename))
+			.or_else(|| self.infer_filename())
+			.expect("unable to get filename of artifact")
+	}
+
+	/// Checks whether this artifact is unrecognized (URL begins with [`UNRECOGNIZED_ARTIFACT_BASE_URL`])
+	#[must_use]
+	pub fn is_unrecognized(&self) -> bool {
+		self.url.as_str().starts_with(UNRECOGNIZED_ARTIFACT_BASE_URL.as_str())
+	}
+
+	/// Gets the full final destination path for the artifact within a base path.
+	/// Fails if the final destination is outside of the base path or if there are any issues building the path.
+	pub fn dest_within(&self, base_path: impl AsRef<Path>) -> crate::Result<PathBuf> {
+		let base_path = base_path.as_ref();
+
+		// Add the artifact's install location to the path
+		let mut dest = base_path.join(match &self.install_location {
+			Some(install_location) => {
+				let path = Path::new(install_location);
+				path.strip_prefix("/").unwrap_or(path)
+			}
+			None => Path::new("rml_mods"),
+		});
+
+		// Add the artifact's filename to the path
+		let filename = match &self.filename {
+			Some(filename) => OsString::from(filename),
+			None => self
+				.infer_filename()
+				.ok_or_else(|| Error::Path(format!("unable to infer filename from url: {}", self.url)))?
+				.clone(),
+		};
+		dest.push(&filename);
+
+		// Ensure the final path is inside the base path
+		let final_dest = dest.clean();
+		if !final_dest.starts_with(base_path) {
+			return Err(Error::Path(
+				"artifact's final destination is not a subdirectory of the base destination".to_owned(),
+			));
+		}
+
+		Ok(final_dest)
+	}
+
+	/// Creates a new unrecognized artifact from details about an encountered artifact file
+	pub fn new_unrecognized(
+		filename: impl AsRef<str>,
+		install_location: impl AsRef<str>,
+		sha256: impl AsRef<str>,
+	) -> Self {
+		let filename = filename.as_ref();
+		let install_location = install_location.as_ref();
+		let sha256 = sha256.as_ref();
+
+		let mut url = UNRECOGNIZED_ARTIFACT_BASE_URL.clone();
+		url.path_segments_mut()
+			.expect("unable to get mutable path segments of unrecognized artifact base url")
+			.push(install_location)
+			.push(filename);
+
+		let install_location = if install_location.starts_with('/') {
+			install_location.to_owned()
+		} else {
+			let mut install_location = install_location.to_owned();
+			install_location.insert(0, '/');
+			install_location
+		};
+
+		let (filename, override_filename) = if let Some(stripped) = filename.strip_suffix(".disabled") {
+			(stripped.to_owned(), Some(filename.to_owned()))
+		} else {
+			(filename.to_owned(), None)
+		};
+
+		ModArtifact {
+			url,
+			sha256: sha256.to_owned(),
+			filename: Some(filename),
+			install_location: Some(install_location),
+			override_filename,
+		}
+	}
+
+	fn from_manifest_and_category(value: ManifestEntryArtifact, category: &str) -> Self {
+		Self {
+			url: value.url,
+			sha256: value.sha256,
+			filename: value.filename,
+			install_location: value.install_location.or_else(|| match category {
+				"Plugins" => Some("/Libraries".to_owned()),
+				_ => None,
+			}),
+			override_filename: None,
+		}
+	}
+
+	/// Gets the temporary destination path for an artifact from its final destination path.
+	/// Fails if there is no filename in the input path.
+	pub fn tmp_dest(dest: impl AsRef<Path>) -> crate::Result<PathBuf> {
+		let dest = dest.as_ref();
+		let mut filename = dest
+			.file_name()
+			.ok_or_else(|| {
+				Error::Path(format!(
+					"unable to build temporary destination for final destination ({})",
+					dest.display()
+				))
+			})?
+			.to_owned();
+		filename.push(".new");
+		Ok(dest.with_file_name(filename))
+	}
+
+	/// Gets the path of the file that records how to resume a partial download of an artifact from its temporary
+	/// destination path. Fails if there is no filename in the input path.
+	pub fn resume_dest(tmp_dest: impl AsRef<Path>) -> crate::Result<PathBuf> {
+		let tmp_dest = tmp_dest.as_ref();
+		let mut filename = tmp_dest
+			.file_name()
+			.ok_or_else(|| {
+				Error::Path(format!(
+					"unable to build resume destination for temporary destination ({})",
+					tmp_dest.display()
+				))
+			})?
+			.to_owned();
+		filename.push(".resume");
+		Ok(tmp_dest.with_file_name(filename))
+	}
+
+	/// Gets the old (existing, being replaced) destination path for an artifact from its final destination path.
+	/// Fails if there is no filename in the input path.
+	pub fn old_dest(dest: impl AsRef<Path>) -> crate::Result<PathBuf> {
+		let dest = dest.as_ref();
+		let mut filename = dest
+			.file_name()
+			.ok_or_else(|| {
+				Error::Path(format!(
+					"unable to build old destination for final destination ({})",
+					dest.display()
+				))
+			})?
+			.to_owned();
+		filename.push(".old");
+		Ok(dest.with_file_name(filename))
+	}
+
+	/// Gets the disabled destination path for an artifact from its final destination path.
+	/// Fails if there is no filename in the input path.
+	pub fn disabled_dest(dest: impl AsRef<Path>) -> crate::Result<PathBuf> {
+		let dest = dest.as_ref();
+		let mut filename = dest
+			.file_name()
+			.ok_or_else(|| {
+				Error::Path(format!(
+					"unable to build disabled destination for final destination ({})",
+					dest.display()
+				))
+			})?
+			.to_owned();
+		filename.push(".disabled");
+		Ok(dest.with_file_name(filename))
+	}
+}
+
+impl fmt::Display for ModArtifact {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = self
+			.filename
+			.clone()
+			.or_else(|| {
+				Path::new(self.url.path())
+					.file_name()
+					.and_then(|name| name.to_str())
+					.map(ToOwned::to_owned)
+			})
+			.or_else(|| Some(self.url.to_string()))
+			.ok_or(fmt::Error)?;
+
+		write!(f, "{name}")
+	}
+}
+
+impl From<ManifestEntryArtifact> for ModArtifact {
+	fn from(value: ManifestEntryArtifact) -> Self {
+		Self {
+			url: value.url,
+			sha256: value.sha256,
+			filename: value.filename,
+			install_location: value.install_location,
+			override_filename: None,
+		}
+	}
+}
+
+/// Map of mod IDs to semver ranges
+pub type ModDependencyMap = HashMap<String, VersionReq>;
+
+/// Serializes a map with its entries ordered by key. The database compares the encoded bytes of stored items when
+/// updating or removing them, so the encoding must not depend on the iteration order of a [`HashMap`].
+fn serialize_sorted<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+	K: Ord + Serialize,
+	V: Serialize,
+	S: Serializer,
+{
+	serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+#[cfg(test)]
+mod tests {
+	use semver::{Version, VersionReq};
+
+	use super::{
+		ChannelPolicy, InstallReason, ModDependencyMap, ModVersion, Platform, ReleaseChannel, ResoluteMod,
+		UNRECOGNIZED_SEMVER,
+	};
+
+	fn version(semver: &Version) -> ModVersion {
+		ModVersion {
+			semver: semver.clone(),
+			artifacts: Vec::new(),
+			dependencies: ModDependencyMap::new(),
+			conflicts: ModDependencyMap::new(),
+			release_url: None,
+			changelog: None,
+		}
+	}
+
+	/// Builds a mod with the given versions available and the first one installed
+	fn rmod(id: &str, versions: &[&Version]) -> ResoluteMod {
+		ResoluteMod {
+			id: id.to_owned(),
+			name: id.to_owned(),
+			description: String::new(),
+			category: "Libraries".to_owned(),
+			authors: Vec::new(),
+			source_location: None,
+			website: None,
+			tags: None,
+			flags: None,
+			platforms: None,
+			versions: versions
+				.iter()
+				.map(|semver| ((*semver).clone(), version(semver)))
+				.collect(),
+			installed_version: versions.first().map(|semver| (*semver).clone()),
+			install_reason: Some(InstallReason::Explicit),
+			pin: None,
+			active: true,
+		}
+	}
+
+	fn semver(version: &str) -> Version {
+		Version::parse(version).expect("parse version")
+	}
+
+	#[test]
+	fn stable_channel_ignores_prereleases() {
+		let stable = semver("1.0.0");
+		let beta = semver("1.1.0-beta.1");
+		let rmod = rmod("mod", &[&stable, &beta, &UNRECOGNIZED_SEMVER]);
+		let channels = ChannelPolicy::default();
+
+		assert_eq!(
+			rmod.latest_version(&channels).map(|version| &version.semver),
+			Some(&stable)
+		);
+		assert_eq!(rmod.has_update(&channels), Some(false));
+	}
+
+	#[test]
+	fn prerelease_channel_applies_globally_or_per_mod() {
+		let stable = semver("1.0.0");
+		let beta = semver("1.1.0-beta.1");
+		let rmod = rmod("mod", &[&stable, &beta, &UNRECOGNIZED_SEMVER]);
+
+		let global = ChannelPolicy::new(ReleaseChannel::Prerelease);
+		assert_eq!(rmod.latest_version(&global).map(|version| &version.semver), Some(&beta));
+		assert_eq!(rmod.has_update(&global), Some(true));
+
+		let per_mod = ChannelPolicy::default().with_mod("mod", ReleaseChannel::Prerelease);
+		assert_eq!(
+			rmod.latest_version(&per_mod).map(|version| &version.semver),
+			Some(&beta)
+		);
+		let other_mod = ChannelPolicy::default().with_mod("other", ReleaseChannel::Prerelease);
+		assert_eq!(
+			rmod.latest_version(&other_mod).map(|version| &version.semver),
+			Some(&stable)
+		);
+	}
+
+	#[test]
+	fn no_channel_allows_the_unrecognized_version() {
+		assert!(!ReleaseChannel::Stable.allows(&UNRECOGNIZED_SEMVER));
+		assert!(!ReleaseChannel::Prerelease.allows(&UNRECOGNIZED_SEMVER));
+
+		let rmod = rmod("mod", &[&UNRECOGNIZED_SEMVER]);
+		assert!(rmod
+			.latest_version(&ChannelPolicy::new(ReleaseChannel::Prerelease))
+			.is_none());
+	}
+
+	#[test]
+	fn linux_listings_cover_each_other() {
+		assert!(Platform::Linux.is_covered_by("linux"));
+		assert!(Platform::Linux.is_covered_by("linux-native"));
+		assert!(Platform::Linux.is_covered_by("linux-wine"));
+		assert!(Platform::LinuxWine.is_covered_by("linux"));
+		assert!(Platform::LinuxWine.is_covered_by("linux-wine"));
+		assert!(!Platform::LinuxWine.is_covered_by("linux-native"));
+		assert!(!Platform::Windows.is_covered_by("linux"));
+		assert!(!Platform::Headless.is_covered_by("windows"));
+	}
+
+	#[test]
+	fn mods_without_listed_platforms_support_all_of_them() {
+		let mut rmod = rmod("mod", &[]);
+		assert!(rmod.supports_platform(Platform::Android));
+
+		rmod.platforms = Some(Vec::new());
+		assert!(rmod.supports_platform(Platform::Android));
+
+		rmod.platforms = Some(vec!["windows".to_owned(), "linux-native".to_owned()]);
+		assert!(rmod.supports_platform(Platform::Windows));
+		assert!(rmod.supports_platform(Platform::Linux));
+		assert!(!rmod.supports_platform(Platform::LinuxWine));
+		assert!(!rmod.supports_platform(Platform::Android));
+	}
+
+	#[test]
+	fn serializes_maps_in_a_stable_order() {
+		// Build the same mod twice with its maps filled in opposite orders, since separately created maps iterate
+		// differently and stored mods need to encode to the same bytes every time
+		let semvers: Vec<Version> = (0..16).map(|minor| Version::new(1, minor, 0)).collect();
+		let build = |reverse: bool| {
+			let mut ordered: Vec<&Version> = semvers.iter().collect();
+			if reverse {
+				ordered.reverse();
+			}
+
+			let mut rmod = rmod("mod", &ordered);
+			rmod.installed_version = None;
+			for version in rmod.versions.values_mut() {
+				for semver in &ordered {
+					let req = VersionReq::parse(&format!("^{semver}")).expect("parse version requirement");
+					version.dependencies.insert(format!("dep{}", semver.minor), req.clone());
+					version.conflicts.insert(format!("conflict{}", semver.minor), req);
+				}
+			}
+			rmod
+		};
+
+		let forward = serde_json::to_string(&build(false)).expect("serialize mod");
+		let backward = serde_json::to_string(&build(true)).expect("serialize mod");
+		assert_eq!(forward, backward);
+	}
+}
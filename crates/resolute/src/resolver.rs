@@ -0,0 +1,2038 @@
+use core::result;
+use std::{
+	cell::RefCell,
+	collections::{BTreeMap, HashMap, HashSet},
+	fmt,
+};
+
+use log::{debug, trace};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	models::{ChannelPolicy, ModProfile, ModVersion, ReleaseChannel, ResoluteMod, ResoluteModMap},
+	Error, Result,
+};
+
+/// Resolves the dependencies of mods within a mod map.
+/// Installed versions of mods (from their `installed_version` fields) are taken into account, so that any already
+/// installed dependencies are kept when they're compatible and any installed dependents aren't broken.
+#[derive(Debug)]
+pub struct Resolver<'a> {
+	mods: &'a ResoluteModMap,
+	channels: &'a ChannelPolicy,
+}
+
+impl<'a> Resolver<'a> {
+	/// Creates a new resolver for a mod map.
+	/// The release channels determine which versions are considered for dependencies and when updating mods. On top of
+	/// that, dependencies only resolve to prereleases when a requirement explicitly asks for one, as is usual for semver.
+	/// Versions of mods that are explicitly requested are used regardless of their release channel.
+	#[must_use]
+	pub const fn new(mods: &'a ResoluteModMap, channels: &'a ChannelPolicy) -> Self {
+		Self { mods, channels }
+	}
+
+	/// Builds a plan for installing a specific version of a mod along with every dependency it needs.
+	/// The given mod takes precedence over any entry for the same ID in the resolver's mod map.
+	pub fn resolve(&self, rmod: &'a ResoluteMod, version: &Version) -> Result<InstallPlan> {
+		if !rmod.versions.contains_key(version) {
+			return Err(Error::UnknownVersion(rmod.id.clone(), version.clone()));
+		}
+		if let Some(pin) = rmod.pin.as_ref().filter(|pin| !pin.matches(version)) {
+			return Err(Error::Pinned {
+				id: rmod.id.clone(),
+				version: version.clone(),
+				pin: pin.clone(),
+			});
+		}
+
+		let context = Context::new(self.mods, self.channels, Some(rmod), HashSet::new());
+		let mut selected = BTreeMap::new();
+		selected.insert(rmod.id.clone(), version.clone());
+
+		// Make sure the requested version itself doesn't break any installed mods before going any further
+		let unmatched: Vec<Requirement> = context
+			.requirements(&selected, &rmod.id)
+			.into_iter()
+			.filter(|req| !req.range.matches(version))
+			.collect();
+		if !unmatched.is_empty() {
+			return Err(Error::UnresolvableDependencies {
+				id: rmod.id.clone(),
+				version: version.clone(),
+				cause: Box::new(Incompatibility::Requested {
+					id: rmod.id.clone(),
+					version: version.clone(),
+					requirements: unmatched,
+				}),
+			});
+		}
+
+		debug!("Resolving dependencies for mod {} v{}", rmod, version);
+		context.solve(&mut selected).map_err(|cause| {
+			debug!(
+				"Unable to resolve dependencies for mod {} v{}: {}",
+				rmod, version, cause
+			);
+			Error::UnresolvableDependencies {
+				id: rmod.id.clone(),
+				version: version.clone(),
+				cause: Box::new(cause),
+			}
+		})?;
+
+		let plan = context.build_plan(Some(&rmod.id), &selected);
+		debug!("Resolved dependencies for mod {} v{}: {}", rmod, version, plan);
+		Ok(plan)
+	}
+
+	/// Builds a plan for updating every installed mod to the newest versions that are compatible with each other.
+	/// Dependents are decided before their dependencies so that newer dependents can pull in the newer dependency
+	/// versions they need, and a choice that leaves a later mod without any working version is backtracked. Any mod
+	/// that can't be updated without breaking something is kept at its installed version. This only fails when the
+	/// installed mods can't be kept working at all, such as when an installed dependency is missing.
+	pub fn resolve_update_all(&self) -> Result<InstallPlan> {
+		let order = self.update_order();
+		let mut failed = HashMap::new();
+		let selected = self
+			.solve_update(BTreeMap::new(), &order, &mut failed)
+			.map_err(|cause| {
+				debug!("Unable to find compatible versions for all installed mods: {}", cause);
+				Error::UnresolvableUpdate(Box::new(cause))
+			})?;
+
+		let context = Context::new(self.mods, self.channels, None, HashSet::new());
+		let plan = context.build_plan(None, &selected);
+		debug!("Resolved update of all mods: {}", plan);
+		Ok(plan)
+	}
+
+	/// Finds every conflict that installing a plan would introduce.
+	/// Conflicts declared by the planned versions against the installed mods (and each other) are checked, as well as
+	/// conflicts declared by the installed mods against the planned versions. Installed mods that the plan replaces
+	/// aren't considered, nor are conflicts solely between installed mods.
+	/// If a mod is given, it takes precedence over any entry for the same ID in the resolver's mod map.
+	#[must_use]
+	pub fn conflicts(
+		&self,
+		rmod: Option<&'a ResoluteMod>,
+		plan: &InstallPlan,
+		installed: &ResoluteModMap,
+	) -> ModConflictVec {
+		let context = Context::new(self.mods, self.channels, rmod, HashSet::new());
+
+		// Build the set of mod versions that will be installed once the plan is complete, tracking which are planned
+		let planned: BTreeMap<&str, &ModVersion> = plan
+			.steps
+			.iter()
+			.filter_map(|step| {
+				let version = context.get(&step.id)?.versions.get(&step.version)?;
+				Some((step.id.as_str(), version))
+			})
+			.collect();
+		let remaining = installed
+			.values()
+			.filter(|rmod| !planned.contains_key(rmod.id.as_str()))
+			.filter_map(|rmod| {
+				let version = rmod.versions.get(rmod.installed_version.as_ref()?)?;
+				Some((rmod.id.as_str(), version))
+			});
+		let all: BTreeMap<&str, &ModVersion> = planned.clone().into_iter().chain(remaining).collect();
+
+		let mut conflicts = ModConflictVec::new();
+		for (id, version) in &all {
+			for (conflict_id, range) in &version.conflicts {
+				let Some(conflict_version) = all.get(conflict_id.as_str()) else {
+					continue;
+				};
+
+				// Only report conflicts that involve at least one planned version
+				if !planned.contains_key(id) && !planned.contains_key(conflict_id.as_str()) {
+					continue;
+				}
+
+				if range.matches(&conflict_version.semver) {
+					conflicts.push(ModConflict {
+						id: (*id).to_owned(),
+						version: version.semver.clone(),
+						conflicting_id: conflict_id.clone(),
+						conflicting_version: conflict_version.semver.clone(),
+						range: range.clone(),
+					});
+				}
+			}
+		}
+
+		conflicts
+	}
+
+	/// Finds every mod in the resolver's mod map with an installed version that depends on the given mod
+	#[must_use]
+	pub fn dependents(&self, id: &str) -> Vec<&'a ResoluteMod> {
+		let mut dependents: Vec<&ResoluteMod> = self
+			.mods
+			.values()
+			.filter(|rmod| rmod.id != id)
+			.filter(|rmod| {
+				rmod.installed_version
+					.as_ref()
+					.and_then(|semver| rmod.versions.get(semver))
+					.is_some_and(|version| version.dependencies.contains_key(id))
+			})
+			.collect();
+		dependents.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+		dependents
+	}
+
+	/// Builds a plan for uninstalling a mod.
+	/// If any installed mods depend on the mod, this fails unless cascading is requested, in which case the dependents
+	/// (and anything depending on them) are included in the plan as well. Dependents always precede the mods they
+	/// depend on in the plan.
+	pub fn resolve_uninstall(&self, rmod: &'a ResoluteMod, cascade: bool) -> Result<UninstallPlan> {
+		let Some(semver) = &rmod.installed_version else {
+			return Err(Error::ModNotInstalled(Box::new(rmod.clone())));
+		};
+
+		// Refuse to break any dependents unless they're being removed too
+		let dependents = self.dependents(&rmod.id);
+		if !dependents.is_empty() && !cascade {
+			return Err(Error::RequiredBy {
+				id: rmod.id.clone(),
+				dependents: dependents.into_iter().map(|dependent| dependent.id.clone()).collect(),
+			});
+		}
+
+		let mut steps = Vec::new();
+		let mut visited = HashSet::new();
+		visited.insert(rmod.id.clone());
+		for dependent in dependents {
+			self.visit_dependents(dependent, &mut visited, &mut steps);
+		}
+		steps.push(PlannedUninstall {
+			id: rmod.id.clone(),
+			version: semver.clone(),
+		});
+
+		let plan = UninstallPlan { steps };
+		debug!("Resolved uninstallation of mod {}: {}", rmod, plan);
+		Ok(plan)
+	}
+
+	/// Builds a plan for uninstalling every mod that was only installed as a dependency and isn't needed (directly or
+	/// indirectly) by any explicitly installed mod
+	#[must_use]
+	pub fn resolve_orphans(&self) -> UninstallPlan {
+		// Walk the dependencies of every explicitly installed mod to find everything that's still needed
+		let mut needed = HashSet::new();
+		let mut pending: Vec<&ResoluteMod> = self
+			.mods
+			.values()
+			.filter(|rmod| rmod.installed_version.is_some() && !rmod.is_dependency_only())
+			.collect();
+		while let Some(rmod) = pending.pop() {
+			if !needed.insert(rmod.id.as_str()) {
+				continue;
+			}
+
+			let Some(version) = rmod
+				.installed_version
+				.as_ref()
+				.and_then(|semver| rmod.versions.get(semver))
+			else {
+				continue;
+			};
+			pending.extend(version.dependencies.keys().filter_map(|dep_id| self.mods.get(dep_id)));
+		}
+
+		// Anything left over that was installed as a dependency is an orphan - since nothing needed depends on them,
+		// any dependents they have are orphans too, so they can be ordered the same way as a cascading uninstall
+		let mut orphans: Vec<&ResoluteMod> = self
+			.mods
+			.values()
+			.filter(|rmod| rmod.is_dependency_only() && !needed.contains(rmod.id.as_str()))
+			.collect();
+		orphans.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+
+		let mut steps = Vec::with_capacity(orphans.len());
+		let mut visited = HashSet::with_capacity(orphans.len());
+		for orphan in orphans {
+			self.visit_dependents(orphan, &mut visited, &mut steps);
+		}
+
+		let plan = UninstallPlan { steps };
+		debug!("Resolved orphaned mods: {}", plan);
+		plan
+	}
+
+	/// Builds a plan for switching the installed mods over to a profile.
+	/// Each mod in the profile gets the version the profile specifies for it, otherwise it keeps its installed version
+	/// or gets the latest version allowed for it. Dependencies are resolved as usual, but installed mods outside of the
+	/// profile don't place any requirements since they won't stay active. Installed mods that aren't needed by the
+	/// profile are disabled, unless they were only installed as dependencies of other mods that aren't staying
+	/// installed, in which case they're uninstalled.
+	pub fn resolve_profile(&self, profile: &ModProfile) -> Result<ProfilePlan> {
+		// Determine the version to use for each mod in the profile
+		let mut selected = BTreeMap::new();
+		for (id, version) in &profile.mods {
+			let rmod = self.mods.get(id).ok_or_else(|| Error::UnknownMod(id.clone()))?;
+			let version = match version.as_ref().or(rmod.installed_version.as_ref()) {
+				Some(version) => version.clone(),
+				None => rmod
+					.latest_allowed_version(self.channels)
+					.ok_or_else(|| Error::NoAvailableVersion(id.clone()))?
+					.semver
+					.clone(),
+			};
+
+			if !rmod.versions.contains_key(&version) {
+				return Err(Error::UnknownVersion(id.clone(), version));
+			}
+			if let Some(pin) = rmod.pin.as_ref().filter(|pin| !pin.matches(&version)) {
+				return Err(Error::Pinned {
+					id: id.clone(),
+					version,
+					pin: pin.clone(),
+				});
+			}
+
+			selected.insert(id.clone(), version);
+		}
+
+		// Installed mods outside of the profile are treated as undecided so that they don't hold anything back
+		let pending = self
+			.mods
+			.values()
+			.filter(|rmod| rmod.installed_version.is_some() && !profile.mods.contains_key(&rmod.id))
+			.map(|rmod| rmod.id.as_str())
+			.collect();
+		let context = Context::new(self.mods, self.channels, None, pending);
+
+		// Make sure the profile's versions work with each other before resolving their dependencies
+		context.check_selection(&selected)?;
+
+		debug!("Resolving dependencies for profile {}", profile);
+		context.solve(&mut selected).map_err(|cause| {
+			debug!("Unable to resolve dependencies for profile {}: {}", profile, cause);
+			Error::UnresolvableProfile {
+				name: profile.name.clone(),
+				cause: Box::new(cause),
+			}
+		})?;
+		let install = context.build_plan(None, &selected);
+
+		// Find everything the profile needs once its versions are in place
+		let needed = self.dependency_closure(profile.mods.keys().cloned(), |id| {
+			context.effective_version(&selected, id)
+		});
+
+		// Mods that will stay installed (but disabled) keep their dependencies around too, while anything else that
+		// was only installed as a dependency can go
+		let kept = self.dependency_closure(
+			self.mods
+				.values()
+				.filter(|rmod| rmod.installed_version.is_some() && !rmod.is_dependency_only())
+				.map(|rmod| rmod.id.clone())
+				.chain(needed.iter().cloned()),
+			|id| context.effective_version(&selected, id),
+		);
+
+		let mut removed: Vec<&ResoluteMod> = self
+			.mods
+			.values()
+			.filter(|rmod| rmod.installed_version.is_some() && !kept.contains(&rmod.id))
+			.collect();
+		removed.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+		let mut steps = Vec::with_capacity(removed.len());
+		let mut visited = HashSet::with_capacity(removed.len());
+		for rmod in removed {
+			self.visit_dependents(rmod, &mut visited, &mut steps);
+		}
+
+		// Enable any needed mods that are disabled (newly installed versions are always enabled) and disable any
+		// active mods that aren't needed
+		let mut enable: Vec<String> = needed
+			.iter()
+			.filter(|id| install.steps.iter().all(|step| step.id != **id))
+			.filter(|id| {
+				self.mods
+					.get(*id)
+					.is_some_and(|rmod| rmod.installed_version.is_some() && !rmod.active)
+			})
+			.cloned()
+			.collect();
+		enable.sort_unstable();
+		let mut disable: Vec<String> = self
+			.mods
+			.values()
+			.filter(|rmod| rmod.installed_version.is_some() && rmod.active)
+			.filter(|rmod| kept.contains(&rmod.id) && !needed.contains(&rmod.id))
+			.map(|rmod| rmod.id.clone())
+			.collect();
+		disable.sort_unstable();
+
+		let plan = ProfilePlan {
+			install,
+			enable,
+			disable,
+			remove: UninstallPlan { steps },
+		};
+		debug!("Resolved profile {}: {}", profile, plan);
+		Ok(plan)
+	}
+
+	/// Collects the given mod IDs along with the IDs of all of their dependencies (direct or indirect), using the given
+	/// function to determine the version of each mod
+	fn dependency_closure(
+		&self,
+		ids: impl IntoIterator<Item = String>,
+		version_of: impl Fn(&str) -> Option<&'a ModVersion>,
+	) -> HashSet<String> {
+		let mut closure = HashSet::new();
+		let mut pending: Vec<String> = ids.into_iter().collect();
+		while let Some(id) = pending.pop() {
+			if closure.contains(&id) {
+				continue;
+			}
+
+			if let Some(version) = version_of(&id) {
+				pending.extend(
+					version
+						.dependencies
+						.keys()
+						.filter(|dep_id| self.mods.contains_key(*dep_id))
+						.cloned(),
+				);
+			}
+			closure.insert(id);
+		}
+
+		closure
+	}
+
+	/// Recursively selects the newest working version (no older than the installed one) for each mod in the given
+	/// order, backtracking whenever a choice leaves a later mod without any working version. Selections that are
+	/// already known to fail at a given depth are remembered in `failed` so that they aren't explored again.
+	fn solve_update(
+		&self,
+		selected: BTreeMap<String, Version>,
+		order: &[&'a ResoluteMod],
+		failed: &mut HashMap<(usize, BTreeMap<String, Version>), Incompatibility>,
+	) -> result::Result<BTreeMap<String, Version>, Incompatibility> {
+		let Some((rmod, rest)) = order.split_first() else {
+			return Ok(selected);
+		};
+
+		// Skip anything that was already selected as a dependency of an earlier mod or that can't be updated at all
+		let installed = match &rmod.installed_version {
+			Some(installed) if !selected.contains_key(&rmod.id) && rmod.versions.contains_key(installed) => installed,
+			_ => return self.solve_update(selected, rest, failed),
+		};
+
+		let key = (order.len(), selected);
+		if let Some(cause) = failed.get(&key) {
+			trace!("Already ruled out this selection for {}", rmod);
+			return Err(cause.clone());
+		}
+		let (_, selected) = &key;
+
+		// The mods that haven't been decided yet shouldn't hold back the choice for this one
+		let pending = rest.iter().map(|rmod| rmod.id.as_str()).collect();
+		let context = Context::new(self.mods, self.channels, None, pending);
+
+		// Try each version from the newest down to the installed one, skipping any that the mod's release channel or pin
+		// doesn't allow - the installed version is always kept as a last resort, even if they don't allow it
+		let mut candidates: Vec<&Version> = rmod
+			.versions
+			.values()
+			.filter(|version| !version.is_unrecognized())
+			.map(|version| &version.semver)
+			.filter(|semver| {
+				*semver > installed && rmod.allows_version(semver) && self.channels.allows(&rmod.id, semver)
+			})
+			.chain([installed])
+			.collect();
+		candidates.sort_unstable_by(|a, b| b.cmp(a));
+
+		let mut failures = Vec::with_capacity(candidates.len());
+		for candidate in candidates {
+			trace!("Trying {} v{} for updating all mods", rmod, candidate);
+			let result = context
+				.try_select(selected, &rmod.id, candidate)
+				.and_then(|next| self.solve_update(next, rest, failed));
+			match result {
+				Ok(solution) => return Ok(solution),
+				Err(cause) => {
+					trace!("Unable to use {} v{}: {}", rmod, candidate, cause);
+					failures.push(CandidateFailure {
+						version: candidate.clone(),
+						cause,
+					});
+				}
+			}
+		}
+
+		trace!("No version of {} works with the current selection", rmod);
+		let cause = Incompatibility::AllVersionsFailed {
+			id: rmod.id.clone(),
+			failures,
+		};
+		failed.insert(key, cause.clone());
+		Err(cause)
+	}
+
+	/// Orders the installed mods so that dependents always come before their dependencies
+	fn update_order(&self) -> Vec<&'a ResoluteMod> {
+		let mut installed: Vec<&ResoluteMod> = self
+			.mods
+			.values()
+			.filter(|rmod| rmod.installed_version.is_some())
+			.collect();
+		installed.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+
+		// Walk dependencies depth-first so that dependencies precede dependents, then flip the order
+		let mut order = Vec::with_capacity(installed.len());
+		let mut visited = HashSet::with_capacity(installed.len());
+		let mut pending: Vec<(&ResoluteMod, bool)> = installed.into_iter().rev().map(|rmod| (rmod, false)).collect();
+		while let Some((rmod, expanded)) = pending.pop() {
+			if expanded {
+				order.push(rmod);
+				continue;
+			}
+			if !visited.insert(rmod.id.as_str()) {
+				continue;
+			}
+
+			pending.push((rmod, true));
+			let Some(version) = rmod
+				.installed_version
+				.as_ref()
+				.and_then(|semver| rmod.versions.get(semver))
+			else {
+				continue;
+			};
+			let mut deps: Vec<&ResoluteMod> = version
+				.dependencies
+				.keys()
+				.filter_map(|dep_id| self.mods.get(dep_id))
+				.filter(|dep| dep.installed_version.is_some() && !visited.contains(dep.id.as_str()))
+				.collect();
+			deps.sort_unstable_by(|a, b| b.id.cmp(&a.id));
+			pending.extend(deps.into_iter().map(|dep| (dep, false)));
+		}
+
+		order.reverse();
+		order
+	}
+
+	/// Visits an installed mod's dependents depth-first, then adds a step for the mod itself
+	fn visit_dependents(
+		&self,
+		rmod: &'a ResoluteMod,
+		visited: &mut HashSet<String>,
+		steps: &mut Vec<PlannedUninstall>,
+	) {
+		if !visited.insert(rmod.id.clone()) {
+			return;
+		}
+
+		for dependent in self.dependents(&rmod.id) {
+			self.visit_dependents(dependent, visited, steps);
+		}
+
+		if let Some(semver) = &rmod.installed_version {
+			steps.push(PlannedUninstall {
+				id: rmod.id.clone(),
+				version: semver.clone(),
+			});
+		}
+	}
+}
+
+/// State shared throughout a single resolution
+struct Context<'a> {
+	mods: &'a ResoluteModMap,
+	/// Release channels that versions of dependencies must belong to
+	channels: &'a ChannelPolicy,
+	root: Option<&'a ResoluteMod>,
+	/// IDs of installed mods whose versions are yet to be decided, so they don't place any requirements
+	pending: HashSet<&'a str>,
+	/// Selections that are already known to have no solution, along with the reason why
+	failed: RefCell<HashMap<BTreeMap<String, Version>, Incompatibility>>,
+}
+
+impl<'a> Context<'a> {
+	fn new(
+		mods: &'a ResoluteModMap,
+		channels: &'a ChannelPolicy,
+		root: Option<&'a ResoluteMod>,
+		pending: HashSet<&'a str>,
+	) -> Self {
+		Self {
+			mods,
+			channels,
+			root,
+			pending,
+			failed: RefCell::new(HashMap::new()),
+		}
+	}
+
+	/// Gets a mod by its ID, preferring the root mod being resolved
+	fn get(&self, id: &str) -> Option<&'a ResoluteMod> {
+		match self.root {
+			Some(root) if root.id == id => Some(root),
+			_ => self.mods.get(id),
+		}
+	}
+
+	/// Gets the version of a mod that will be in effect once the selected versions are installed, if any
+	fn effective_version(&self, selected: &BTreeMap<String, Version>, id: &str) -> Option<&'a ModVersion> {
+		let rmod = self.get(id)?;
+		let semver = selected.get(id).or(rmod.installed_version.as_ref())?;
+		rmod.versions.get(semver)
+	}
+
+	/// Checks whether a mod's installed version will be kept as-is alongside the selected versions
+	fn is_settled(&self, selected: &BTreeMap<String, Version>, rmod: &ResoluteMod) -> bool {
+		rmod.installed_version.is_some() && !selected.contains_key(&rmod.id) && !self.pending.contains(rmod.id.as_str())
+	}
+
+	/// Collects every requirement placed on a mod by the selected versions and any installed mods that aren't being
+	/// replaced by a selected version
+	fn requirements(&self, selected: &BTreeMap<String, Version>, id: &str) -> Vec<Requirement> {
+		let selected_mods = selected.keys().map(|dependent| (dependent.as_str(), false));
+		let installed_mods = self
+			.mods
+			.values()
+			.filter(|rmod| self.is_settled(selected, rmod))
+			.map(|rmod| (rmod.id.as_str(), true));
+
+		let mut requirements: Vec<Requirement> = selected_mods
+			.chain(installed_mods)
+			.filter_map(|(dependent, installed)| {
+				let version = self.effective_version(selected, dependent)?;
+				let range = version.dependencies.get(id)?;
+				Some(Requirement {
+					dependent: dependent.to_owned(),
+					dependent_version: version.semver.clone(),
+					dependent_installed: installed,
+					id: id.to_owned(),
+					range: range.clone(),
+				})
+			})
+			.collect();
+		requirements.sort_unstable_by(|a, b| a.dependent.cmp(&b.dependent));
+		requirements
+	}
+
+	/// Checks that each selected version satisfies the requirements placed on it by the others and doesn't conflict
+	/// with anything
+	fn check_selection(&self, selected: &BTreeMap<String, Version>) -> Result<()> {
+		for (id, version) in selected {
+			let unmatched: Vec<Requirement> = self
+				.requirements(selected, id)
+				.into_iter()
+				.filter(|req| !req.range.matches(version))
+				.collect();
+			let cause = if unmatched.is_empty() {
+				self.get(id)
+					.and_then(|rmod| rmod.versions.get(version))
+					.and_then(|candidate| self.find_conflict(selected, id, candidate))
+					.map(|conflict| Incompatibility::Conflict(Box::new(conflict)))
+			} else {
+				Some(Incompatibility::Requested {
+					id: id.clone(),
+					version: version.clone(),
+					requirements: unmatched,
+				})
+			};
+
+			if let Some(cause) = cause {
+				return Err(Error::UnresolvableDependencies {
+					id: id.clone(),
+					version: version.clone(),
+					cause: Box::new(cause),
+				});
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Checks whether a version of a mod satisfies every requirement placed on it
+	fn satisfies_all(&self, selected: &BTreeMap<String, Version>, id: &str, version: &Version) -> bool {
+		self.requirements(selected, id)
+			.iter()
+			.all(|req| req.range.matches(version))
+	}
+
+	/// Finds a conflict between a candidate version of a mod and the versions that would be in effect alongside it
+	fn find_conflict(
+		&self,
+		selected: &BTreeMap<String, Version>,
+		id: &str,
+		candidate: &ModVersion,
+	) -> Option<ModConflict> {
+		let others = selected.keys().map(String::as_str).chain(
+			self.mods
+				.values()
+				.filter(|rmod| self.is_settled(selected, rmod))
+				.map(|rmod| rmod.id.as_str()),
+		);
+
+		for other_id in others.filter(|other_id| *other_id != id) {
+			let Some(other) = self.effective_version(selected, other_id) else {
+				continue;
+			};
+
+			// Check the candidate's conflicts against the other version
+			if let Some(range) = candidate.conflicts.get(other_id) {
+				if range.matches(&other.semver) {
+					return Some(ModConflict {
+						id: id.to_owned(),
+						version: candidate.semver.clone(),
+						conflicting_id: other_id.to_owned(),
+						conflicting_version: other.semver.clone(),
+						range: range.clone(),
+					});
+				}
+			}
+
+			// Check the other version's conflicts against the candidate
+			if let Some(range) = other.conflicts.get(id) {
+				if range.matches(&candidate.semver) {
+					return Some(ModConflict {
+						id: other_id.to_owned(),
+						version: other.semver.clone(),
+						conflicting_id: id.to_owned(),
+						conflicting_version: candidate.semver.clone(),
+						range: range.clone(),
+					});
+				}
+			}
+		}
+
+		None
+	}
+
+	/// Finds a dependency of a candidate version of a mod that contradicts a version that's already selected
+	fn find_mismatch(
+		&self,
+		selected: &BTreeMap<String, Version>,
+		id: &str,
+		candidate: &ModVersion,
+	) -> Option<Incompatibility> {
+		candidate.dependencies.iter().find_map(|(dep_id, range)| {
+			let dep_version = selected.get(dep_id)?;
+			(!range.matches(dep_version)).then(|| Incompatibility::SelectedVersion {
+				id: dep_id.clone(),
+				version: dep_version.clone(),
+				requirement: Box::new(Requirement {
+					dependent: id.to_owned(),
+					dependent_version: candidate.semver.clone(),
+					dependent_installed: false,
+					id: dep_id.clone(),
+					range: range.clone(),
+				}),
+				selected_because: self.requirements(selected, dep_id),
+			})
+		})
+	}
+
+	/// Finds the first dependency of the selected versions that doesn't yet have a compatible version selected or
+	/// installed
+	fn next_unsatisfied(&self, selected: &BTreeMap<String, Version>) -> Option<String> {
+		selected
+			.keys()
+			.filter_map(|id| self.effective_version(selected, id))
+			.flat_map(|version| version.dependencies.keys())
+			.filter(|dep_id| !selected.contains_key(*dep_id))
+			.find(|dep_id| {
+				let installed = self.get(dep_id).and_then(|rmod| rmod.installed_version.as_ref());
+				match installed {
+					Some(installed) => !self.satisfies_all(selected, dep_id, installed),
+					None => true,
+				}
+			})
+			.cloned()
+	}
+
+	/// Recursively selects versions for unsatisfied dependencies, backtracking whenever a choice leads to a dead end.
+	/// The selection is completed in place if a complete one could be found, otherwise it's left as it was and the
+	/// reason why one couldn't be found is returned.
+	fn solve(&self, selected: &mut BTreeMap<String, Version>) -> result::Result<(), Incompatibility> {
+		if let Some(cause) = self.failed.borrow().get(selected) {
+			return Err(cause.clone());
+		}
+
+		self.solve_uncached(selected).inspect_err(|cause| {
+			self.failed.borrow_mut().insert(selected.clone(), cause.clone());
+		})
+	}
+
+	/// Selects a version for the next unsatisfied dependency without checking for known failures (see [`Self::solve`])
+	fn solve_uncached(&self, selected: &mut BTreeMap<String, Version>) -> result::Result<(), Incompatibility> {
+		let Some(id) = self.next_unsatisfied(selected) else {
+			return Ok(());
+		};
+
+		let requirements = self.requirements(selected, &id);
+		let Some(rmod) = self.get(&id) else {
+			debug!("Dependency {} isn't a known mod", id);
+			return Err(Incompatibility::UnknownMod {
+				id,
+				required_by: requirements,
+			});
+		};
+
+		// If any single requirement can't be met by any version, there's no point in looking further
+		let versions: Vec<&Version> = rmod
+			.versions
+			.values()
+			.filter(|version| !version.is_unrecognized())
+			.map(|version| &version.semver)
+			.collect();
+		if let Some(req) = requirements
+			.iter()
+			.find(|req| !versions.iter().any(|semver| req.range.matches(semver)))
+		{
+			return Err(Incompatibility::NoMatchingVersion {
+				id,
+				requirements: vec![req.clone()],
+			});
+		}
+
+		// Try each compatible version of the dependency, newest first
+		let mut candidates: Vec<&Version> = versions
+			.into_iter()
+			.filter(|semver| requirements.iter().all(|req| req.range.matches(semver)))
+			.collect();
+		if candidates.is_empty() {
+			return Err(Incompatibility::NoMatchingVersion { id, requirements });
+		}
+
+		// Only consider versions that the dependency's release channel allows
+		candidates.retain(|semver| self.channels.allows(&id, semver));
+		if candidates.is_empty() {
+			return Err(Incompatibility::Channel {
+				channel: self.channels.channel(&id),
+				id,
+				requirements,
+			});
+		}
+
+		// Only consider versions that the dependency's pin allows
+		candidates.retain(|semver| rmod.allows_version(semver));
+		if candidates.is_empty() {
+			return Err(Incompatibility::Pinned {
+				id,
+				pin: rmod.pin.clone().unwrap_or_default(),
+				requirements,
+			});
+		}
+		candidates.sort_unstable_by(|a, b| b.cmp(a));
+
+		let mut failures = Vec::with_capacity(candidates.len());
+		for candidate in candidates {
+			trace!("Trying {} v{} for dependency resolution", rmod, candidate);
+			let version = &rmod.versions[candidate];
+
+			// Ensure the candidate's own dependencies don't contradict any of the versions already selected
+			if let Some(cause) = self.find_mismatch(selected, &id, version) {
+				failures.push(CandidateFailure {
+					version: candidate.clone(),
+					cause,
+				});
+				continue;
+			}
+
+			// Ensure the candidate doesn't conflict with anything
+			if let Some(conflict) = self.find_conflict(selected, &id, version) {
+				failures.push(CandidateFailure {
+					version: candidate.clone(),
+					cause: Incompatibility::Conflict(Box::new(conflict)),
+				});
+				continue;
+			}
+
+			selected.insert(id.clone(), candidate.clone());
+			match self.solve(selected) {
+				Ok(()) => return Ok(()),
+				Err(cause) => {
+					selected.remove(&id);
+					failures.push(CandidateFailure {
+						version: candidate.clone(),
+						cause,
+					});
+				}
+			}
+		}
+
+		trace!("No version of {} works with the current selection", rmod);
+		Err(Incompatibility::AllVersionsFailed { id, failures })
+	}
+
+	/// Selects a specific version of a mod on top of an existing selection, then selects versions for any dependencies
+	/// it needs that aren't already satisfied. The version must satisfy everything that depends on the mod.
+	fn try_select(
+		&self,
+		selected: &BTreeMap<String, Version>,
+		id: &str,
+		candidate: &Version,
+	) -> result::Result<BTreeMap<String, Version>, Incompatibility> {
+		let requirements: Vec<Requirement> = self
+			.requirements(selected, id)
+			.into_iter()
+			.filter(|req| !req.range.matches(candidate))
+			.collect();
+		if !requirements.is_empty() {
+			return Err(Incompatibility::Requested {
+				id: id.to_owned(),
+				version: candidate.clone(),
+				requirements,
+			});
+		}
+
+		let version = self
+			.get(id)
+			.and_then(|rmod| rmod.versions.get(candidate))
+			.ok_or_else(|| Incompatibility::UnknownMod {
+				id: id.to_owned(),
+				required_by: Vec::new(),
+			})?;
+		if let Some(cause) = self.find_mismatch(selected, id, version) {
+			return Err(cause);
+		}
+		if let Some(conflict) = self.find_conflict(selected, id, version) {
+			return Err(Incompatibility::Conflict(Box::new(conflict)));
+		}
+
+		let mut next = selected.clone();
+		next.insert(id.to_owned(), candidate.clone());
+		self.solve(&mut next)?;
+		Ok(next)
+	}
+
+	/// Builds an install plan from a complete selection, ordered so that dependencies come before their dependents
+	fn build_plan(&self, root: Option<&str>, selected: &BTreeMap<String, Version>) -> InstallPlan {
+		let mut steps = Vec::with_capacity(selected.len());
+		let mut visited = HashSet::with_capacity(selected.len());
+		if let Some(root) = root {
+			self.visit(root, selected, &mut visited, &mut steps);
+		}
+
+		// Anything unreachable from the root goes at the end
+		for id in selected.keys() {
+			self.visit(id, selected, &mut visited, &mut steps);
+		}
+
+		InstallPlan { steps }
+	}
+
+	/// Visits a selected mod's dependencies depth-first, then adds a step for the mod itself if it needs one
+	fn visit(
+		&self,
+		id: &str,
+		selected: &BTreeMap<String, Version>,
+		visited: &mut HashSet<String>,
+		steps: &mut Vec<PlannedInstall>,
+	) {
+		let Some(semver) = selected.get(id) else {
+			return;
+		};
+		if !visited.insert(id.to_owned()) {
+			return;
+		}
+
+		let rmod = self.get(id).expect("selected mod is missing from the mod map");
+		let mut dep_ids: Vec<&String> = rmod.versions[semver].dependencies.keys().collect();
+		dep_ids.sort_unstable();
+		for dep_id in dep_ids {
+			self.visit(dep_id, selected, visited, steps);
+		}
+
+		// Skip mods that already have the selected version installed
+		if rmod.installed_version.as_ref() != Some(semver) {
+			steps.push(PlannedInstall {
+				id: id.to_owned(),
+				version: semver.clone(),
+				replaces: rmod.installed_version.clone(),
+			});
+		}
+	}
+}
+
+/// An ordered set of mod versions to install, with dependencies always preceding their dependents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct InstallPlan {
+	pub steps: Vec<PlannedInstall>,
+}
+
+impl InstallPlan {
+	/// Checks whether the plan doesn't require anything to be installed
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.steps.is_empty()
+	}
+}
+
+impl fmt::Display for InstallPlan {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let text = self
+			.steps
+			.iter()
+			.map(ToString::to_string)
+			.collect::<Vec<_>>()
+			.join(", ");
+		write!(f, "[{text}]")
+	}
+}
+
+/// A single mod version to be installed as part of an [`InstallPlan`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PlannedInstall {
+	pub id: String,
+	pub version: Version,
+	#[serde(rename = "replacesVersion")]
+	pub replaces: Option<Version>,
+}
+
+impl fmt::Display for PlannedInstall {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.replaces {
+			Some(old) => write!(f, "{} v{} -> v{}", self.id, old, self.version),
+			None => write!(f, "{} v{}", self.id, self.version),
+		}
+	}
+}
+
+/// An ordered set of installed mod versions to uninstall, with dependents always preceding the mods they depend on
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct UninstallPlan {
+	pub steps: Vec<PlannedUninstall>,
+}
+
+impl UninstallPlan {
+	/// Checks whether the plan doesn't require anything to be uninstalled
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.steps.is_empty()
+	}
+}
+
+impl fmt::Display for UninstallPlan {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let text = self
+			.steps
+			.iter()
+			.map(ToString::to_string)
+			.collect::<Vec<_>>()
+			.join(", ");
+		write!(f, "[{text}]")
+	}
+}
+
+/// A single installed mod version to be uninstalled as part of an [`UninstallPlan`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PlannedUninstall {
+	pub id: String,
+	pub version: Version,
+}
+
+impl fmt::Display for PlannedUninstall {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} v{}", self.id, self.version)
+	}
+}
+
+/// Changes needed to switch the installed mods over to a [`ModProfile`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ProfilePlan {
+	/// Mod versions to install or update to
+	pub install: InstallPlan,
+	/// IDs of installed mods to enable
+	pub enable: Vec<String>,
+	/// IDs of installed mods to disable
+	pub disable: Vec<String>,
+	/// Installed mods to uninstall
+	pub remove: UninstallPlan,
+}
+
+impl ProfilePlan {
+	/// Checks whether the plan doesn't require any changes
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.install.is_empty() && self.enable.is_empty() && self.disable.is_empty() && self.remove.is_empty()
+	}
+}
+
+impl fmt::Display for ProfilePlan {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"install {}, enable [{}], disable [{}], remove {}",
+			self.install,
+			self.enable.join(", "),
+			self.disable.join(", "),
+			self.remove
+		)
+	}
+}
+
+/// A conflict declared by one mod version against a version of another mod
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ModConflict {
+	pub id: String,
+	pub version: Version,
+	#[serde(rename = "conflictingId")]
+	pub conflicting_id: String,
+	#[serde(rename = "conflictingVersion")]
+	pub conflicting_version: Version,
+	pub range: VersionReq,
+}
+
+impl fmt::Display for ModConflict {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} v{} conflicts with {} {} (v{})",
+			self.id, self.version, self.conflicting_id, self.range, self.conflicting_version
+		)
+	}
+}
+
+/// A Vec of mod conflicts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[allow(clippy::exhaustive_structs)]
+pub struct ModConflictVec(pub Vec<ModConflict>);
+
+impl ModConflictVec {
+	/// Creates a new empty conflict vec
+	#[must_use]
+	pub const fn new() -> Self {
+		Self(Vec::new())
+	}
+
+	#[inline]
+	pub fn push(&mut self, conflict: ModConflict) {
+		self.0.push(conflict);
+	}
+
+	#[inline]
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	#[inline]
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.0.len()
+	}
+}
+
+impl fmt::Display for ModConflictVec {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let text = self.0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+		write!(f, "[{text}]")
+	}
+}
+
+/// A requirement placed on a mod by a version of another mod that depends on it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Requirement {
+	pub dependent: String,
+	#[serde(rename = "dependentVersion")]
+	pub dependent_version: Version,
+	#[serde(rename = "dependentInstalled")]
+	pub dependent_installed: bool,
+	pub id: String,
+	pub range: VersionReq,
+}
+
+impl fmt::Display for Requirement {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} v{}", self.dependent, self.dependent_version)?;
+		if self.dependent_installed {
+			write!(f, " (installed)")?;
+		}
+		write!(f, " needs {} {}", self.id, self.range)
+	}
+}
+
+/// Explanation of why dependency resolution failed, structured as a derivation tree.
+/// Each node describes a single incompatibility, and [`Incompatibility::AllVersionsFailed`] nodes branch out into the
+/// reasons each version of a mod was ruled out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum Incompatibility {
+	/// The explicitly requested version of a mod doesn't satisfy requirements placed on it by installed mods
+	Requested {
+		id: String,
+		version: Version,
+		requirements: Vec<Requirement>,
+	},
+
+	/// A required mod doesn't exist
+	UnknownMod {
+		id: String,
+		#[serde(rename = "requiredBy")]
+		required_by: Vec<Requirement>,
+	},
+
+	/// No version of a mod satisfies all of the requirements placed on it
+	NoMatchingVersion { id: String, requirements: Vec<Requirement> },
+
+	/// No version of a mod that its release channel allows satisfies all of the requirements placed on it
+	Channel {
+		id: String,
+		channel: ReleaseChannel,
+		requirements: Vec<Requirement>,
+	},
+
+	/// No version of a mod that its pin allows satisfies all of the requirements placed on it
+	Pinned {
+		id: String,
+		pin: VersionReq,
+		requirements: Vec<Requirement>,
+	},
+
+	/// A version of a mod needs a version of a dependency other than the one already selected
+	SelectedVersion {
+		id: String,
+		version: Version,
+		requirement: Box<Requirement>,
+		#[serde(rename = "selectedBecause")]
+		selected_because: Vec<Requirement>,
+	},
+
+	/// A version of a mod conflicts with another mod
+	Conflict(Box<ModConflict>),
+
+	/// Every version of a mod that satisfies its requirements was ruled out
+	AllVersionsFailed {
+		id: String,
+		failures: Vec<CandidateFailure>,
+	},
+}
+
+impl Incompatibility {
+	/// Writes the explanation at a given depth of the derivation tree
+	fn fmt_depth(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+		match self {
+			Self::Requested {
+				id,
+				version,
+				requirements,
+			} => write!(
+				f,
+				"{id} v{version} was requested, but {}",
+				join_requirements(requirements)
+			),
+
+			Self::UnknownMod { id, required_by } => {
+				if required_by.is_empty() {
+					write!(f, "no mod with the ID {id} exists")
+				} else {
+					write!(
+						f,
+						"{}, but no mod with the ID {id} exists",
+						join_requirements(required_by)
+					)
+				}
+			}
+
+			Self::NoMatchingVersion { id, requirements } => match requirements.split_first() {
+				Some((first, [])) => write!(f, "{first}, but no version of {id} matches"),
+				Some((first, rest)) => write!(f, "{first}, but {}", join_requirements(rest)),
+				None => write!(f, "no version of {id} is available"),
+			},
+
+			Self::Channel {
+				id,
+				channel,
+				requirements,
+			} => write!(
+				f,
+				"{}, but only versions outside of the {channel} release channel of {id} match",
+				join_requirements(requirements)
+			),
+
+			Self::Pinned { id, pin, requirements } => {
+				if requirements.is_empty() {
+					write!(f, "{id} is pinned to {pin}, which doesn't allow any available version")
+				} else {
+					write!(f, "{}, but {id} is pinned to {pin}", join_requirements(requirements))
+				}
+			}
+
+			Self::SelectedVersion {
+				id,
+				version,
+				requirement,
+				selected_because,
+			} => {
+				if selected_because.is_empty() {
+					write!(f, "{requirement}, but {id} v{version} was requested")
+				} else {
+					write!(
+						f,
+						"{requirement}, but {id} v{version} was selected because {}",
+						join_requirements(selected_because)
+					)
+				}
+			}
+
+			Self::Conflict(conflict) => write!(f, "{conflict}"),
+
+			Self::AllVersionsFailed { id, failures } => {
+				write!(f, "every usable version of {id} was ruled out:")?;
+				let indent = "  ".repeat(depth.saturating_add(1));
+				for failure in failures {
+					write!(f, "\n{indent}- {id} v{}: ", failure.version)?;
+					failure.cause.fmt_depth(f, depth.saturating_add(1))?;
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
+impl fmt::Display for Incompatibility {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.fmt_depth(f, 0)
+	}
+}
+
+/// The reason a single version of a mod was ruled out during dependency resolution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CandidateFailure {
+	pub version: Version,
+	pub cause: Incompatibility,
+}
+
+/// Joins a list of requirements into a readable sentence fragment
+fn join_requirements(requirements: &[Requirement]) -> String {
+	requirements
+		.iter()
+		.map(ToString::to_string)
+		.collect::<Vec<_>>()
+		.join(" and ")
+}
+
+#[cfg(test)]
+mod tests {
+	use semver::{Version, VersionReq};
+
+	use super::{CandidateFailure, Incompatibility, InstallPlan, Requirement, Resolver, UninstallPlan};
+	use crate::{
+		models::{ChannelPolicy, InstallReason, ModProfile, ModVersion, ReleaseChannel, ResoluteMod, ResoluteModMap},
+		Error,
+	};
+
+	fn semver(version: &str) -> Version {
+		Version::parse(version).expect("parse version")
+	}
+
+	fn range(req: &str) -> VersionReq {
+		VersionReq::parse(req).expect("parse version requirement")
+	}
+
+	/// Builds a version of a mod with the given dependencies and conflicts
+	fn version(semver_str: &str, dependencies: &[(&str, &str)], conflicts: &[(&str, &str)]) -> ModVersion {
+		ModVersion {
+			semver: semver(semver_str),
+			artifacts: Vec::new(),
+			dependencies: dependencies
+				.iter()
+				.map(|(id, req)| ((*id).to_owned(), range(req)))
+				.collect(),
+			conflicts: conflicts
+				.iter()
+				.map(|(id, req)| ((*id).to_owned(), range(req)))
+				.collect(),
+			release_url: None,
+			changelog: None,
+		}
+	}
+
+	/// Builds a mod that isn't installed
+	fn rmod(id: &str, versions: Vec<ModVersion>) -> ResoluteMod {
+		ResoluteMod {
+			id: id.to_owned(),
+			name: id.to_owned(),
+			description: String::new(),
+			category: "Libraries".to_owned(),
+			authors: Vec::new(),
+			source_location: None,
+			website: None,
+			tags: None,
+			flags: None,
+			platforms: None,
+			versions: versions
+				.into_iter()
+				.map(|version| (version.semver.clone(), version))
+				.collect(),
+			installed_version: None,
+			install_reason: None,
+			pin: None,
+			active: false,
+		}
+	}
+
+	/// Marks a mod as having a version installed
+	fn installed(mut rmod: ResoluteMod, version: &str, reason: InstallReason) -> ResoluteMod {
+		rmod.installed_version = Some(semver(version));
+		rmod.install_reason = Some(reason);
+		rmod.active = true;
+		rmod
+	}
+
+	fn map(mods: impl IntoIterator<Item = ResoluteMod>) -> ResoluteModMap {
+		mods.into_iter().map(|rmod| (rmod.id.clone(), rmod)).collect()
+	}
+
+	fn requirement(dependent: &str, dependent_version: &str, installed: bool, id: &str, req: &str) -> Requirement {
+		Requirement {
+			dependent: dependent.to_owned(),
+			dependent_version: semver(dependent_version),
+			dependent_installed: installed,
+			id: id.to_owned(),
+			range: range(req),
+		}
+	}
+
+	fn steps(plan: &InstallPlan) -> Vec<String> {
+		plan.steps.iter().map(ToString::to_string).collect()
+	}
+
+	fn uninstall_steps(plan: &UninstallPlan) -> Vec<String> {
+		plan.steps.iter().map(ToString::to_string).collect()
+	}
+
+	/// Resolves the installation of a version of a mod in the mod map
+	fn resolve(mods: &ResoluteModMap, id: &str, version: &str) -> crate::Result<InstallPlan> {
+		let channels = ChannelPolicy::default();
+		Resolver::new(mods, &channels).resolve(&mods[id], &semver(version))
+	}
+
+	#[test]
+	fn resolves_newest_matching_dependencies() {
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[("lib", "^1")], &[])]),
+			rmod(
+				"lib",
+				vec![
+					version("1.0.0", &[("core", "^1")], &[]),
+					version("1.2.0", &[("core", "^1")], &[]),
+					version("2.0.0", &[("core", "^1")], &[]),
+				],
+			),
+			rmod(
+				"core",
+				vec![version("1.0.0", &[], &[]), version("1.1.0-beta.1", &[], &[])],
+			),
+		]);
+
+		let plan = resolve(&mods, "app", "1.0.0").expect("resolve");
+		assert_eq!(steps(&plan), ["core v1.0.0", "lib v1.2.0", "app v1.0.0"]);
+	}
+
+	#[test]
+	fn keeps_compatible_installed_dependencies() {
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[("lib", "^1")], &[])]),
+			installed(
+				rmod("lib", vec![version("1.0.0", &[], &[]), version("1.2.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+		]);
+
+		let plan = resolve(&mods, "app", "1.0.0").expect("resolve");
+		assert_eq!(steps(&plan), ["app v1.0.0"]);
+	}
+
+	#[test]
+	fn updates_installed_dependencies_that_dont_match() {
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[("lib", "^1.2")], &[])]),
+			installed(
+				rmod("lib", vec![version("1.0.0", &[], &[]), version("1.2.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+		]);
+
+		let plan = resolve(&mods, "app", "1.0.0").expect("resolve");
+		assert_eq!(steps(&plan), ["lib v1.0.0 -> v1.2.0", "app v1.0.0"]);
+	}
+
+	#[test]
+	fn backtracks_to_older_versions() {
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[("lib", ">=1")], &[])]),
+			rmod(
+				"lib",
+				vec![
+					version("1.0.0", &[("core", "^1")], &[]),
+					version("2.0.0", &[("core", "^2")], &[]),
+				],
+			),
+			rmod("core", vec![version("1.0.0", &[], &[])]),
+		]);
+
+		let plan = resolve(&mods, "app", "1.0.0").expect("resolve");
+		assert_eq!(steps(&plan), ["core v1.0.0", "lib v1.0.0", "app v1.0.0"]);
+	}
+
+	#[test]
+	fn avoids_versions_that_conflict_with_installed_mods() {
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[("lib", "*")], &[])]),
+			rmod(
+				"lib",
+				vec![version("1.0.0", &[], &[]), version("2.0.0", &[], &[("other", "^1")])],
+			),
+			installed(
+				rmod("other", vec![version("1.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+		]);
+
+		let plan = resolve(&mods, "app", "1.0.0").expect("resolve");
+		assert_eq!(steps(&plan), ["lib v1.0.0", "app v1.0.0"]);
+	}
+
+	#[test]
+	fn fails_when_every_version_is_ruled_out() {
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[("lib", "*")], &[])]),
+			rmod("lib", vec![version("1.0.0", &[], &[("other", "*")])]),
+			installed(
+				rmod("other", vec![version("1.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+		]);
+
+		let err = resolve(&mods, "app", "1.0.0").expect_err("resolution should fail");
+		let Error::UnresolvableDependencies { id, cause, .. } = err else {
+			panic!("unexpected error: {err}");
+		};
+		assert_eq!(id, "app");
+		assert!(matches!(*cause, Incompatibility::AllVersionsFailed { id, .. } if id == "lib"));
+	}
+
+	#[test]
+	fn fails_for_unknown_dependencies() {
+		let mods = map([rmod("app", vec![version("1.0.0", &[("missing", "^1")], &[])])]);
+
+		let err = resolve(&mods, "app", "1.0.0").expect_err("resolution should fail");
+		let Error::UnresolvableDependencies { cause, .. } = err else {
+			panic!("unexpected error: {err}");
+		};
+		assert!(matches!(*cause, Incompatibility::UnknownMod { id, .. } if id == "missing"));
+	}
+
+	#[test]
+	fn refuses_to_break_installed_dependents() {
+		let mods = map([
+			installed(
+				rmod("app", vec![version("1.0.0", &[("lib", "^1")], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+			installed(
+				rmod("lib", vec![version("1.0.0", &[], &[]), version("2.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Dependency,
+			),
+		]);
+
+		let err = resolve(&mods, "lib", "2.0.0").expect_err("resolution should fail");
+		let Error::UnresolvableDependencies { cause, .. } = err else {
+			panic!("unexpected error: {err}");
+		};
+		assert!(matches!(*cause, Incompatibility::Requested { id, .. } if id == "lib"));
+	}
+
+	#[test]
+	fn finds_conflicts_introduced_by_a_plan() {
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[], &[("other", "<2")])]),
+			installed(
+				rmod("other", vec![version("1.5.0", &[], &[])]),
+				"1.5.0",
+				InstallReason::Explicit,
+			),
+			installed(
+				rmod("unrelated", vec![version("1.0.0", &[], &[("other", "*")])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+		]);
+		let channels = ChannelPolicy::default();
+		let resolver = Resolver::new(&mods, &channels);
+
+		let plan = resolve(&mods, "app", "1.0.0").expect("resolve");
+		let conflicts = resolver.conflicts(None, &plan, &mods);
+		assert_eq!(conflicts.len(), 1);
+		assert_eq!(conflicts.to_string(), "[app v1.0.0 conflicts with other <2 (v1.5.0)]");
+	}
+
+	#[test]
+	fn explains_simple_incompatibilities() {
+		let missing = Incompatibility::UnknownMod {
+			id: "missing".to_owned(),
+			required_by: vec![requirement("app", "1.0.0", false, "missing", "^1")],
+		};
+		assert_eq!(
+			missing.to_string(),
+			"app v1.0.0 needs missing ^1, but no mod with the ID missing exists"
+		);
+
+		let requested = Incompatibility::Requested {
+			id: "lib".to_owned(),
+			version: semver("2.0.0"),
+			requirements: vec![
+				requirement("app", "1.0.0", true, "lib", "^1"),
+				requirement("tool", "0.3.0", true, "lib", "<1.5"),
+			],
+		};
+		assert_eq!(
+			requested.to_string(),
+			"lib v2.0.0 was requested, but app v1.0.0 (installed) needs lib ^1 and tool v0.3.0 (installed) needs lib <1.5"
+		);
+
+		let no_match = Incompatibility::NoMatchingVersion {
+			id: "lib".to_owned(),
+			requirements: vec![requirement("app", "1.0.0", false, "lib", "^3")],
+		};
+		assert_eq!(
+			no_match.to_string(),
+			"app v1.0.0 needs lib ^3, but no version of lib matches"
+		);
+
+		let pinned = Incompatibility::Pinned {
+			id: "lib".to_owned(),
+			pin: range("=1.0.0"),
+			requirements: vec![requirement("app", "1.0.0", false, "lib", "^1.2")],
+		};
+		assert_eq!(
+			pinned.to_string(),
+			"app v1.0.0 needs lib ^1.2, but lib is pinned to =1.0.0"
+		);
+	}
+
+	#[test]
+	fn explains_nested_incompatibilities() {
+		let cause = Incompatibility::AllVersionsFailed {
+			id: "lib".to_owned(),
+			failures: vec![
+				CandidateFailure {
+					version: semver("2.0.0"),
+					cause: Incompatibility::AllVersionsFailed {
+						id: "core".to_owned(),
+						failures: vec![CandidateFailure {
+							version: semver("2.0.0"),
+							cause: Incompatibility::SelectedVersion {
+								id: "util".to_owned(),
+								version: semver("1.0.0"),
+								requirement: Box::new(requirement("core", "2.0.0", false, "util", "^2")),
+								selected_because: vec![requirement("app", "1.0.0", false, "util", "^1")],
+							},
+						}],
+					},
+				},
+				CandidateFailure {
+					version: semver("1.0.0"),
+					cause: Incompatibility::NoMatchingVersion {
+						id: "core".to_owned(),
+						requirements: vec![requirement("lib", "1.0.0", false, "core", "^1")],
+					},
+				},
+			],
+		};
+
+		assert_eq!(
+			cause.to_string(),
+			"every usable version of lib was ruled out:\n  - lib v2.0.0: every usable version of core was ruled \
+			 out:\n    - core v2.0.0: core v2.0.0 needs util ^2, but util v1.0.0 was selected because app v1.0.0 \
+			 needs util ^1\n  - lib v1.0.0: lib v1.0.0 needs core ^1, but no version of core matches"
+		);
+	}
+
+	#[test]
+	fn explains_failures_from_resolution() {
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[("lib", "*")], &[])]),
+			rmod("lib", vec![version("1.0.0", &[], &[("other", "*")])]),
+			installed(
+				rmod("other", vec![version("1.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+		]);
+
+		let err = resolve(&mods, "app", "1.0.0").expect_err("resolution should fail");
+		let Error::UnresolvableDependencies { cause, .. } = err else {
+			panic!("unexpected error: {err}");
+		};
+		assert_eq!(
+			cause.to_string(),
+			"every usable version of lib was ruled out:\n  - lib v1.0.0: lib v1.0.0 conflicts with other * (v1.0.0)"
+		);
+	}
+
+	/// Builds a chain of installed mods where each one depends on the next: app -> lib -> core
+	fn dependency_chain() -> ResoluteModMap {
+		map([
+			installed(
+				rmod("app", vec![version("1.0.0", &[("lib", "^1")], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+			installed(
+				rmod("lib", vec![version("1.0.0", &[("core", "^1")], &[])]),
+				"1.0.0",
+				InstallReason::Dependency,
+			),
+			installed(
+				rmod("core", vec![version("1.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Dependency,
+			),
+			installed(
+				rmod("other", vec![version("1.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+		])
+	}
+
+	#[test]
+	fn refuses_to_uninstall_mods_with_dependents() {
+		let mods = dependency_chain();
+		let channels = ChannelPolicy::default();
+		let resolver = Resolver::new(&mods, &channels);
+
+		let err = resolver
+			.resolve_uninstall(&mods["core"], false)
+			.expect_err("uninstall should fail");
+		let Error::RequiredBy { id, dependents } = err else {
+			panic!("unexpected error: {err}");
+		};
+		assert_eq!(id, "core");
+		assert_eq!(dependents, ["lib"]);
+	}
+
+	#[test]
+	fn cascades_uninstalls_to_dependents() {
+		let mods = dependency_chain();
+		let channels = ChannelPolicy::default();
+		let resolver = Resolver::new(&mods, &channels);
+
+		let plan = resolver
+			.resolve_uninstall(&mods["core"], true)
+			.expect("resolve uninstall");
+		assert_eq!(uninstall_steps(&plan), ["app v1.0.0", "lib v1.0.0", "core v1.0.0"]);
+
+		let plan = resolver
+			.resolve_uninstall(&mods["other"], false)
+			.expect("resolve uninstall");
+		assert_eq!(uninstall_steps(&plan), ["other v1.0.0"]);
+	}
+
+	#[test]
+	fn refuses_to_uninstall_mods_that_arent_installed() {
+		let mods = map([rmod("app", vec![version("1.0.0", &[], &[])])]);
+		let channels = ChannelPolicy::default();
+		let resolver = Resolver::new(&mods, &channels);
+
+		let err = resolver
+			.resolve_uninstall(&mods["app"], true)
+			.expect_err("uninstall should fail");
+		assert!(matches!(err, Error::ModNotInstalled(_)));
+	}
+
+	#[test]
+	fn finds_no_orphans_while_dependencies_are_needed() {
+		let mods = dependency_chain();
+		let channels = ChannelPolicy::default();
+
+		let plan = Resolver::new(&mods, &channels).resolve_orphans();
+		assert!(plan.is_empty());
+	}
+
+	#[test]
+	fn finds_orphaned_dependencies() {
+		let mut mods = dependency_chain();
+		mods.remove("app");
+		let channels = ChannelPolicy::default();
+
+		let plan = Resolver::new(&mods, &channels).resolve_orphans();
+		assert_eq!(uninstall_steps(&plan), ["lib v1.0.0", "core v1.0.0"]);
+	}
+
+	#[test]
+	fn keeps_dependencies_needed_by_other_mods() {
+		let mut mods = dependency_chain();
+		mods.remove("app");
+		mods.insert(
+			"tool".to_owned(),
+			installed(
+				rmod("tool", vec![version("1.0.0", &[("core", "*")], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+		);
+		let channels = ChannelPolicy::default();
+
+		let plan = Resolver::new(&mods, &channels).resolve_orphans();
+		assert_eq!(uninstall_steps(&plan), ["lib v1.0.0"]);
+	}
+
+	#[test]
+	fn updates_all_mods_together() {
+		let mods = map([
+			installed(
+				rmod(
+					"app",
+					vec![
+						version("1.0.0", &[("lib", "^1")], &[]),
+						version("2.0.0", &[("lib", "^2")], &[]),
+					],
+				),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+			installed(
+				rmod("lib", vec![version("1.0.0", &[], &[]), version("2.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Dependency,
+			),
+		]);
+		let channels = ChannelPolicy::default();
+
+		let plan = Resolver::new(&mods, &channels)
+			.resolve_update_all()
+			.expect("resolve update");
+		assert_eq!(steps(&plan), ["lib v1.0.0 -> v2.0.0", "app v1.0.0 -> v2.0.0"]);
+	}
+
+	#[test]
+	fn holds_back_updates_that_would_break_dependents() {
+		let mods = map([
+			installed(
+				rmod("tool", vec![version("1.0.0", &[("lib", "^1")], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+			installed(
+				rmod(
+					"lib",
+					vec![
+						version("1.0.0", &[], &[]),
+						version("1.1.0", &[], &[]),
+						version("2.0.0", &[], &[]),
+					],
+				),
+				"1.0.0",
+				InstallReason::Dependency,
+			),
+		]);
+		let channels = ChannelPolicy::default();
+
+		let plan = Resolver::new(&mods, &channels)
+			.resolve_update_all()
+			.expect("resolve update");
+		assert_eq!(steps(&plan), ["lib v1.0.0 -> v1.1.0"]);
+	}
+
+	#[test]
+	fn respects_channels_and_pins_when_updating_all() {
+		let mut pinned = installed(
+			rmod("pinned", vec![version("1.0.0", &[], &[]), version("1.1.0", &[], &[])]),
+			"1.0.0",
+			InstallReason::Explicit,
+		);
+		pinned.pin = Some(range("=1.0.0"));
+		let mods = map([
+			installed(
+				rmod(
+					"stable",
+					vec![version("1.0.0", &[], &[]), version("1.1.0-beta.1", &[], &[])],
+				),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+			installed(
+				rmod(
+					"beta",
+					vec![version("1.0.0", &[], &[]), version("1.1.0-beta.1", &[], &[])],
+				),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+			pinned,
+		]);
+		let channels = ChannelPolicy::default().with_mod("beta", ReleaseChannel::Prerelease);
+
+		let plan = Resolver::new(&mods, &channels)
+			.resolve_update_all()
+			.expect("resolve update");
+		assert_eq!(steps(&plan), ["beta v1.0.0 -> v1.1.0-beta.1"]);
+	}
+
+	#[test]
+	fn fails_to_update_all_when_installed_mods_are_broken() {
+		let mods = map([installed(
+			rmod("app", vec![version("1.0.0", &[("missing", "^1")], &[])]),
+			"1.0.0",
+			InstallReason::Explicit,
+		)]);
+		let channels = ChannelPolicy::default();
+
+		let err = Resolver::new(&mods, &channels)
+			.resolve_update_all()
+			.expect_err("update should fail");
+		assert!(matches!(err, Error::UnresolvableUpdate(_)));
+	}
+
+	/// Builds a profile from mod IDs and the versions it specifies for them
+	fn profile(mods: &[(&str, Option<&str>)]) -> ModProfile {
+		let mut profile = ModProfile::new("test");
+		profile.mods = mods
+			.iter()
+			.map(|(id, version)| ((*id).to_owned(), version.map(semver)))
+			.collect();
+		profile
+	}
+
+	#[test]
+	fn switches_installed_mods_over_to_profiles() {
+		let mut disabled = installed(
+			rmod("disabled", vec![version("1.0.0", &[], &[])]),
+			"1.0.0",
+			InstallReason::Explicit,
+		);
+		disabled.active = false;
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[("lib", "^1")], &[])]),
+			rmod("lib", vec![version("1.0.0", &[], &[]), version("1.1.0", &[], &[])]),
+			installed(
+				rmod("other", vec![version("1.0.0", &[("other-lib", "^1")], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+			installed(
+				rmod("other-lib", vec![version("1.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Dependency,
+			),
+			installed(
+				rmod("stray", vec![version("1.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Dependency,
+			),
+			disabled,
+		]);
+		let channels = ChannelPolicy::default();
+
+		let plan = Resolver::new(&mods, &channels)
+			.resolve_profile(&profile(&[("app", None), ("disabled", None)]))
+			.expect("resolve profile");
+		assert_eq!(steps(&plan.install), ["lib v1.1.0", "app v1.0.0"]);
+		assert_eq!(plan.enable, ["disabled"]);
+		assert_eq!(plan.disable, ["other", "other-lib"]);
+		assert_eq!(uninstall_steps(&plan.remove), ["stray v1.0.0"]);
+	}
+
+	#[test]
+	fn uses_profile_versions_over_installed_ones() {
+		let mods = map([installed(
+			rmod("app", vec![version("1.0.0", &[], &[]), version("2.0.0", &[], &[])]),
+			"2.0.0",
+			InstallReason::Explicit,
+		)]);
+		let channels = ChannelPolicy::default();
+		let resolver = Resolver::new(&mods, &channels);
+
+		let plan = resolver
+			.resolve_profile(&profile(&[("app", Some("1.0.0"))]))
+			.expect("resolve profile");
+		assert_eq!(steps(&plan.install), ["app v2.0.0 -> v1.0.0"]);
+		assert!(plan.enable.is_empty() && plan.disable.is_empty() && plan.remove.is_empty());
+
+		let plan = resolver
+			.resolve_profile(&profile(&[("app", None)]))
+			.expect("resolve profile");
+		assert!(plan.install.is_empty());
+	}
+
+	#[test]
+	fn ignores_requirements_of_mods_outside_profiles() {
+		let mods = map([
+			installed(
+				rmod("app", vec![version("1.0.0", &[("lib", "^1")], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+			installed(
+				rmod("lib", vec![version("1.0.0", &[], &[]), version("2.0.0", &[], &[])]),
+				"1.0.0",
+				InstallReason::Explicit,
+			),
+		]);
+		let channels = ChannelPolicy::default();
+
+		let plan = Resolver::new(&mods, &channels)
+			.resolve_profile(&profile(&[("lib", Some("2.0.0"))]))
+			.expect("resolve profile");
+		assert_eq!(steps(&plan.install), ["lib v1.0.0 -> v2.0.0"]);
+		assert_eq!(plan.disable, ["app"]);
+	}
+
+	#[test]
+	fn refuses_profiles_that_dont_work() {
+		let mut pinned = rmod("pinned", vec![version("1.0.0", &[], &[]), version("2.0.0", &[], &[])]);
+		pinned.pin = Some(range("^1"));
+		let mods = map([
+			rmod("app", vec![version("1.0.0", &[("lib", "^1")], &[])]),
+			rmod("lib", vec![version("1.0.0", &[], &[]), version("2.0.0", &[], &[])]),
+			pinned,
+		]);
+		let channels = ChannelPolicy::default();
+		let resolver = Resolver::new(&mods, &channels);
+
+		let err = resolver
+			.resolve_profile(&profile(&[("app", None), ("lib", Some("2.0.0"))]))
+			.expect_err("profile should fail");
+		let Error::UnresolvableDependencies { id, cause, .. } = err else {
+			panic!("unexpected error: {err}");
+		};
+		assert_eq!(id, "lib");
+		assert!(matches!(*cause, Incompatibility::Requested { .. }));
+
+		let err = resolver
+			.resolve_profile(&profile(&[("pinned", Some("2.0.0"))]))
+			.expect_err("profile should fail");
+		assert!(matches!(err, Error::Pinned { .. }));
+
+		let err = resolver
+			.resolve_profile(&profile(&[("missing", None)]))
+			.expect_err("profile should fail");
+		assert!(matches!(err, Error::UnknownMod(_)));
+	}
+}
@@ -0,0 +1,271 @@
+use std::{
+	fmt,
+	future::Future,
+	time::{Duration, SystemTime},
+};
+
+use log::{debug, error, warn};
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+use tokio::time as tokio_time;
+
+use crate::{Error, Result};
+
+/// Policy for retrying HTTP requests that fail with transient errors, such as connection problems or server errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RetryPolicy {
+	/// Maximum number of attempts to make, including the first one
+	pub max_attempts: u32,
+	/// Delay before the first retry - it doubles for each retry after that
+	pub initial_backoff: Duration,
+	/// Longest delay before any retry, including delays requested by servers with a Retry-After header
+	pub max_backoff: Duration,
+	/// Whether to randomize each delay to between half and all of its backoff, so that many clients that failed at the
+	/// same time don't all retry at the same time
+	pub jitter: bool,
+	/// Response statuses that are retried
+	pub retryable_statuses: Vec<StatusCode>,
+	/// Whether to retry requests that fail due to connection problems or timeouts
+	pub retry_network_errors: bool,
+}
+
+impl RetryPolicy {
+	/// Creates a new policy with defaults set
+	#[inline]
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a policy that never retries
+	#[must_use]
+	pub fn never() -> Self {
+		Self::default().max_attempts(1)
+	}
+
+	/// Sets the maximum number of attempts to make, including the first one (at least one)
+	#[must_use]
+	pub fn max_attempts(mut self, attempts: u32) -> Self {
+		self.max_attempts = attempts.max(1);
+		self
+	}
+
+	/// Sets the delay before the first retry and the longest delay before any retry
+	#[must_use]
+	pub const fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+		self.initial_backoff = initial;
+		self.max_backoff = max;
+		self
+	}
+
+	/// Sets whether to randomize each delay
+	#[must_use]
+	pub const fn jitter(mut self, jitter: bool) -> Self {
+		self.jitter = jitter;
+		self
+	}
+
+	/// Sets the response statuses that are retried
+	#[must_use]
+	pub fn retryable_statuses(mut self, statuses: Vec<StatusCode>) -> Self {
+		self.retryable_statuses = statuses;
+		self
+	}
+
+	/// Sets whether to retry requests that fail due to connection problems or timeouts
+	#[must_use]
+	pub const fn retry_network_errors(mut self, retry: bool) -> Self {
+		self.retry_network_errors = retry;
+		self
+	}
+
+	/// Runs an operation, retrying it according to the policy whenever it fails with a retryable error.
+	/// The description of the operation is used for logging each attempt.
+	pub async fn run<T, F, Fut>(&self, description: impl fmt::Display, mut operation: F) -> Result<T>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<T>>,
+	{
+		let mut attempt = 1;
+		loop {
+			debug!("Attempt {}/{} of {}", attempt, self.max_attempts, description);
+			match operation().await {
+				Ok(value) => return Ok(value),
+				Err(err) if attempt < self.max_attempts && self.is_retryable(&err) => {
+					let delay = self.delay(attempt, &err);
+					warn!(
+						"Attempt {}/{} of {} failed, retrying in {:?}: {}",
+						attempt, self.max_attempts, description, delay, err
+					);
+					tokio_time::sleep(delay).await;
+					attempt = attempt.saturating_add(1);
+				}
+				Err(err) => {
+					if attempt > 1 {
+						error!("Giving up on {} after {} attempts: {}", description, attempt, err);
+					}
+					return Err(err);
+				}
+			}
+		}
+	}
+
+	/// Checks whether an error is one that the policy retries
+	#[must_use]
+	pub fn is_retryable(&self, err: &Error) -> bool {
+		match err {
+			Error::Http(status) | Error::HttpRetryAfter { status, .. } => self.retryable_statuses.contains(status),
+			// Connections dropped partway through a response body show up as decode errors
+			Error::Request(err) => {
+				self.retry_network_errors
+					&& (err.is_timeout() || err.is_connect() || err.is_request() || err.is_body() || err.is_decode())
+			}
+			_ => false,
+		}
+	}
+
+	/// Determines how long to wait before a retry. Delays requested by the server take precedence over the backoff.
+	fn delay(&self, attempt: u32, err: &Error) -> Duration {
+		if let Error::HttpRetryAfter { retry_after, .. } = err {
+			return (*retry_after).min(self.max_backoff);
+		}
+
+		let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+		let backoff = self.initial_backoff.saturating_mul(factor).min(self.max_backoff);
+		if !self.jitter {
+			return backoff;
+		}
+
+		// Pick a random delay between half of the backoff and all of it
+		let half = backoff.checked_div(2).unwrap_or_default();
+		let half_millis = u64::try_from(half.as_millis()).unwrap_or(u64::MAX);
+		let extra = rand::thread_rng().gen_range(0..=half_millis);
+		half.saturating_add(Duration::from_millis(extra))
+	}
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			max_attempts: 3,
+			initial_backoff: Duration::from_millis(500),
+			max_backoff: Duration::from_secs(30),
+			jitter: true,
+			retryable_statuses: vec![
+				StatusCode::REQUEST_TIMEOUT,
+				StatusCode::TOO_MANY_REQUESTS,
+				StatusCode::INTERNAL_SERVER_ERROR,
+				StatusCode::BAD_GATEWAY,
+				StatusCode::SERVICE_UNAVAILABLE,
+				StatusCode::GATEWAY_TIMEOUT,
+			],
+			retry_network_errors: true,
+		}
+	}
+}
+
+/// Ensures a response has a successful status, turning it into an error otherwise.
+/// If the server indicated when to retry with a Retry-After header, that's included in the error.
+pub(crate) fn check_response(response: Response) -> Result<Response> {
+	let status = response.status();
+	if status.is_success() {
+		return Ok(response);
+	}
+
+	let retry_after = response
+		.headers()
+		.get(RETRY_AFTER)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| parse_retry_after(value, OffsetDateTime::now_utc()));
+	Err(match retry_after {
+		Some(retry_after) => Error::HttpRetryAfter { status, retry_after },
+		None => Error::Http(status),
+	})
+}
+
+/// Parses the value of a Retry-After header, which is either a number of seconds or an HTTP date, into how long to
+/// wait from the given time. Dates in the past mean no wait at all.
+fn parse_retry_after(value: &str, now: OffsetDateTime) -> Option<Duration> {
+	let value = value.trim();
+	if let Ok(seconds) = value.parse() {
+		return Some(Duration::from_secs(seconds));
+	}
+
+	let date = OffsetDateTime::parse(value, &Rfc2822).ok()?;
+	Some(SystemTime::from(date).duration_since(now.into()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use reqwest::StatusCode;
+	use time::OffsetDateTime;
+
+	use super::{parse_retry_after, RetryPolicy};
+	use crate::Error;
+
+	fn policy() -> RetryPolicy {
+		RetryPolicy::new()
+			.backoff(Duration::from_millis(500), Duration::from_secs(3))
+			.jitter(false)
+	}
+
+	#[test]
+	fn backoff_doubles_up_to_max() {
+		let policy = policy();
+		let err = Error::Http(StatusCode::SERVICE_UNAVAILABLE);
+		let delays: Vec<_> = (1..=5).map(|attempt| policy.delay(attempt, &err)).collect();
+		assert_eq!(
+			delays,
+			[500, 1000, 2000, 3000, 3000].map(Duration::from_millis).to_vec()
+		);
+	}
+
+	#[test]
+	fn jitter_stays_within_half_of_backoff() {
+		let policy = policy().jitter(true);
+		let err = Error::Http(StatusCode::SERVICE_UNAVAILABLE);
+		for _ in 0..100 {
+			let delay = policy.delay(3, &err);
+			assert!(
+				delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2),
+				"{delay:?}"
+			);
+		}
+	}
+
+	#[test]
+	fn retry_after_takes_precedence_but_is_capped() {
+		let policy = policy();
+		let err = |secs| Error::HttpRetryAfter {
+			status: StatusCode::TOO_MANY_REQUESTS,
+			retry_after: Duration::from_secs(secs),
+		};
+		assert_eq!(policy.delay(1, &err(2)), Duration::from_secs(2));
+		assert_eq!(policy.delay(1, &err(60)), Duration::from_secs(3));
+	}
+
+	#[test]
+	fn parses_retry_after_seconds() {
+		let now = OffsetDateTime::now_utc();
+		assert_eq!(parse_retry_after(" 90 ", now), Some(Duration::from_secs(90)));
+		assert_eq!(parse_retry_after("soon", now), None);
+	}
+
+	#[test]
+	fn parses_retry_after_dates() {
+		// Wed, 21 Oct 2015 07:27:30 GMT
+		let now = OffsetDateTime::from_unix_timestamp(1_445_412_450).expect("valid timestamp");
+		assert_eq!(
+			parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+			Some(Duration::from_secs(30))
+		);
+		assert_eq!(
+			parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+			Some(Duration::ZERO)
+		);
+	}
+}
@@ -0,0 +1,50 @@
+use resolute::{
+	resolver::{Incompatibility, ModConflictVec},
+	Error,
+};
+use serde::Serialize;
+
+/// Error returned by commands that change mods, structured so the frontend can explain failures in detail.
+/// Every kind of error carries a readable message to fall back on.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(crate) enum CommandError {
+	/// Dependency resolution failed
+	Unresolvable {
+		message: String,
+		/// Derivation tree explaining why resolution failed
+		explanation: Box<Incompatibility>,
+	},
+
+	/// Changes would introduce conflicts between mods
+	Conflicts {
+		message: String,
+		conflicts: Box<ModConflictVec>,
+	},
+
+	/// Any other failure
+	Other { message: String },
+}
+
+impl CommandError {
+	/// Builds a command error from an error returned by the manager, prefixing its message with some context
+	pub(crate) fn from_manager(context: &str, err: Error) -> Self {
+		let message = format!("{context}: {err}");
+		match err {
+			Error::UnresolvableDependencies { cause, .. }
+			| Error::UnresolvableProfile { cause, .. }
+			| Error::UnresolvableUpdate(cause) => Self::Unresolvable {
+				message,
+				explanation: cause,
+			},
+			Error::Conflicts(conflicts) => Self::Conflicts { message, conflicts },
+			_ => Self::Other { message },
+		}
+	}
+}
+
+impl From<String> for CommandError {
+	fn from(message: String) -> Self {
+		Self::Other { message }
+	}
+}
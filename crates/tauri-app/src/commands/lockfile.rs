@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use log::{error, info};
+use resolute::{
+	lockfile::Lockfile,
+	manager::{InstallOptions, LoadedMods, ModManager},
+	resolver::{InstallPlan, UninstallPlan},
+};
+use tauri::{AppHandle, State};
+use tokio::{fs, sync::Mutex};
+
+use super::{error::CommandError, operations::Operations};
+use crate::{build_manifest_config, settings};
+
+/// Exports the installed mods to a lockfile at the given path
+#[tauri::command]
+pub(crate) async fn export_lockfile(manager: State<'_, Mutex<ModManager<'_>>>, path: PathBuf) -> Result<(), String> {
+	let manager = manager.lock().await;
+
+	let LoadedMods { mods, .. } = manager
+		.get_installed_mods()
+		.await
+		.map_err(|err| format!("Unable to get installed mods from manager: {err}"))?;
+	let json = manager
+		.export_lockfile(&mods)
+		.await
+		.and_then(|lockfile| lockfile.to_json())
+		.map_err(|err| {
+			error!("Failed to build lockfile: {err}");
+			format!("Unable to build lockfile: {err}")
+		})?;
+
+	fs::write(&path, json).await.map_err(|err| {
+		error!("Failed to write lockfile to {}: {err}", path.display());
+		format!("Unable to write lockfile: {err}")
+	})?;
+
+	info!("Exported lockfile to {}", path.display());
+	Ok(())
+}
+
+/// Restores the installation recorded in a lockfile at the given path
+#[tauri::command]
+pub(crate) async fn restore_lockfile(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	operations: State<'_, Operations>,
+	path: PathBuf,
+	prune: Option<bool>,
+	allow_unsupported_platform: Option<bool>,
+	confirm_flagged: Option<bool>,
+	operation_id: Option<String>,
+) -> Result<(InstallPlan, UninstallPlan), CommandError> {
+	let operation = operations.start(operation_id)?;
+	let mut manager = manager.lock().await;
+	operation.check_canceled()?;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	// Read the lockfile
+	let json = fs::read_to_string(&path).await.map_err(|err| {
+		error!("Failed to read lockfile from {}: {err}", path.display());
+		format!("Unable to read lockfile: {err}")
+	})?;
+	let lockfile = Lockfile::parse(&json).map_err(|err| {
+		error!("Failed to parse lockfile from {}: {err}", path.display());
+		format!("Unable to parse lockfile: {err}")
+	})?;
+
+	// Load the mods to fill in details for the locked mods
+	let LoadedMods { mods, .. } = manager
+		.get_all_mods(build_manifest_config(&app)?, false)
+		.await
+		.map_err(|err| format!("Unable to get all mods from manager: {err}"))?;
+
+	info!("Restoring lockfile from {}", path.display());
+	let options = InstallOptions::new()
+		.allow_unsupported_platform(allow_unsupported_platform.unwrap_or(false))
+		.allow_flagged(confirm_flagged.unwrap_or(false));
+	let (install, uninstall) = manager
+		.restore_lockfile(
+			&mods,
+			&lockfile,
+			prune.unwrap_or(false),
+			&options,
+			|_| {},
+			&operation.token,
+		)
+		.await
+		.map_err(|err| {
+			error!("Failed to restore lockfile from {}: {err}", path.display());
+			CommandError::from_manager("Unable to restore lockfile", err)
+		})?;
+
+	info!(
+		"Successfully restored lockfile from {} (installed: {install}, uninstalled: {uninstall})",
+		path.display()
+	);
+	Ok((install, uninstall))
+}
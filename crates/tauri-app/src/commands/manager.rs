@@ -1,11 +1,13 @@
-use log::{error, info};
+use log::{error, info, warn};
 use resolute::{
-	manager::{LoadedMods, ModManager},
-	models::{ModVersion, ResoluteMod},
+	manager::{InstallOptions, IntegrityReport, LoadedMods, ModManager, Progress, RecoveryReport},
+	models::{self, ModVersion, ResoluteMod},
+	resolver::{InstallPlan, UninstallPlan},
 };
-use tauri::{AppHandle, State};
+use tauri::{ipc::Channel, AppHandle, State};
 use tokio::sync::Mutex;
 
+use super::{error::CommandError, operations::Operations};
 use crate::{build_manifest_config, settings};
 
 /// Loads all mods from the manager
@@ -41,27 +43,53 @@ pub(crate) async fn load_installed_mods(manager: State<'_, Mutex<ModManager<'_>>
 pub(crate) async fn install_mod_version(
 	app: AppHandle,
 	manager: State<'_, Mutex<ModManager<'_>>>,
+	operations: State<'_, Operations>,
 	rmod: ResoluteMod,
 	version: ModVersion,
-) -> Result<(), String> {
+	allow_unsupported_platform: Option<bool>,
+	confirm_flagged: Option<bool>,
+	operation_id: Option<String>,
+	on_progress: Channel<Progress>,
+) -> Result<InstallPlan, CommandError> {
+	let operation = operations.start(operation_id)?;
 	let mut manager = manager.lock().await;
+	operation.check_canceled()?;
 
 	// Update the Resonite path in case the setting has changed
 	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
 	manager.set_base_dest(resonite_path);
 
-	// Download the version
+	// Load the mods to resolve dependencies against
+	let LoadedMods { mods, .. } = manager
+		.get_all_mods(build_manifest_config(&app)?, false)
+		.await
+		.map_err(|err| format!("Unable to get all mods from manager: {err}"))?;
+
+	// Download the version and its dependencies
 	info!("Installing mod {} v{}", rmod.name, version.semver);
-	manager
-		.install_mod(&rmod, version.semver.to_string(), |_, _| {})
+	let options = InstallOptions::new()
+		.allow_unsupported_platform(allow_unsupported_platform.unwrap_or(false))
+		.allow_flagged(confirm_flagged.unwrap_or(false));
+	let plan = manager
+		.install_mod(
+			&mods,
+			&rmod,
+			version.semver.to_string(),
+			&options,
+			forward_progress(&on_progress),
+			&operation.token,
+		)
 		.await
 		.map_err(|err| {
 			error!("Failed to download mod {} v{}: {err}", rmod.name, version.semver);
-			format!("Unable to download mod version: {err}")
+			CommandError::from_manager("Unable to download mod version", err)
 		})?;
 
-	info!("Successfully installed mod {} v{}", rmod.name, version.semver);
-	Ok(())
+	info!(
+		"Successfully installed mod {} v{} (plan: {plan})",
+		rmod.name, version.semver
+	);
+	Ok(plan)
 }
 
 /// Updates a mod to a new version
@@ -69,10 +97,17 @@ pub(crate) async fn install_mod_version(
 pub(crate) async fn replace_mod_version(
 	app: AppHandle,
 	manager: State<'_, Mutex<ModManager<'_>>>,
+	operations: State<'_, Operations>,
 	rmod: ResoluteMod,
 	version: ModVersion,
-) -> Result<(), String> {
+	allow_unsupported_platform: Option<bool>,
+	confirm_flagged: Option<bool>,
+	operation_id: Option<String>,
+	on_progress: Channel<Progress>,
+) -> Result<InstallPlan, CommandError> {
+	let operation = operations.start(operation_id)?;
 	let mut manager = manager.lock().await;
+	operation.check_canceled()?;
 
 	// Update the Resonite path in case the setting has changed
 	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
@@ -80,40 +115,59 @@ pub(crate) async fn replace_mod_version(
 
 	// Ensure the mod is installed
 	let Some(old_version) = &rmod.installed_version else {
-		return Err(format!(
-			"Mod {} doesn't have an installed version to replace",
-			rmod.name
-		));
+		return Err(format!("Mod {} doesn't have an installed version to replace", rmod.name).into());
 	};
 
+	// Load the mods to resolve dependencies against
+	let LoadedMods { mods, .. } = manager
+		.get_all_mods(build_manifest_config(&app)?, false)
+		.await
+		.map_err(|err| format!("Unable to get all mods from manager: {err}"))?;
+
 	// Update the mod to the given version
 	info!("Replacing mod {} v{} with v{}", rmod.name, old_version, version.semver);
-	manager
-		.update_mod(&rmod, version.semver.to_string(), |_, _| {})
+	let options = InstallOptions::new()
+		.allow_unsupported_platform(allow_unsupported_platform.unwrap_or(false))
+		.allow_flagged(confirm_flagged.unwrap_or(false));
+	let plan = manager
+		.update_mod(
+			&mods,
+			&rmod,
+			version.semver.to_string(),
+			&options,
+			forward_progress(&on_progress),
+			&operation.token,
+		)
 		.await
 		.map_err(|err| {
 			error!(
 				"Failed to replace mod {} v{} with v{}: {err}",
 				rmod.name, old_version, version.semver
 			);
-			format!("Unable to replace mod version: {err}")
+			CommandError::from_manager("Unable to replace mod version", err)
 		})?;
 
 	info!(
-		"Successfully replaced mod {} v{} with v{}",
+		"Successfully replaced mod {} v{} with v{} (plan: {plan})",
 		rmod.name, old_version, version.semver
 	);
-	Ok(())
+	Ok(plan)
 }
 
-/// Uninstalls a mod
+/// Uninstalls a mod, and optionally any installed mods that depend on it
 #[tauri::command]
 pub(crate) async fn uninstall_mod(
 	app: AppHandle,
 	manager: State<'_, Mutex<ModManager<'_>>>,
+	operations: State<'_, Operations>,
 	rmod: ResoluteMod,
-) -> Result<(), String> {
+	cascade: Option<bool>,
+	operation_id: Option<String>,
+	on_progress: Channel<Progress>,
+) -> Result<UninstallPlan, CommandError> {
+	let operation = operations.start(operation_id)?;
 	let mut manager = manager.lock().await;
+	operation.check_canceled()?;
 
 	// Update the Resonite path in case the setting has changed
 	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
@@ -121,19 +175,336 @@ pub(crate) async fn uninstall_mod(
 
 	// Ensure the mod is installed
 	let Some(old_version) = &rmod.installed_version else {
-		return Err(format!(
-			"Mod {} doesn't have an installed version to uninstall",
-			rmod.name
-		));
+		return Err(format!("Mod {} doesn't have an installed version to uninstall", rmod.name).into());
 	};
 
+	// Load the installed mods to check for dependents against
+	let LoadedMods { mods, .. } = manager
+		.get_installed_mods()
+		.await
+		.map_err(|err| format!("Unable to get installed mods from manager: {err}"))?;
+
 	// Uninstall the mod
 	info!("Uninstalling mod {} v{}", rmod.name, old_version);
-	manager.uninstall_mod(&rmod).await.map_err(|err| {
-		error!("Failed to uninstall mod {} v{}: {err}", rmod.name, old_version);
-		format!("Unable to uninstall mod: {err}")
+	let plan = manager
+		.uninstall_mod(
+			&mods,
+			&rmod,
+			cascade.unwrap_or(false),
+			forward_progress(&on_progress),
+			&operation.token,
+		)
+		.await
+		.map_err(|err| {
+			error!("Failed to uninstall mod {} v{}: {err}", rmod.name, old_version);
+			CommandError::from_manager("Unable to uninstall mod", err)
+		})?;
+
+	info!(
+		"Successfully uninstalled mod {} v{} (plan: {plan})",
+		rmod.name, old_version
+	);
+	Ok(plan)
+}
+
+/// Uninstalls any mods that were installed as dependencies and are no longer needed
+#[tauri::command]
+pub(crate) async fn remove_orphaned_mods(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	operations: State<'_, Operations>,
+	operation_id: Option<String>,
+	on_progress: Channel<Progress>,
+) -> Result<UninstallPlan, CommandError> {
+	let operation = operations.start(operation_id)?;
+	let mut manager = manager.lock().await;
+	operation.check_canceled()?;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	// Load the installed mods to find orphans within
+	let LoadedMods { mods, .. } = manager
+		.get_installed_mods()
+		.await
+		.map_err(|err| format!("Unable to get installed mods from manager: {err}"))?;
+
+	// Remove the orphans
+	info!("Removing orphaned mods");
+	let plan = manager
+		.remove_orphans(&mods, forward_progress(&on_progress), &operation.token)
+		.await
+		.map_err(|err| {
+			error!("Failed to remove orphaned mods: {err}");
+			CommandError::from_manager("Unable to remove orphaned mods", err)
+		})?;
+
+	info!("Successfully removed orphaned mods (plan: {plan})");
+	Ok(plan)
+}
+
+/// Plans an update of all installed mods to their newest mutually compatible versions without changing anything
+#[tauri::command]
+pub(crate) async fn preview_update_all_mods(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+) -> Result<InstallPlan, CommandError> {
+	let manager = manager.lock().await;
+
+	// Load the mods to resolve updates against
+	let LoadedMods { mods, .. } = manager
+		.get_all_mods(build_manifest_config(&app)?, false)
+		.await
+		.map_err(|err| format!("Unable to get all mods from manager: {err}"))?;
+
+	let plan = manager.plan_update_all(&mods).await.map_err(|err| {
+		error!("Failed to plan update of all mods: {err}");
+		CommandError::from_manager("Unable to plan update of all mods", err)
+	})?;
+
+	info!("Planned update of all mods: {plan}");
+	Ok(plan)
+}
+
+/// Updates all installed mods to their newest mutually compatible versions.
+/// If a previewed plan is given, it's carried out as-is rather than planning the update again.
+#[tauri::command]
+pub(crate) async fn update_all_mods(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	operations: State<'_, Operations>,
+	plan: Option<InstallPlan>,
+	allow_unsupported_platform: Option<bool>,
+	confirm_flagged: Option<bool>,
+	operation_id: Option<String>,
+	on_progress: Channel<Progress>,
+) -> Result<InstallPlan, CommandError> {
+	let operation = operations.start(operation_id)?;
+	let mut manager = manager.lock().await;
+	operation.check_canceled()?;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	// Load the mods to resolve updates against
+	let LoadedMods { mods, .. } = manager
+		.get_all_mods(build_manifest_config(&app)?, false)
+		.await
+		.map_err(|err| format!("Unable to get all mods from manager: {err}"))?;
+
+	// Update everything
+	info!("Updating all mods");
+	let options = InstallOptions::new()
+		.allow_unsupported_platform(allow_unsupported_platform.unwrap_or(false))
+		.allow_flagged(confirm_flagged.unwrap_or(false));
+	let result = match plan {
+		Some(plan) => match manager.check_plan(&mods, None, &plan, &options) {
+			Ok(()) => manager
+				.apply_plan(&mods, None, &plan, forward_progress(&on_progress), &operation.token)
+				.await
+				.map(|()| plan),
+			Err(err) => Err(err),
+		},
+		None => {
+			manager
+				.update_all_mods(&mods, &options, forward_progress(&on_progress), &operation.token)
+				.await
+		}
+	};
+	let plan = result.map_err(|err| {
+		error!("Failed to update all mods: {err}");
+		CommandError::from_manager("Unable to update all mods", err)
+	})?;
+
+	info!("Successfully updated all mods (plan: {plan})");
+	Ok(plan)
+}
+
+/// Pins a mod to an exact version or version requirement
+#[tauri::command]
+pub(crate) async fn pin_mod(
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	rmod: ResoluteMod,
+	pin: String,
+) -> Result<(), String> {
+	let pin = models::parse_pin(&pin).map_err(|err| format!("Unable to parse version pin \"{pin}\": {err}"))?;
+
+	info!("Pinning mod {} to {}", rmod.name, pin);
+	manager.lock().await.pin_mod(&rmod, pin).await.map_err(|err| {
+		error!("Failed to pin mod {}: {err}", rmod.name);
+		format!("Unable to pin mod: {err}")
+	})?;
+
+	Ok(())
+}
+
+/// Removes the version pin from a mod
+#[tauri::command]
+pub(crate) async fn unpin_mod(manager: State<'_, Mutex<ModManager<'_>>>, rmod: ResoluteMod) -> Result<(), String> {
+	info!("Unpinning mod {}", rmod.name);
+	manager.lock().await.unpin_mod(&rmod).await.map_err(|err| {
+		error!("Failed to unpin mod {}: {err}", rmod.name);
+		format!("Unable to unpin mod: {err}")
 	})?;
 
-	info!("Successfully uninstalled mod {} v{}", rmod.name, old_version);
 	Ok(())
 }
+
+/// Disables an installed mod without uninstalling it
+#[tauri::command]
+pub(crate) async fn disable_mod(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	rmod: ResoluteMod,
+) -> Result<(), String> {
+	let mut manager = manager.lock().await;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	info!("Disabling mod {}", rmod.name);
+	manager.disable_mod(&rmod).await.map_err(|err| {
+		error!("Failed to disable mod {}: {err}", rmod.name);
+		format!("Unable to disable mod: {err}")
+	})?;
+
+	info!("Successfully disabled mod {}", rmod.name);
+	Ok(())
+}
+
+/// Enables a disabled mod
+#[tauri::command]
+pub(crate) async fn enable_mod(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	rmod: ResoluteMod,
+) -> Result<(), String> {
+	let mut manager = manager.lock().await;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	info!("Enabling mod {}", rmod.name);
+	manager.enable_mod(&rmod).await.map_err(|err| {
+		error!("Failed to enable mod {}: {err}", rmod.name);
+		format!("Unable to enable mod: {err}")
+	})?;
+
+	info!("Successfully enabled mod {}", rmod.name);
+	Ok(())
+}
+
+/// Recovers from operations that were interrupted partway through changing mod files
+#[tauri::command]
+pub(crate) async fn recover_interrupted_operations(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+) -> Result<RecoveryReport, String> {
+	let mut manager = manager.lock().await;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	recover_interrupted(&app, &manager).await
+}
+
+/// Recovers from operations that were interrupted partway through changing mod files, checking leftover files against
+/// all mods if they can be loaded, or just the installed ones otherwise
+pub(crate) async fn recover_interrupted(app: &AppHandle, manager: &ModManager<'_>) -> Result<RecoveryReport, String> {
+	let mods = match manager.get_all_mods(build_manifest_config(app)?, false).await {
+		Ok(LoadedMods { mods, .. }) => mods,
+		Err(err) => {
+			warn!("Unable to get all mods from manager for recovery, using installed mods only: {err}");
+			let LoadedMods { mods, .. } = manager
+				.get_installed_mods()
+				.await
+				.map_err(|err| format!("Unable to get installed mods from manager: {err}"))?;
+			mods
+		}
+	};
+
+	manager.recover_interrupted(&mods).await.map_err(|err| {
+		error!("Failed to recover from interrupted operations: {err}");
+		format!("Unable to recover from interrupted operations: {err}")
+	})
+}
+
+/// Verifies the files of all installed mods against the checksums of their artifacts
+#[tauri::command]
+pub(crate) async fn verify_installed_mods(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+) -> Result<IntegrityReport, String> {
+	let mut manager = manager.lock().await;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	let LoadedMods { mods, .. } = manager
+		.get_installed_mods()
+		.await
+		.map_err(|err| format!("Unable to get installed mods from manager: {err}"))?;
+
+	info!("Verifying installed mods");
+	manager.verify_installed(&mods).await.map_err(|err| {
+		error!("Failed to verify installed mods: {err}");
+		format!("Unable to verify installed mods: {err}")
+	})
+}
+
+/// Repairs the files of all installed mods by downloading any missing or modified artifacts again, and optionally
+/// discarding copies of artifacts at other paths
+#[tauri::command]
+pub(crate) async fn repair_installed_mods(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	operations: State<'_, Operations>,
+	remove_duplicates: Option<bool>,
+	operation_id: Option<String>,
+	on_progress: Channel<Progress>,
+) -> Result<IntegrityReport, String> {
+	let operation = operations.start(operation_id)?;
+	let mut manager = manager.lock().await;
+	operation.check_canceled()?;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	let LoadedMods { mods, .. } = manager
+		.get_installed_mods()
+		.await
+		.map_err(|err| format!("Unable to get installed mods from manager: {err}"))?;
+
+	info!("Repairing installed mods");
+	let report = manager
+		.repair(
+			&mods,
+			remove_duplicates.unwrap_or(false),
+			forward_progress(&on_progress),
+			&operation.token,
+		)
+		.await
+		.map_err(|err| {
+			error!("Failed to repair installed mods: {err}");
+			format!("Unable to repair installed mods: {err}")
+		})?;
+
+	info!("Successfully repaired installed mods");
+	Ok(report)
+}
+
+/// Builds a progress callback that forwards each progress update to the frontend over a channel
+fn forward_progress(channel: &Channel<Progress>) -> impl Fn(Progress) + '_ {
+	move |progress| {
+		if let Err(err) = channel.send(progress) {
+			warn!("Unable to send progress update to the frontend: {err}");
+		}
+	}
+}
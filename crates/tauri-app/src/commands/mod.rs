@@ -1,6 +1,11 @@
 #![allow(clippy::needless_pass_by_value, clippy::used_underscore_binding)]
 
 pub(crate) mod discover;
+pub(crate) mod error;
+pub(crate) mod lockfile;
 pub(crate) mod manager;
+pub(crate) mod operations;
+pub(crate) mod profiles;
 pub(crate) mod settings;
+pub(crate) mod snapshots;
 pub(crate) mod system;
@@ -0,0 +1,94 @@
+use std::{
+	collections::{hash_map::Entry, HashMap},
+	sync::{Mutex, PoisonError},
+};
+
+use log::{info, warn};
+use resolute::{manager::CancellationToken, Error};
+use tauri::State;
+
+/// Cancellation tokens for the running operations that the frontend has given IDs to
+#[derive(Debug, Default)]
+pub(crate) struct Operations(Mutex<HashMap<String, CancellationToken>>);
+
+impl Operations {
+	/// Starts tracking an operation. If it has an ID, it can be canceled with [`cancel_operation`] until the returned
+	/// guard is dropped. Fails if another running operation already has the ID.
+	pub(crate) fn start(&self, id: Option<String>) -> Result<OperationGuard<'_>, String> {
+		let token = CancellationToken::new();
+		if let Some(id) = &id {
+			let mut tokens = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+			match tokens.entry(id.clone()) {
+				Entry::Vacant(entry) => {
+					entry.insert(token.clone());
+				}
+				Entry::Occupied(..) => {
+					warn!("Unable to start operation {id}, as another operation with the ID is running");
+					return Err(format!("Operation ID {id} is already in use"));
+				}
+			}
+		}
+
+		Ok(OperationGuard {
+			operations: self,
+			id,
+			token,
+		})
+	}
+
+	/// Cancels an operation by its ID. Returns whether there was a running operation with the ID.
+	fn cancel(&self, id: &str) -> bool {
+		let tokens = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+		match tokens.get(id) {
+			Some(token) => {
+				token.cancel();
+				true
+			}
+			None => false,
+		}
+	}
+}
+
+/// A running operation that stops being tracked when dropped
+#[derive(Debug)]
+pub(crate) struct OperationGuard<'a> {
+	operations: &'a Operations,
+	id: Option<String>,
+	pub(crate) token: CancellationToken,
+}
+
+impl OperationGuard<'_> {
+	/// Fails if the operation has been canceled, such as while it was waiting for another operation to finish
+	pub(crate) fn check_canceled(&self) -> Result<(), String> {
+		if self.token.is_cancelled() {
+			info!(
+				"Operation {} was canceled before it started",
+				self.id.as_deref().unwrap_or("(no ID)")
+			);
+			return Err(Error::Cancelled.to_string());
+		}
+		Ok(())
+	}
+}
+
+impl Drop for OperationGuard<'_> {
+	fn drop(&mut self) {
+		if let Some(id) = &self.id {
+			let mut tokens = self.operations.0.lock().unwrap_or_else(PoisonError::into_inner);
+			tokens.remove(id);
+		}
+	}
+}
+
+/// Cancels a running operation by the ID it was started with.
+/// Returns whether there was a running operation with the ID.
+#[tauri::command]
+pub(crate) fn cancel_operation(operations: State<'_, Operations>, id: String) -> bool {
+	let canceled = operations.cancel(&id);
+	if canceled {
+		info!("Canceling operation {id}");
+	} else {
+		warn!("Unable to cancel operation {id}, as it isn't running");
+	}
+	canceled
+}
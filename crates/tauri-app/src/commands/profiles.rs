@@ -0,0 +1,142 @@
+use log::{error, info};
+use resolute::{
+	manager::{InstallOptions, LoadedMods, ModManager},
+	models::ModProfile,
+	resolver::ProfilePlan,
+};
+use tauri::{AppHandle, State};
+use tokio::sync::Mutex;
+
+use super::{error::CommandError, operations::Operations};
+use crate::{build_manifest_config, settings};
+
+/// Loads all mod profiles from the manager
+#[tauri::command]
+pub(crate) async fn load_profiles(manager: State<'_, Mutex<ModManager<'_>>>) -> Result<Vec<ModProfile>, String> {
+	let profiles = manager.lock().await.get_profiles().await.map_err(|err| {
+		error!("Unable to get profiles from manager: {err}");
+		format!("Unable to get profiles from manager: {err}")
+	})?;
+	Ok(profiles)
+}
+
+/// Saves a mod profile, replacing any existing profile with the same name
+#[tauri::command]
+pub(crate) async fn save_profile(manager: State<'_, Mutex<ModManager<'_>>>, profile: ModProfile) -> Result<(), String> {
+	info!("Saving profile {profile}");
+	manager.lock().await.store_profile(profile).await.map_err(|err| {
+		error!("Failed to save profile: {err}");
+		format!("Unable to save profile: {err}")
+	})?;
+
+	Ok(())
+}
+
+/// Saves the currently active mods (at their installed versions) as a profile
+#[tauri::command]
+pub(crate) async fn save_current_profile(
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	name: String,
+) -> Result<ModProfile, String> {
+	let manager = manager.lock().await;
+
+	let LoadedMods { mods, .. } = manager
+		.get_installed_mods()
+		.await
+		.map_err(|err| format!("Unable to get installed mods from manager: {err}"))?;
+	let profile = ModProfile::from_installed(name, &mods);
+
+	info!("Saving current mods as profile {profile}");
+	manager.store_profile(profile.clone()).await.map_err(|err| {
+		error!("Failed to save profile {}: {err}", profile.name);
+		format!("Unable to save profile: {err}")
+	})?;
+
+	Ok(profile)
+}
+
+/// Deletes a mod profile
+#[tauri::command]
+pub(crate) async fn delete_profile(manager: State<'_, Mutex<ModManager<'_>>>, name: String) -> Result<(), String> {
+	info!("Deleting profile {name}");
+	manager.lock().await.remove_profile(&name).await.map_err(|err| {
+		error!("Failed to delete profile {name}: {err}");
+		format!("Unable to delete profile: {err}")
+	})?;
+
+	Ok(())
+}
+
+/// Plans switching to a mod profile without changing anything
+#[tauri::command]
+pub(crate) async fn preview_profile(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	name: String,
+) -> Result<ProfilePlan, CommandError> {
+	let manager = manager.lock().await;
+
+	// Load the profile and the mods to resolve it against
+	let profile = manager
+		.get_profile(&name)
+		.await
+		.map_err(|err| format!("Unable to get profile {name}: {err}"))?;
+	let LoadedMods { mods, .. } = manager
+		.get_all_mods(build_manifest_config(&app)?, false)
+		.await
+		.map_err(|err| format!("Unable to get all mods from manager: {err}"))?;
+
+	let plan = manager.plan_profile(&mods, &profile).await.map_err(|err| {
+		error!("Failed to plan switch to profile {name}: {err}");
+		CommandError::from_manager("Unable to plan switch to profile", err)
+	})?;
+
+	info!("Planned switch to profile {name}: {plan}");
+	Ok(plan)
+}
+
+/// Switches to a mod profile
+#[tauri::command]
+pub(crate) async fn apply_profile(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	operations: State<'_, Operations>,
+	name: String,
+	allow_unsupported_platform: Option<bool>,
+	confirm_flagged: Option<bool>,
+	operation_id: Option<String>,
+) -> Result<ProfilePlan, CommandError> {
+	let operation = operations.start(operation_id)?;
+	let mut manager = manager.lock().await;
+	operation.check_canceled()?;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	// Load the profile and the mods to resolve it against
+	let profile = manager
+		.get_profile(&name)
+		.await
+		.map_err(|err| format!("Unable to get profile {name}: {err}"))?;
+	let LoadedMods { mods, .. } = manager
+		.get_all_mods(build_manifest_config(&app)?, false)
+		.await
+		.map_err(|err| format!("Unable to get all mods from manager: {err}"))?;
+
+	// Switch to the profile
+	info!("Applying profile {profile}");
+	let options = InstallOptions::new()
+		.allow_unsupported_platform(allow_unsupported_platform.unwrap_or(false))
+		.allow_flagged(confirm_flagged.unwrap_or(false));
+	let plan = manager
+		.apply_profile(&mods, &profile, &options, |_| {}, &operation.token)
+		.await
+		.map_err(|err| {
+			error!("Failed to apply profile {name}: {err}");
+			CommandError::from_manager("Unable to apply profile", err)
+		})?;
+
+	info!("Successfully applied profile {name} (plan: {plan})");
+	Ok(plan)
+}
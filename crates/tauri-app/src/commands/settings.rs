@@ -3,7 +3,7 @@ use resolute::manager::ModManager;
 use tauri::{AppHandle, State};
 use tokio::sync::Mutex;
 
-use crate::{build_http_client, settings};
+use crate::{build_channel_policy, build_http_client, get_resonite_platform, settings};
 
 /// Ensures a change to the Resonite path setting is propagated to the manager
 #[tauri::command]
@@ -28,3 +28,27 @@ pub(crate) async fn connect_timeout_changed(
 	info!("Changed manager's HTTP client for connectTimeout setting change");
 	Ok(())
 }
+
+/// Ensures a change to the release channel settings is propagated to the manager
+#[tauri::command]
+pub(crate) async fn release_channels_changed(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+) -> Result<(), String> {
+	let channels = build_channel_policy(&app).map_err(|err| err.to_string())?;
+	manager.lock().await.set_channel_policy(channels);
+	info!("Changed manager's release channels for allowPrereleases/prereleaseMods setting change");
+	Ok(())
+}
+
+/// Ensures a change to the Resonite platform setting is propagated to the manager
+#[tauri::command]
+pub(crate) async fn resonite_platform_changed(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+) -> Result<(), String> {
+	let platform = get_resonite_platform(&app).map_err(|err| err.to_string())?;
+	manager.lock().await.set_platform(Some(platform));
+	info!("Changed manager's platform to {}", platform);
+	Ok(())
+}
@@ -0,0 +1,56 @@
+use log::{error, info};
+use resolute::{
+	manager::{LoadedMods, ModManager},
+	models::Snapshot,
+};
+use tauri::{AppHandle, State};
+use tokio::sync::Mutex;
+
+use super::operations::Operations;
+use crate::{build_manifest_config, settings};
+
+/// Lists every snapshot of the installed mods, newest first
+#[tauri::command]
+pub(crate) async fn list_snapshots(manager: State<'_, Mutex<ModManager<'_>>>) -> Result<Vec<Snapshot>, String> {
+	let snapshots = manager.lock().await.list_snapshots().await.map_err(|err| {
+		error!("Unable to get snapshots from manager: {err}");
+		format!("Unable to get snapshots from manager: {err}")
+	})?;
+	Ok(snapshots)
+}
+
+/// Rolls the installed mods back to a snapshot
+#[tauri::command]
+pub(crate) async fn rollback_to_snapshot(
+	app: AppHandle,
+	manager: State<'_, Mutex<ModManager<'_>>>,
+	operations: State<'_, Operations>,
+	id: u64,
+	operation_id: Option<String>,
+) -> Result<Snapshot, String> {
+	let operation = operations.start(operation_id)?;
+	let mut manager = manager.lock().await;
+	operation.check_canceled()?;
+
+	// Update the Resonite path in case the setting has changed
+	let resonite_path: String = settings::require(&app, "resonitePath").map_err(|err| err.to_string())?;
+	manager.set_base_dest(resonite_path);
+
+	// Load the mods to look up the snapshot's versions in
+	let LoadedMods { mods, .. } = manager
+		.get_all_mods(build_manifest_config(&app)?, false)
+		.await
+		.map_err(|err| format!("Unable to get all mods from manager: {err}"))?;
+
+	info!("Rolling back to snapshot {id}");
+	let snapshot = manager
+		.rollback_to(&mods, id, |_| {}, &operation.token)
+		.await
+		.map_err(|err| {
+			error!("Failed to roll back to snapshot {id}: {err}");
+			format!("Unable to roll back to snapshot: {err}")
+		})?;
+
+	info!("Successfully rolled back to snapshot {snapshot}");
+	Ok(snapshot)
+}
@@ -3,9 +3,9 @@ use std::path::PathBuf;
 use itertools::Itertools;
 use log::{error, info};
 use path_clean::PathClean;
+use resolute::manager;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
-use tauri::{async_runtime, AppHandle, Manager, Window};
+use tauri::{AppHandle, Manager, Window};
 use tokio::{fs, io::AsyncReadExt};
 
 use crate::settings;
@@ -56,27 +56,10 @@ pub(crate) async fn hash_file(path: String) -> Result<String, String> {
 
 	// Hash the file
 	info!("Hashing file {}", path);
-	let file = path.clone();
-	let digest = async_runtime::spawn_blocking(
-		#[allow(clippy::absolute_paths)]
-		move || {
-			let mut hasher = Sha256::new();
-			let mut file = std::fs::File::open(file).map_err(|err| format!("Error opening file: {err}"))?;
-			std::io::copy(&mut file, &mut hasher).map_err(|err| format!("Error hashing file: {err}"))?;
-			Ok::<_, String>(hasher.finalize())
-		},
-	)
-	.await
-	.map_err(|err| {
-		error!("Error spawning hashing task for file {path}: {err}");
-		format!("Error spawning hashing task: {err}")
-	})?
-	.map_err(|err| {
+	let hash = manager::hash_file(&path).await.map_err(|err| {
 		error!("Error hashing file {path}: {err}");
 		format!("Error hashing file: {err}")
 	})?;
-
-	let hash = format!("{digest:x}");
 	info!("Finished hashing file {path}: {hash}");
 	Ok(hash)
 }
@@ -77,7 +77,13 @@ use std::{env, error::Error, io, thread, time::Duration};
 use anyhow::{bail, Context};
 use clap::Parser;
 use log::{debug, error, info, warn, LevelFilter};
-use resolute::{db::ResoluteDatabase, discover, manager::ModManager, manifest};
+use resolute::{
+	db::ResoluteDatabase,
+	discover,
+	manager::{ArtifactStore, ModManager},
+	manifest,
+	models::{ChannelPolicy, Platform, ReleaseChannel},
+};
 use tauri::{async_runtime, App, AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
 use tauri_plugin_deep_link::DeepLinkExt;
 use tauri_plugin_log::{Target, TargetKind};
@@ -85,6 +91,8 @@ use tauri_plugin_window_state::StateFlags;
 use tokio::{fs, join, sync::Mutex};
 use url::Url;
 
+use crate::commands::operations::Operations;
+
 mod commands;
 mod settings;
 
@@ -151,6 +159,27 @@ fn main() -> anyhow::Result<()> {
 			commands::manager::install_mod_version,
 			commands::manager::replace_mod_version,
 			commands::manager::uninstall_mod,
+			commands::manager::remove_orphaned_mods,
+			commands::manager::preview_update_all_mods,
+			commands::manager::update_all_mods,
+			commands::manager::pin_mod,
+			commands::manager::unpin_mod,
+			commands::manager::disable_mod,
+			commands::manager::enable_mod,
+			commands::manager::recover_interrupted_operations,
+			commands::manager::verify_installed_mods,
+			commands::manager::repair_installed_mods,
+			commands::operations::cancel_operation,
+			commands::profiles::load_profiles,
+			commands::profiles::save_profile,
+			commands::profiles::save_current_profile,
+			commands::profiles::delete_profile,
+			commands::profiles::preview_profile,
+			commands::profiles::apply_profile,
+			commands::lockfile::export_lockfile,
+			commands::lockfile::restore_lockfile,
+			commands::snapshots::list_snapshots,
+			commands::snapshots::rollback_to_snapshot,
 			commands::discover::discover_resonite_path,
 			commands::discover::discover_installed_mods,
 			commands::system::show_window,
@@ -162,7 +191,10 @@ fn main() -> anyhow::Result<()> {
 			commands::system::open_log_dir,
 			commands::settings::resonite_path_changed,
 			commands::settings::connect_timeout_changed,
+			commands::settings::release_channels_changed,
+			commands::settings::resonite_platform_changed,
 		])
+		.manage(Operations::default())
 		.setup(setup(cli.open_url))
 		.run(
 			#[cfg(debug_assertions)]
@@ -252,14 +284,11 @@ async fn init(app: &AppHandle) -> Result<(), anyhow::Error> {
 	});
 
 	let handle = app.clone();
-	async_runtime::spawn_blocking(move || {
+	let (manager, resonite_path) = async_runtime::spawn_blocking(move || {
 		// Open the database
 		let resolver = handle.path();
-		let db_path = resolver
-			.app_data_dir()
-			.context("Unable to get data dir")?
-			.join("resolute.db");
-		let db = ResoluteDatabase::open(db_path).context("Unable to open database")?;
+		let data_dir = resolver.app_data_dir().context("Unable to get data dir")?;
+		let db = ResoluteDatabase::open(data_dir.join("resolute.db")).context("Unable to open database")?;
 
 		// Get the Resonite path setting
 		info!("Retrieving Resonite path from settings store");
@@ -271,14 +300,26 @@ async fn init(app: &AppHandle) -> Result<(), anyhow::Error> {
 		// Set up the shared mod manager
 		info!("Setting up mod manager");
 		let http_client = build_http_client(&handle)?;
-		let manager = ModManager::new(db, resonite_path, http_client);
-		handle.manage(Mutex::new(manager));
+		let mut manager = ModManager::new(db, &resonite_path, http_client);
+		manager.set_channel_policy(build_channel_policy(&handle)?);
+		manager.set_platform(Some(get_resonite_platform(&handle)?));
+		let cache_dir = resolver.app_cache_dir().context("Unable to get cache dir")?;
+		manager.set_artifact_store(Some(ArtifactStore::new(cache_dir.join("artifacts"))));
 
-		Ok::<(), anyhow::Error>(())
+		Ok::<_, anyhow::Error>((manager, resonite_path))
 	})
 	.await
 	.context("Error running blocking task for initialization")??;
 
+	// Recover from any operations that were interrupted the last time the app ran before the manager is made available
+	// to commands, so that nothing else touches the mod files first
+	if !resonite_path.is_empty() {
+		if let Err(err) = commands::manager::recover_interrupted(app, &manager).await {
+			warn!("Unable to recover from interrupted operations: {}", err);
+		}
+	}
+	app.manage(Mutex::new(manager));
+
 	// Register for deep links
 	if app.deep_link().is_registered("resolute").unwrap_or_default() {
 		info!("Already registered for deep links");
@@ -460,6 +501,36 @@ pub(crate) fn build_http_client(app: &AppHandle) -> Result<reqwest::Client, anyh
 		.context("Unable to build HTTP client")
 }
 
+/// Gets the platform that the configured Resonite installation runs on, falling back to the one for the current OS
+pub(crate) fn get_resonite_platform(app: &AppHandle) -> Result<Platform, anyhow::Error> {
+	let platform = settings::get(app, "resonitePlatform")?.unwrap_or_else(Platform::current);
+	debug!("Resonite platform: {}", platform);
+	Ok(platform)
+}
+
+/// Builds a release channel policy that takes the user-configured settings into account
+pub(crate) fn build_channel_policy(app: &AppHandle) -> Result<ChannelPolicy, anyhow::Error> {
+	// Get the global prerelease setting and any mods that should always get prereleases from the settings store
+	let allow_prereleases: bool = settings::get(app, "allowPrereleases")?.unwrap_or_default();
+	let prerelease_mods: Vec<String> = settings::get(app, "prereleaseMods")?.unwrap_or_default();
+	debug!(
+		"Building release channel policy, allowPrereleases = {}, prereleaseMods = {:?}",
+		allow_prereleases, prerelease_mods
+	);
+
+	// Build the policy
+	let default = if allow_prereleases {
+		ReleaseChannel::Prerelease
+	} else {
+		ReleaseChannel::Stable
+	};
+	Ok(prerelease_mods
+		.into_iter()
+		.fold(ChannelPolicy::new(default), |policy, id| {
+			policy.with_mod(id, ReleaseChannel::Prerelease)
+		}))
+}
+
 /// Allows requesting navigation in the UI
 trait Navigate {
 	/// Opens a resolute:// URL in the UI
@@ -7,6 +7,7 @@ let store;
 let storeUnlisten;
 const currentSettings = reactive({
 	resonitePath: null,
+	resonitePlatform: null,
 	manifestUrl: null,
 	connectTimeout: 10,
 	theme: null,
@@ -15,6 +16,8 @@ const currentSettings = reactive({
 	modsPerPageGrouped: -1,
 	modsPerPageUngrouped: 25,
 	showDeprecated: false,
+	allowPrereleases: false,
+	prereleaseMods: [],
 	modAuthorTools: false,
 	console: false,
 	setupGuideDone: false,
@@ -56,7 +59,10 @@ export function useSettings() {
 		if (persistNow) await persist();
 
 		if (setting === 'resonitePath') invoke('resonite_path_changed');
+		else if (setting === 'resonitePlatform') invoke('resonite_platform_changed');
 		else if (setting === 'connectTimeout') invoke('connect_timeout_changed');
+		else if (setting === 'allowPrereleases' || setting === 'prereleaseMods')
+			invoke('release_channels_changed');
 	}
 
 	/**
@@ -1,12 +1,14 @@
 import { ref, reactive } from 'vue';
 import { defineStore } from 'pinia';
 import { lt as semverLt } from 'semver';
-import { invoke } from '@tauri-apps/api/core';
+import { invoke, Channel } from '@tauri-apps/api/core';
+import { ask } from '@tauri-apps/plugin-dialog';
 import { info, error } from '@tauri-apps/plugin-log';
 
 import useNotifications from '../composables/notifications';
 // eslint-disable-next-line no-unused-vars
 import { ResoluteMod, ModVersion } from '../structs/mod';
+import { CommandError } from '../structs/error';
 
 export const useModStore = defineStore('mods', () => {
 	const mods = ref(null);
@@ -16,6 +18,8 @@ export const useModStore = defineStore('mods', () => {
 	const hasLoaded = ref(false);
 	const hasLoadedInstalled = ref(false);
 	const operations = reactive({});
+	const progress = reactive({});
+	const errors = reactive({});
 	const notify = useNotifications();
 
 	/**
@@ -32,11 +36,19 @@ export const useModStore = defineStore('mods', () => {
 		try {
 			// Load the mods from the backend
 			await info(`Requesting mod load, bypassCache = ${bypassCache}`);
-			const { mods: newMods, removed } = await invoke('load_all_mods', {
+			const {
+				mods: newMods,
+				removed,
+				unsupported,
+				newlyFlagged,
+			} = await invoke('load_all_mods', {
 				bypassCache,
 			});
 			for (const id of Object.keys(newMods)) {
-				newMods[id] = new ResoluteMod(newMods[id]);
+				newMods[id] = new ResoluteMod({
+					...newMods[id],
+					supported: !unsupported.includes(id),
+				});
 			}
 
 			// Ditch any mods that were specifically removed
@@ -46,6 +58,15 @@ export const useModStore = defineStore('mods', () => {
 				}
 			}
 
+			// Alert the user to any installed mods that have been deprecated or found to be vulnerable
+			if (newlyFlagged.length > 0) {
+				const names = newlyFlagged.map((id) => newMods[id]?.name ?? id);
+				notify.error(
+					'Installed mods flagged',
+					`The following installed mods have been deprecated or have known vulnerabilities:\n${names.join('\n')}`,
+				);
+			}
+
 			// Mark the mods as loaded
 			hasLoaded.value = true;
 			console.debug('Mods loaded', newMods);
@@ -81,9 +102,16 @@ export const useModStore = defineStore('mods', () => {
 		try {
 			// Load the installed mods from the backend
 			await info('Requesting installed mod load');
-			const { mods: newMods, removed } = await invoke('load_installed_mods');
+			const {
+				mods: newMods,
+				removed,
+				unsupported,
+			} = await invoke('load_installed_mods');
 			for (const id of Object.keys(newMods)) {
-				newMods[id] = new ResoluteMod(newMods[id]);
+				newMods[id] = new ResoluteMod({
+					...newMods[id],
+					supported: !unsupported.includes(id),
+				});
 			}
 
 			// Ditch any mods that were specifically removed
@@ -125,15 +153,32 @@ export const useModStore = defineStore('mods', () => {
 		if (!version) version = mod.latestVersion;
 		else if (typeof version === 'string') version = mod.versions[version];
 
+		// Make sure the user really wants to install a flagged mod
+		const confirmFlagged = mod.isDeprecated || mod.isVulnerable;
+		if (confirmFlagged) {
+			const problem = mod.isVulnerable
+				? 'has a known security vulnerability'
+				: 'has been deprecated';
+			const answer = await ask(
+				`${mod.name} ${problem}. Are you sure you want to install it?`,
+				{ title: 'Install flagged mod', type: 'warning' },
+			);
+			if (!answer) return;
+		}
+
 		try {
 			// Add an operation for the mod being installed and request the installation from the backend
 			operations[mod.id] = 'install';
+			errors[mod.id] = null;
 			await info(
 				`Requesting installation of mod ${mod.name} v${version.semver}`,
 			);
 			await invoke('install_mod_version', {
 				rmod: mod,
 				version,
+				confirmFlagged,
+				operationId: mod.id,
+				onProgress: progressChannel(mod),
 			});
 
 			// Update the mod's installed version and notify the user of the success
@@ -143,15 +188,20 @@ export const useModStore = defineStore('mods', () => {
 				`${mod.name} v${version.semver} was successfully installed.`,
 			);
 		} catch (err) {
+			// Keep the failure around to explain it in detail
+			const failure = CommandError.from(err);
+			errors[mod.id] = failure;
+
 			// Notify the user of the failure
 			notify.error(
 				'Error installing mod',
-				`Error installing ${mod.name} v${version.semver}:\n${err}`,
+				`Error installing ${mod.name} v${version.semver}:\n${failure}`,
 			);
-			throw err;
+			throw failure;
 		} finally {
 			// Clear the operation for the mod
 			operations[mod.id] = null;
+			progress[mod.id] = null;
 		}
 	}
 
@@ -166,10 +216,15 @@ export const useModStore = defineStore('mods', () => {
 		try {
 			// Add an operation for the mod being installed and request the installation from the backend
 			operations[mod.id] = 'uninstall';
+			errors[mod.id] = null;
 			await info(
 				`Requesting uninstallation of mod ${mod.name} v${version.semver}`,
 			);
-			await invoke('uninstall_mod', { rmod: mod });
+			await invoke('uninstall_mod', {
+				rmod: mod,
+				operationId: mod.id,
+				onProgress: progressChannel(mod),
+			});
 
 			// Update the mod's installed version and notify the user of the success
 			mod.installedVersion = null;
@@ -178,10 +233,49 @@ export const useModStore = defineStore('mods', () => {
 				`${mod.name} v${version.semver} was successfully uninstalled.`,
 			);
 		} catch (err) {
+			// Keep the failure around to explain it in detail
+			const failure = CommandError.from(err);
+			errors[mod.id] = failure;
+
 			// Notify the user of the failure
 			notify.error(
 				'Error uninstalling mod',
-				`Error uninstalling ${mod.name} v${version.semver}:\n${err}`,
+				`Error uninstalling ${mod.name} v${version.semver}:\n${failure}`,
+			);
+			throw failure;
+		} finally {
+			// Clear the operation for the mod
+			operations[mod.id] = null;
+			progress[mod.id] = null;
+		}
+	}
+
+	/**
+	 * Requests enabling or disabling of an installed mod from the backend and displays an alert when a result is received
+	 * @param {ResoluteMod|string} mod
+	 * @param {boolean} enable Whether to enable the mod (true) or disable it (false)
+	 */
+	async function setActive(mod, enable) {
+		mod = typeof mod === 'string' ? mods.value[mod] : mod;
+		const action = enable ? 'enable' : 'disable';
+
+		try {
+			// Add an operation for the mod being toggled and request the change from the backend
+			operations[mod.id] = action;
+			await info(`Requesting ${action} of mod ${mod.name}`);
+			await invoke(`${action}_mod`, { rmod: mod });
+
+			// Update the mod's active state and notify the user of the success
+			mod.active = enable;
+			notify.success(
+				enable ? 'Mod enabled' : 'Mod disabled',
+				`${mod.name} was successfully ${action}d.`,
+			);
+		} catch (err) {
+			// Notify the user of the failure
+			notify.error(
+				`Error ${enable ? 'enabling' : 'disabling'} mod`,
+				`Error ${enable ? 'enabling' : 'disabling'} ${mod.name}:\n${err}`,
 			);
 			throw err;
 		} finally {
@@ -207,12 +301,15 @@ export const useModStore = defineStore('mods', () => {
 		try {
 			// Add an operation for the mod being installed and request the installation from the backend
 			operations[mod.id] = 'update';
+			errors[mod.id] = null;
 			await info(
 				`Requesting replacement of mod ${mod.name} v${version.semver} with v${oldVersion.semver}`,
 			);
 			await invoke('replace_mod_version', {
 				rmod: mod,
 				version,
+				operationId: mod.id,
+				onProgress: progressChannel(mod),
 			});
 
 			// Update the mod's installed version and notify the user of the success
@@ -227,6 +324,10 @@ export const useModStore = defineStore('mods', () => {
 				);
 			}
 		} catch (err) {
+			// Keep the failure around to explain it in detail
+			const failure = CommandError.from(err);
+			errors[mod.id] = failure;
+
 			// Notify the user of the failure
 			if (alert) {
 				const action = semverLt(version.semver, oldVersion.semver)
@@ -234,16 +335,71 @@ export const useModStore = defineStore('mods', () => {
 					: 'updating';
 				notify.error(
 					`Error ${action} mod`,
-					`Error ${action} ${mod.name} v${oldVersion.semver} to v${version.semver}:\n${err}`,
+					`Error ${action} ${mod.name} v${oldVersion.semver} to v${version.semver}:\n${failure}`,
 				);
 			}
-			throw err;
+			throw failure;
 		} finally {
 			// Clear the operation for the mod
 			operations[mod.id] = null;
+			progress[mod.id] = null;
 		}
 	}
 
+	/**
+	 * Creates a channel for the backend to send progress updates for an operation on a mod over.
+	 * The latest update is kept in the progress state for the mod, along with the latest download progress of each of
+	 * the operation's artifacts and their combined progress (the total is null until the sizes of all of them are
+	 * known).
+	 * @param {ResoluteMod} mod
+	 * @returns {Channel}
+	 */
+	function progressChannel(mod) {
+		const downloads = [];
+		const channel = new Channel();
+		channel.onmessage = (event) => {
+			if (event.type === 'downloading') {
+				downloads[event.artifact - 1] = { ...event, verifying: false };
+			} else if (event.type === 'verifying' && downloads[event.artifact - 1]) {
+				downloads[event.artifact - 1].verifying = true;
+			}
+
+			const known = downloads.filter(Boolean);
+			const allSized =
+				known.length === (event.count ?? known.length) &&
+				known.every((download) => download.total !== null);
+			progress[mod.id] = {
+				phase: event.type,
+				event,
+				artifacts: known.map((download) => ({ ...download })),
+				downloaded: known.reduce(
+					(sum, download) => sum + download.downloaded,
+					0,
+				),
+				total: allSized
+					? known.reduce((sum, download) => sum + download.total, 0)
+					: null,
+			};
+		};
+		return channel;
+	}
+
+	/**
+	 * Requests cancellation of the install, update, or uninstall operation running for a mod.
+	 * The operation itself fails with a cancellation error once it has stopped.
+	 * @param {ResoluteMod|string} mod
+	 * @returns {boolean} Whether there was an operation to cancel
+	 */
+	async function cancel(mod) {
+		mod = typeof mod === 'string' ? mods.value[mod] : mod;
+		if (!operations[mod.id]) return false;
+
+		await info(
+			`Requesting cancellation of ${operations[mod.id]} of mod ${mod.name}`,
+		);
+		return invoke('cancel_operation', { id: mod.id });
+	}
+
 	/**
 	 * Requests discovery of installed mods from the backend and alerts the user to the result
 	 * @returns {Object} {@link ResoluteMod}s mapped by their ID
@@ -303,6 +459,35 @@ export const useModStore = defineStore('mods', () => {
 		return operations?.[mod] === 'uninstall';
 	}
 
+	/**
+	 * Gets the latest progress of the operation running for a mod
+	 * @param {ResoluteMod|string} mod
+	 * @returns {?Object} Phase, latest event, and per-artifact and combined download progress, or null if there is none
+	 */
+	function getProgress(mod) {
+		mod = typeof mod === 'object' ? mod.id : mod;
+		return progress?.[mod] ?? null;
+	}
+
+	/**
+	 * Gets the error that the most recent install, update, or uninstall of a mod failed with
+	 * @param {ResoluteMod|string} mod
+	 * @returns {?CommandError} The error, or null if the most recent operation succeeded or there hasn't been one
+	 */
+	function getError(mod) {
+		mod = typeof mod === 'object' ? mod.id : mod;
+		return errors?.[mod] ?? null;
+	}
+
+	/**
+	 * Forgets the error that the most recent operation on a mod failed with
+	 * @param {ResoluteMod|string} mod
+	 */
+	function clearError(mod) {
+		mod = typeof mod === 'object' ? mod.id : mod;
+		errors[mod] = null;
+	}
+
 	/**
 	 * Check whether a mod is being updated
 	 * @param {ResoluteMod|string} mod
@@ -315,6 +500,8 @@ export const useModStore = defineStore('mods', () => {
 	return {
 		mods,
 		operations,
+		progress,
+		errors,
 		load,
 		loadInstalled,
 		discover,
@@ -326,10 +513,15 @@ export const useModStore = defineStore('mods', () => {
 		install,
 		uninstall,
 		update,
+		cancel,
+		setActive,
 		isBusy,
 		isInstalling,
 		isUninstalling,
 		isUpdating,
+		getProgress,
+		getError,
+		clearError,
 	};
 });
 
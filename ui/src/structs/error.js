@@ -0,0 +1,41 @@
+/**
+ * Error returned by a backend command.
+ * Some kinds of failures come with details that explain them further.
+ */
+export class CommandError extends Error {
+	constructor(data) {
+		const structured = typeof data === 'object' && data !== null;
+		super(structured ? data.message : String(data));
+
+		/**
+		 * Kind of failure
+		 * @type {'unresolvable'|'conflicts'|'other'}
+		 */
+		this.kind = structured ? data.kind : 'other';
+
+		/**
+		 * Derivation tree explaining why dependency resolution failed (only for unresolvable errors)
+		 * @type {?Object}
+		 */
+		this.explanation = structured ? (data.explanation ?? null) : null;
+
+		/**
+		 * Conflicts between mods that the changes would introduce (only for conflicts errors)
+		 * @type {?Object[]}
+		 */
+		this.conflicts = structured ? (data.conflicts ?? null) : null;
+	}
+
+	/**
+	 * Wraps an error thrown by invoking a command, unless it's already wrapped
+	 * @param {*} err
+	 * @returns {CommandError}
+	 */
+	static from(err) {
+		return err instanceof CommandError ? err : new CommandError(err);
+	}
+
+	toString() {
+		return this.message;
+	}
+}
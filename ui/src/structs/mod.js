@@ -1,4 +1,11 @@
-import { compare as semverCompare, lt as semverLt } from 'semver';
+import {
+	compare as semverCompare,
+	lt as semverLt,
+	prerelease as semverPrerelease,
+	satisfies as semverSatisfies,
+} from 'semver';
+
+import useSettings from '../composables/settings';
 
 /**
  * Container for all data about a Resonite mod
@@ -54,7 +61,7 @@ export class ResoluteMod {
 
 		/**
 		 * Meta flags
-		 * @type {?Array<'deprecated'|'plugin'|'final'>}
+		 * @type {?Array<'deprecated'|'vulnerable'|'plugin'|'final'|string>}
 		 */
 		this.flags = data.flags;
 
@@ -82,11 +89,23 @@ export class ResoluteMod {
 			? this.versions[data.installedVersion]
 			: null;
 
+		/**
+		 * Version requirement the mod is pinned to
+		 * @type {?string}
+		 */
+		this.pin = data.pin;
+
 		/**
 		 * Whether the mod is enabled
 		 * @type {bool}
 		 */
 		this.active = data.active;
+
+		/**
+		 * Whether the mod supports the platform of the configured Resonite installation
+		 * @type {boolean}
+		 */
+		this.supported = data.supported ?? true;
 	}
 
 	/**
@@ -106,19 +125,71 @@ export class ResoluteMod {
 	}
 
 	/**
-	 * Latest available version
+	 * Whether prerelease versions of this mod are offered (based on the allowPrereleases and prereleaseMods settings)
+	 * @type {boolean}
+	 */
+	get allowsPrereleases() {
+		const settings = useSettings();
+		return (
+			settings.current.allowPrereleases ||
+			(settings.current.prereleaseMods?.includes?.(this.id) ?? false)
+		);
+	}
+
+	/**
+	 * Whether this mod has a known security vulnerability (the {@link flags} property has a vulnerability flag in it)
+	 * @type {boolean}
+	 */
+	get isVulnerable() {
+		return (
+			this.flags?.some?.(
+				(flag) => flag === 'vulnerable' || flag.startsWith('vulnerability'),
+			) ?? false
+		);
+	}
+
+	/**
+	 * Latest available version in the mod's release channel, falling back to the newest version of any kind
 	 * @type {ModVersion}
 	 */
 	get latestVersion() {
-		return Object.values(this.versions)[0];
+		const versions = Object.values(this.versions);
+		return (
+			versions.find(
+				(version) =>
+					!version.isUnrecognized &&
+					(this.allowsPrereleases || !version.isPrerelease),
+			) ?? versions[0]
+		);
+	}
+
+	/**
+	 * Latest available version in the mod's release channel that the mod's pin allows
+	 * @type {?ModVersion}
+	 */
+	get latestAllowedVersion() {
+		return (
+			Object.values(this.versions).find(
+				(version) =>
+					!version.isUnrecognized &&
+					(this.allowsPrereleases || !version.isPrerelease) &&
+					(!this.pin ||
+						semverSatisfies(version.semver, this.pin.replaceAll(',', ' '), {
+							includePrerelease: true,
+						})),
+			) ?? null
+		);
 	}
 
 	/**
-	 * Whether an update is available (the installed version is older than the latest version)
+	 * Whether an update is available (the installed version is older than the latest version the pin allows)
 	 */
 	get hasUpdate() {
-		if (!this.installedVersion) return false;
-		return semverLt(this.installedVersion.semver, this.latestVersion.semver);
+		if (!this.installedVersion || !this.latestAllowedVersion) return false;
+		return semverLt(
+			this.installedVersion.semver,
+			this.latestAllowedVersion.semver,
+		);
 	}
 
 	/**
@@ -226,6 +297,14 @@ export class ModVersion {
 		return this.semver === '0.0.0-unknown';
 	}
 
+	/**
+	 * Whether this version is a prerelease
+	 * @type {boolean}
+	 */
+	get isPrerelease() {
+		return !this.isUnrecognized && semverPrerelease(this.semver) !== null;
+	}
+
 	/**
 	 * Text label for the version
 	 * @type {string}
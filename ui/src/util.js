@@ -72,6 +72,21 @@ export function wrappableCamelCase(text) {
 	return pure.replace(/([a-z])([A-Z])/g, '$1<wbr />$2');
 }
 
+/**
+ * Formats a number of bytes as a human-readable size
+ * @param {number} bytes
+ * @returns {string}
+ */
+export function formatBytes(bytes) {
+	const units = ['B', 'KiB', 'MiB', 'GiB'];
+	let unit = 0;
+	while (bytes >= 1024 && unit < units.length - 1) {
+		bytes /= 1024;
+		unit++;
+	}
+	return `${unit === 0 ? bytes : bytes.toFixed(1)} ${units[unit]}`;
+}
+
 /**
  * Disables the context menu for a node
  * @param {Node} [node=document]